@@ -35,6 +35,14 @@ pub struct CliArgs {
     /// Uninstall mode (used internally when relaunching for uninstall)
     #[arg(long)]
     pub uninstall: bool,
+
+    /// Uninstall using a manifest written at install time, without the original configuration
+    #[arg(long, value_name = "MANIFEST", requires = "uninstall")]
+    pub from: Option<String>,
+
+    /// Replace this installer with its newest release and exit
+    #[arg(long)]
+    pub update_installer: bool,
 }
 
 impl CliArgs {
@@ -85,6 +93,14 @@ impl CliArgs {
             args.push("--uninstall".to_string());
         }
 
+        if let Some(manifest) = &self.from {
+            if manifest.contains(' ') {
+                args.push(format!("--from \"{}\"", manifest.replace("\"", "\\\"")));
+            } else {
+                args.push(format!("--from {}", manifest));
+            }
+        }
+
         args.join(" ")
     }
 
@@ -21,7 +21,10 @@ fn get_font_family_name(font_data: &[u8]) -> Option<String> {
 /// Loads and registers embedded fonts
 ///
 /// Fonts are imported directly in the .slint file, so this function just logs
-/// the detected font family names for debugging purposes.
+/// the detected font family names for debugging purposes. `slint-build` embeds
+/// imported fonts into the binary and Slint registers them with its own font
+/// database when the UI is created, on Linux and macOS as well as Windows, so
+/// nothing needs to be written to disk or registered with fontconfig.
 pub fn load_embedded_fonts() -> Result<(), Box<dyn std::error::Error>> {
     info!("Minecraft fonts are imported in UI definition (app.slint)");
 
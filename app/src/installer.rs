@@ -1,14 +1,17 @@
 use anyhow::Result;
-use log::{debug, error, info};
-use oim::{InstallationConfig, InstallationManager, ReleaseChannel, State, StateProgress};
-use std::path::PathBuf;
+use log::{debug, error, info, warn};
+use oim::{CancellationToken, InstallError, InstallObserver, InstallationConfig, InstallationManager, InvalidInstallPath, ReleaseChannel, State, StateProgress};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
 
 pub const GITHUB_REPO: &str = "Obsidian-Minecraft-Server-Portal/obsidian-server-panel";
 pub const SERVICE_NAME: &str = "ObsidianServerPanel";
 const SERVICE_DISPLAY_NAME: &str = "Obsidian Minecraft Server Panel";
 const SERVICE_DESCRIPTION: &str = "Self-hosted Minecraft server management panel";
 const REGISTRY_PATH: &str = r"SOFTWARE\ObsidianMinecraftServerPanel";
+/// Repository whose releases publish this installer
+const INSTALLER_REPO: &str = "Obsidian-Minecraft-Server-Portal/obsidian-installation-manager";
 
 /// Installer state for managing installation progress
 pub struct InstallerState {
@@ -19,6 +22,8 @@ pub struct InstallerState {
     pub message: String,
     pub has_error: bool,
     pub error_message: String,
+    /// Cancels the running installation when triggered from the UI
+    pub cancel: CancellationToken,
 }
 
 impl Default for InstallerState {
@@ -31,10 +36,24 @@ impl Default for InstallerState {
             message: String::new(),
             has_error: false,
             error_message: String::new(),
+            cancel: CancellationToken::new(),
         }
     }
 }
 
+/// Forwards the library's status messages to the log
+struct LogObserver;
+
+impl InstallObserver for LogObserver {
+    fn on_message(&self, message: &str) {
+        info!("{}", message);
+    }
+
+    fn on_warning(&self, message: &str) {
+        warn!("{}", message);
+    }
+}
+
 /// Performs the installation using the OIM library
 ///
 /// # Arguments
@@ -67,13 +86,15 @@ pub async fn perform_installation(
     .service_display_name(SERVICE_DISPLAY_NAME.to_string())
     .service_description(SERVICE_DESCRIPTION.to_string())
     .working_directory(PathBuf::from(&install_path))
-    .registry_path(r"SOFTWARE\ObsidianMinecraftServerPanel".to_string());
+    .registry_path(r"SOFTWARE\ObsidianMinecraftServerPanel".to_string())
+    .create_shortcuts(true);
 
     // If not installing as service, we'll just download and extract
     // The service installation is handled separately by the manager
 
     // Create installation manager (returns InstallationManager, not Result)
     let mut manager = InstallationManager::new(config);
+    manager.set_observer(Arc::new(LogObserver));
 
     // Subscribe to progress updates
     let mut progress_rx = manager.subscribe();
@@ -83,19 +104,25 @@ pub async fn perform_installation(
 
     // Spawn a task to listen for progress updates
     tokio::spawn(async move {
-        while let Ok(progress) = progress_rx.recv().await {
-            update_progress_state(&state_clone, &progress);
+        loop {
+            match progress_rx.recv().await {
+                Ok(progress) => update_progress_state(&state_clone, &progress),
+                // Skipped updates are superseded by the ones still queued
+                Err(RecvError::Lagged(skipped)) => debug!("Progress listener skipped {} updates", skipped),
+                Err(RecvError::Closed) => break,
+            }
         }
     });
 
     // Perform installation
-    {
+    let cancel = {
         let mut s = state.lock().unwrap();
         s.status = "Fetching latest release...".to_string();
         s.progress = 0.1;
-    }
+        s.cancel.clone()
+    };
 
-    match manager.install(channel).await {
+    match manager.install(channel, &cancel).await {
         Ok(_) => {
             info!("Installation completed successfully");
             let mut s = state.lock().unwrap();
@@ -111,6 +138,18 @@ pub async fn perform_installation(
             if install_as_service {
                 s.message.push_str("\nThe service has been installed and started.");
             }
+
+            if manager.reboot_required() {
+                s.message.push_str("\nPlease restart your computer to finish replacing files that were in use.");
+            }
+        }
+        Err(InstallError::Cancelled(_)) => {
+            info!("Installation cancelled");
+            let mut s = state.lock().unwrap();
+            s.status = "Installation cancelled".to_string();
+            s.completed = true;
+            s.success = false;
+            s.message = "The installation was cancelled.".to_string();
         }
         Err(e) => {
             error!("Installation failed: {}", e);
@@ -121,6 +160,10 @@ pub async fn perform_installation(
             s.has_error = true;
             s.error_message = format!("{}", e);
             s.message = format!("Installation failed: {}", e);
+
+            if e.is_retryable() {
+                s.message.push_str("\nCheck your internet connection and try again.");
+            }
         }
     }
 
@@ -163,21 +206,43 @@ pub fn check_existing_installation() -> Option<ExistingInstallation> {
     }
 }
 
-/// Performs a repair operation (reinstall files without deleting existing ones)
+/// Checks a folder chosen for the installation, returning the problem to show under the path field
+///
+/// Until the installer is elevated most system locations aren't writable, so that check is
+/// left to the elevated install.
+pub fn install_path_problem(path: &Path) -> Option<String> {
+    let config = InstallationConfig::new(path.to_path_buf(), GITHUB_REPO.to_string(), SERVICE_NAME.to_string());
+    let error = config.validate().err()?;
+    let invalid = error.downcast_ref::<InvalidInstallPath>()?;
+    if !crate::elevation::is_elevated() && invalid.reason.contains("not writable") {
+        return None;
+    }
+    Some(invalid.to_string())
+}
+
+/// Replaces this installer with its newest release, returning the new version if there was one
+///
+/// The running process keeps using the old binary; the update applies from the next launch.
+pub async fn update_installer() -> Result<Option<String>> {
+    let current_exe = std::env::current_exe()?;
+    let config = InstallationConfig::new(
+        current_exe.parent().map(PathBuf::from).unwrap_or_default(),
+        INSTALLER_REPO.to_string(),
+        "obsidian-installer".to_string(),
+    );
+    let mut manager = InstallationManager::new(config);
+    manager.set_observer(Arc::new(LogObserver));
+
+    let version = manager.self_update(&current_exe).await?;
+    Ok(version.map(|version| version.to_string()))
+}
+
+/// Performs a repair operation (reinstall the installed version without deleting existing files)
 pub async fn perform_repair(
     install_path: String,
-    release_channel: i32,
     state: Arc<Mutex<InstallerState>>,
 ) -> Result<()> {
-    info!("Starting repair to: {} (channel: {})", install_path, release_channel);
-
-    // Convert channel index to ReleaseChannel enum
-    let channel = match release_channel {
-        0 => ReleaseChannel::Release,
-        1 => ReleaseChannel::Beta,
-        2 => ReleaseChannel::Alpha,
-        _ => ReleaseChannel::Release,
-    };
+    info!("Starting repair of: {}", install_path);
 
     // Create installation configuration
     let config = InstallationConfig::new(
@@ -188,10 +253,12 @@ pub async fn perform_repair(
     .service_display_name(SERVICE_DISPLAY_NAME.to_string())
     .service_description(SERVICE_DESCRIPTION.to_string())
     .working_directory(PathBuf::from(&install_path))
-    .registry_path(REGISTRY_PATH.to_string());
+    .registry_path(REGISTRY_PATH.to_string())
+    .create_shortcuts(true);
 
     // Create installation manager
     let mut manager = InstallationManager::new(config);
+    manager.set_observer(Arc::new(LogObserver));
 
     // Subscribe to progress updates
     let mut progress_rx = manager.subscribe();
@@ -201,8 +268,13 @@ pub async fn perform_repair(
 
     // Spawn a task to listen for progress updates
     tokio::spawn(async move {
-        while let Ok(progress) = progress_rx.recv().await {
-            update_progress_state(&state_clone, &progress);
+        loop {
+            match progress_rx.recv().await {
+                Ok(progress) => update_progress_state(&state_clone, &progress),
+                // Skipped updates are superseded by the ones still queued
+                Err(RecvError::Lagged(skipped)) => debug!("Progress listener skipped {} updates", skipped),
+                Err(RecvError::Closed) => break,
+            }
         }
     });
 
@@ -213,7 +285,7 @@ pub async fn perform_repair(
         s.progress = 0.1;
     }
 
-    match manager.repair(channel).await {
+    match manager.repair().await {
         Ok(_) => {
             info!("Repair completed successfully");
             let mut s = state.lock().unwrap();
@@ -260,6 +332,7 @@ pub async fn perform_uninstall(state: Arc<Mutex<InstallerState>>) -> Result<()>
     .registry_path(REGISTRY_PATH.to_string());
 
     let mut manager = InstallationManager::new(config);
+    manager.set_observer(Arc::new(LogObserver));
 
     // Update state
     {
@@ -277,6 +350,10 @@ pub async fn perform_uninstall(state: Arc<Mutex<InstallerState>>) -> Result<()>
             s.completed = true;
             s.success = true;
             s.message = "Obsidian Server Panel has been successfully uninstalled.".to_string();
+
+            if manager.reboot_required() {
+                s.message.push_str("\nPlease restart your computer to finish removing files that were in use.");
+            }
         }
         Err(e) => {
             error!("Uninstall failed: {}", e);
@@ -299,7 +376,17 @@ fn update_progress_state(state: &Arc<Mutex<InstallerState>>, progress: &StatePro
 
     match progress.state {
         State::Downloading => {
-            s.status = "Downloading application files...".to_string();
+            s.status = match (progress.bytes_done, progress.bytes_total) {
+                (Some(done), Some(total)) => format!("Downloading application files... {} / {}", format_bytes(done as f64), format_bytes(total as f64)),
+                (Some(done), None) => format!("Downloading application files... {}", format_bytes(done as f64)),
+                _ => "Downloading application files...".to_string(),
+            };
+            if let Some(rate) = progress.bytes_per_sec {
+                s.status.push_str(&format!(" ({}/s)", format_bytes(rate)));
+            }
+            if let Some(eta) = progress.eta {
+                s.status.push_str(&format!(", {}", format_eta(eta)));
+            }
             s.progress = 0.2 + (progress.progress * 0.4); // 20-60%
             debug!("Download progress: {:.2}%", progress.progress * 100.0);
         }
@@ -310,13 +397,58 @@ fn update_progress_state(state: &Arc<Mutex<InstallerState>>, progress: &StatePro
         }
         State::Installing => {
             s.status = "Installing service...".to_string();
-            s.progress = 0.8 + (progress.progress * 0.15); // 80-95%
+            s.progress = 0.8 + (progress.progress * 0.1); // 80-90%
             debug!("Installing progress: {:.2}%", progress.progress * 100.0);
         }
+        State::Finalizing => {
+            s.status = "Starting service...".to_string();
+            s.progress = 0.9 + (progress.progress * 0.05); // 90-95%
+            debug!("Finalizing progress: {:.2}%", progress.progress * 100.0);
+        }
         State::Updating => {
             s.status = "Updating...".to_string();
             s.progress = 0.5 + (progress.progress * 0.5);
             debug!("Updating progress: {:.2}%", progress.progress * 100.0);
         }
+        State::Verifying => {
+            s.status = "Verifying download...".to_string();
+            debug!("Verifying progress: {:.2}%", progress.progress * 100.0);
+        }
+        State::Complete => {
+            s.status = "Finishing up...".to_string();
+            s.progress = 1.0;
+        }
+        State::Idle => {}
+    }
+}
+
+/// Formats an estimated time remaining for display, e.g. "about 3 minutes left"
+fn format_eta(eta: std::time::Duration) -> String {
+    let secs = eta.as_secs();
+    if secs < 5 {
+        "almost done".to_string()
+    } else if secs < 60 {
+        format!("about {} seconds left", secs)
+    } else if secs < 90 * 60 {
+        let minutes = (secs + 30) / 60;
+        format!("about {} minute{} left", minutes, if minutes == 1 { "" } else { "s" })
+    } else {
+        format!("about {:.1} hours left", secs as f64 / 3600.0)
+    }
+}
+
+/// Formats a byte count for display, e.g. "12.3 MB"
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
     }
 }
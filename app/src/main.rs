@@ -17,7 +17,6 @@ use cli::CliArgs;
 use installer::{InstallerState, perform_installation};
 use log::*;
 use slint::ComponentHandle;
-use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -37,6 +36,19 @@ async fn main() -> Result<()> {
     info!("Starting Obsidian Installer");
     debug!("Parsed CLI args: {:?}", args);
 
+    // Manifest-driven uninstall never needs the UI
+    if args.from.is_some() {
+        return run_manifest_uninstall(args);
+    }
+
+    if args.update_installer {
+        match installer::update_installer().await? {
+            Some(version) => info!("Installer updated to {}; it will be used from the next launch", version),
+            None => info!("The installer is already up to date"),
+        }
+        return Ok(());
+    }
+
     // If headless mode is requested, run without UI
     if args.headless {
         return run_headless(args).await;
@@ -98,12 +110,34 @@ async fn run_headless(args: CliArgs) -> Result<()> {
     }
 }
 
+/// Uninstall from a manifest written by `write_uninstaller` (no UI)
+fn run_manifest_uninstall(args: CliArgs) -> Result<()> {
+    let manifest = PathBuf::from(args.from.as_deref().unwrap_or_default());
+    info!("Uninstalling from manifest: {}", manifest.display());
+
+    if !elevation::is_elevated() {
+        info!("Requesting elevation for manifest uninstall...");
+        elevation::request_elevation_with_args(&args)?;
+        // The elevated instance performs the uninstall
+        return Ok(());
+    }
+
+    let reboot_required = oim::InstallationManager::uninstall_from_manifest(&manifest)?;
+    info!("Uninstall completed successfully!");
+
+    if reboot_required {
+        info!("Please restart your computer to finish removing files that were in use.");
+    }
+
+    Ok(())
+}
+
 /// Run the installer with GUI
 async fn run_gui(args: CliArgs) -> Result<()> {
     info!("Running in GUI mode");
 
-    // Load embedded fonts (WOFF2 fonts need conversion to TTF for Windows GDI)
-    // See app/res/fonts/README.md for conversion instructions
+    // The fonts are embedded through app.slint's imports on every platform; this checks them
+    // (WOFF2 fonts need conversion to TTF, see app/res/fonts/README.md)
     if let Err(e) = fonts::load_embedded_fonts() {
         warn!(
             "Failed to load embedded fonts: {}. Using system fallback.",
@@ -188,6 +222,7 @@ async fn run_gui(args: CliArgs) -> Result<()> {
             && let Some(path) = dialogs::browse_folder()
         {
             ui.set_install_path(path.to_string_lossy().to_string().into());
+            ui.set_install_path_error(installer::install_path_problem(&path).unwrap_or_default().into());
         }
     });
 
@@ -280,6 +315,7 @@ async fn run_gui(args: CliArgs) -> Result<()> {
                     accept_tos: true, // TOS already accepted in UI
                     page: Some(4), // Go directly to Installing page
                     uninstall: false,
+                    from: None,
                 };
                 
                 debug!("Elevation args: {:?}", elevation_args);
@@ -433,6 +469,7 @@ async fn run_gui(args: CliArgs) -> Result<()> {
                     accept_tos: true,
                     page: Some(4), // Go directly to Installing page
                     uninstall: false,
+                    from: None,
                 };
 
                 debug!("Requesting elevation for repair: {:?}", elevation_args);
@@ -466,7 +503,6 @@ async fn run_gui(args: CliArgs) -> Result<()> {
             ui.set_current_page(Page::Installing);
 
             let install_path = ui.get_install_path().to_string();
-            let release_channel = ui.get_release_channel();
 
             // Create shared state
             let state = Arc::new(Mutex::new(installer::InstallerState::default()));
@@ -475,7 +511,7 @@ async fn run_gui(args: CliArgs) -> Result<()> {
 
             // Spawn repair task
             tokio::spawn(async move {
-                if let Err(e) = installer::perform_repair(install_path, release_channel, state_clone.clone()).await {
+                if let Err(e) = installer::perform_repair(install_path, state_clone.clone()).await {
                     error!("Repair error: {}", e);
                     let mut s = state_clone.lock().unwrap();
                     s.success = false;
@@ -562,6 +598,7 @@ async fn run_gui(args: CliArgs) -> Result<()> {
                     accept_tos: true,
                     page: Some(4), // Go directly to Installing page
                     uninstall: true, // Flag to indicate uninstall mode
+                    from: None,
                 };
 
                 debug!("Requesting elevation for uninstall: {:?}", elevation_args);
@@ -721,37 +758,19 @@ async fn load_tos_content() -> Vec<TextSegment> {
 
 /// Launches the installed application and exits the installer
 fn launch_application(install_path: &str) {
-    let install_dir = PathBuf::from(install_path);
-
-    // Try to find and launch the executable
-    if let Ok(entries) = fs::read_dir(&install_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("exe") {
-                info!("Launching application: {}", path.display());
-
-                #[cfg(target_os = "windows")]
-                {
-                    use std::process::Command;
-                    match Command::new("explorer.exe").arg(&path).current_dir(&install_dir).spawn() {
-                        Ok(_) => {
-                            info!("Application launched successfully, exiting installer");
-                            std::process::exit(0);
-                        }
-                        Err(e) => {
-                            error!("Failed to launch application: {}", e);
-                        }
-                    }
-                }
-
-                #[cfg(not(target_os = "windows"))]
-                {
-                    // Non-Windows platforms
-                    info!("Launch not implemented for this platform");
-                }
-
-                break;
-            }
+    let config = oim::InstallationConfig::new(
+        PathBuf::from(install_path),
+        installer::GITHUB_REPO.to_string(),
+        installer::SERVICE_NAME.to_string(),
+    );
+
+    match oim::InstallationManager::new(config).launch() {
+        Ok(child) => {
+            info!("Application launched (pid {}), exiting installer", child.id());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            error!("Failed to launch application: {}", e);
         }
     }
 }
@@ -1,4 +1,4 @@
-use slint::{LogicalPosition, Window};
+use slint::Window;
 
 /// Centers the window on the screen
 ///
@@ -8,6 +8,8 @@ use slint::{LogicalPosition, Window};
 /// * `height` - Window height in pixels
 #[cfg(target_os = "windows")]
 pub fn center_window(window: &Window, width: f32, height: f32) {
+    use slint::LogicalPosition;
+
     // Get screen dimensions using Windows API
     let (screen_width, screen_height) = unsafe {
         use windows::Win32::UI::WindowsAndMessaging::{
@@ -115,14 +117,53 @@ pub fn apply_rounded_corners(window: &Window) {
     }
 }
 
-/// Centers the window on the screen (non-Windows platforms)
+/// Sizes the window and centers it on its monitor (non-Windows platforms)
+///
+/// The winit window only exists once the event loop runs, so it is centered on its first
+/// event. winit can't read the cursor position outside the window, so instead of the monitor
+/// under the cursor this uses the monitor the window manager opened the window on, which
+/// follows the cursor or the focused monitor on most desktops, falling back to the primary
+/// monitor. Wayland compositors don't let clients position windows, so there only the size
+/// is applied.
 ///
-/// This is a placeholder implementation for non-Windows platforms.
-/// On Linux/macOS, window positioning may be handled by the window manager.
+/// # Arguments
+/// * `window` - The Slint window to center
+/// * `width` - Window width in logical pixels
+/// * `height` - Window height in logical pixels
 #[cfg(not(target_os = "windows"))]
-pub fn center_window(_window: &Window, _width: f32, _height: f32) {
-    // On Linux/macOS, window positioning is typically managed by the window manager
-    // This is a no-op for now
+pub fn center_window(window: &Window, width: f32, height: f32) {
+    use slint::winit_030::{EventResult, WinitWindowAccessor};
+    use slint::{LogicalSize, PhysicalPosition};
+    use std::cell::Cell;
+
+    window.set_size(LogicalSize::new(width, height));
+
+    let centered = Cell::new(false);
+    window.on_winit_window_event(move |window, _event| {
+        if centered.get() {
+            return EventResult::Propagate;
+        }
+
+        let position = window.with_winit_window(|winit_window| {
+            let monitor = winit_window
+                .current_monitor()
+                .or_else(|| winit_window.primary_monitor())
+                .or_else(|| winit_window.available_monitors().next())?;
+            let (origin, area) = (monitor.position(), monitor.size());
+            let outer = winit_window.outer_size();
+
+            // Keep the title bar on screen when the window is larger than the monitor
+            let x = origin.x + (area.width as i32 - outer.width as i32).max(0) / 2;
+            let y = origin.y + (area.height as i32 - outer.height as i32).max(0) / 2;
+            Some(PhysicalPosition::new(x, y))
+        });
+
+        if let Some(Some(position)) = position {
+            window.set_position(position);
+            centered.set(true);
+        }
+        EventResult::Propagate
+    });
 }
 
 /// Applies rounded corners to the window (non-Windows platforms)
@@ -1,7 +1,7 @@
-/// Advanced configuration example
-///
-/// This example demonstrates using the builder pattern to create
-/// a fully customized installation configuration.
+//! Advanced configuration example
+//!
+//! This example demonstrates using the builder pattern to create
+//! a fully customized installation configuration.
 
 use oim::{InstallationManager, InstallationConfig};
 use std::path::PathBuf;
@@ -43,7 +43,7 @@ async fn main() -> anyhow::Result<()> {
     println!("  Registry Path: {}", manager.config().get_registry_path());
 
     #[cfg(target_os = "linux")]
-    println!("  Version File Dir: {}", manager.config().get_version_file_dir());
+    println!("  Version File Dir: {}", manager.config().get_version_file_dir().display());
 
     println!("\nThis configuration provides:");
     println!("  - Custom service display name and description");
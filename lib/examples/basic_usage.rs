@@ -1,9 +1,9 @@
-/// Basic usage example demonstrating simple installation
-///
-/// This example shows how to use the installation manager with default settings
-/// to install an application from a GitHub repository.
+//! Basic usage example demonstrating simple installation
+//!
+//! This example shows how to use the installation manager with default settings
+//! to install an application from a GitHub repository.
 
-use oim::{InstallationManager, InstallationConfig, ReleaseChannel};
+use oim::{InstallationManager, InstallationConfig};
 use std::path::PathBuf;
 
 #[tokio::main]
@@ -39,17 +39,17 @@ async fn main() -> anyhow::Result<()> {
     }
 
     println!("\nTo install:");
-    println!("  manager.install(ReleaseChannel::Release)?;  // Install stable releases only");
-    println!("  manager.install(ReleaseChannel::Beta)?;     // Install beta/RC releases");
-    println!("  manager.install(ReleaseChannel::Alpha)?;    // Install all pre-releases");
+    println!("  manager.install(ReleaseChannel::Release, &CancellationToken::new()).await?;  // Install stable releases only");
+    println!("  manager.install(ReleaseChannel::Beta, &CancellationToken::new()).await?;     // Install beta/RC releases");
+    println!("  manager.install(ReleaseChannel::Alpha, &CancellationToken::new()).await?;    // Install all pre-releases");
 
     println!("\nTo check for updates:");
-    println!("  if manager.check_for_updates(ReleaseChannel::Release)? {{");
-    println!("      manager.update(ReleaseChannel::Release)?;");
+    println!("  if manager.check_for_updates(ReleaseChannel::Release).await?.update_available() {{");
+    println!("      manager.update(ReleaseChannel::Release, &CancellationToken::new()).await?;");
     println!("  }}");
 
     println!("\nTo uninstall:");
-    println!("  manager.uninstall()?;");
+    println!("  manager.uninstall().await?;");
 
     Ok(())
 }
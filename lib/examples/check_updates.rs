@@ -1,10 +1,11 @@
-/// Check for updates example
-///
-/// This example demonstrates how to check if updates are available
-/// for an installed application.
+//! Check for updates example
+//!
+//! This example demonstrates how to check if updates are available
+//! for an installed application.
 
-use oim::{InstallationManager, InstallationConfig, ReleaseChannel};
+use oim::{ConsoleObserver, InstallationManager, InstallationConfig, ReleaseChannel};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -20,12 +21,15 @@ async fn main() -> anyhow::Result<()> {
 
     let mut manager = InstallationManager::new(config);
 
+    // Print retries and other status messages from the library
+    manager.set_observer(Arc::new(ConsoleObserver));
+
     println!("Checking for updates on Release channel...\n");
 
     // Check for updates on the Release channel (stable only)
     match manager.check_for_updates(ReleaseChannel::Release).await {
-        Ok(has_update) => {
-            if has_update {
+        Ok(status) => {
+            if status.update_available() {
                 println!("✓ Update available!");
                 println!();
 
@@ -39,7 +43,7 @@ async fn main() -> anyhow::Result<()> {
 
                 println!();
                 println!("To install the update, run:");
-                println!("  manager.update(ReleaseChannel::Release)?;");
+                println!("  manager.update(ReleaseChannel::Release, &CancellationToken::new()).await?;");
             } else {
                 println!("✓ Already up to date!");
 
@@ -60,8 +64,8 @@ async fn main() -> anyhow::Result<()> {
     }
 
     println!("\nTo check other channels:");
-    println!("  manager.check_for_updates(ReleaseChannel::Beta)?;   // Include beta/RC");
-    println!("  manager.check_for_updates(ReleaseChannel::Alpha)?;  // Include all pre-releases");
+    println!("  manager.check_for_updates(ReleaseChannel::Beta).await?;   // Include beta/RC");
+    println!("  manager.check_for_updates(ReleaseChannel::Alpha).await?;  // Include all pre-releases");
 
     Ok(())
 }
@@ -1,15 +1,15 @@
-/// Full workflow example
-///
-/// This example demonstrates a complete workflow:
-/// 1. Create configuration
-/// 2. Fetch releases
-/// 3. Check for updates
-/// 4. Install or update
-///
-/// Note: This example won't actually install anything without proper permissions
-/// and a valid GitHub repository with releases.
+//! Full workflow example
+//!
+//! This example demonstrates a complete workflow:
+//! 1. Create configuration
+//! 2. Fetch releases
+//! 3. Check for updates
+//! 4. Install or update
+//!
+//! Note: This example won't actually install anything without proper permissions
+//! and a valid GitHub repository with releases.
 
-use oim::{InstallationManager, InstallationConfig, Architecture, ReleaseChannel};
+use oim::{InstallationManager, InstallationConfig, Architecture};
 use std::path::PathBuf;
 
 #[tokio::main]
@@ -36,7 +36,7 @@ async fn main() -> anyhow::Result<()> {
 
     println!("Step 1: Fetch available releases from GitHub");
     println!("----------------------------------------------");
-    match manager.fetch_releases().await {
+    match manager.fetch_releases(false).await {
         Ok(releases) => {
             println!("Found {} releases", releases.len());
             if let Some(latest) = releases.first() {
@@ -76,9 +76,9 @@ async fn main() -> anyhow::Result<()> {
     println!("-------------------------");
     println!("(Skipped in example - requires actual installation)");
     // Uncomment to actually check:
-    // match manager.check_for_updates(ReleaseChannel::Release) {
-    //     Ok(has_update) => {
-    //         if has_update {
+    // match manager.check_for_updates(ReleaseChannel::Release).await {
+    //     Ok(status) => {
+    //         if status.update_available() {
     //             println!("Update available!");
     //             if let Some(latest) = manager.latest_version() {
     //                 println!("Latest version: {}", latest);
@@ -93,17 +93,17 @@ async fn main() -> anyhow::Result<()> {
     println!("\nStep 4: Installation workflow");
     println!("-----------------------------");
     println!("To install (requires elevated privileges):");
-    println!("  manager.install(ReleaseChannel::Release)?;  // Stable only");
-    println!("  manager.install(ReleaseChannel::Beta)?;     // Beta/RC");
-    println!("  manager.install(ReleaseChannel::Alpha)?;    // All pre-releases");
+    println!("  manager.install(ReleaseChannel::Release, &CancellationToken::new()).await?;  // Stable only");
+    println!("  manager.install(ReleaseChannel::Beta, &CancellationToken::new()).await?;     // Beta/RC");
+    println!("  manager.install(ReleaseChannel::Alpha, &CancellationToken::new()).await?;    // All pre-releases");
     println!();
     println!("To update an existing installation:");
-    println!("  if manager.check_for_updates(ReleaseChannel::Release)? {{");
-    println!("      manager.update(ReleaseChannel::Release)?;");
+    println!("  if manager.check_for_updates(ReleaseChannel::Release).await?.update_available() {{");
+    println!("      manager.update(ReleaseChannel::Release, &CancellationToken::new()).await?;");
     println!("  }}");
     println!();
     println!("To uninstall:");
-    println!("  manager.uninstall()?;");
+    println!("  manager.uninstall().await?;");
 
     println!("\nWorkflow Summary");
     println!("----------------");
@@ -0,0 +1,611 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::extract_state::ExtractState;
+
+/// Archive formats the installer knows how to extract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    /// Gzip-compressed tarball (.tar.gz, .tgz)
+    TarGz,
+    /// XZ-compressed tarball (.tar.xz, .txz)
+    TarXz,
+    /// Bzip2-compressed tarball (.tar.bz2, .tbz2, .tbz)
+    TarBz2,
+    /// Zip archive (.zip)
+    Zip,
+    /// A single gzip-compressed file (.gz, but not .tar.gz)
+    Gzip,
+    /// A bare executable (.exe, or no extension at all)
+    Binary,
+}
+
+impl ArchiveFormat {
+    /// Every format compiled into this build
+    pub(crate) const ALL: &'static [ArchiveFormat] = &[
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::Zip,
+        ArchiveFormat::Gzip,
+        ArchiveFormat::Binary,
+    ];
+
+    /// File name suffixes recognized for this format
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ArchiveFormat::TarGz => &[".tar.gz", ".tgz"],
+            ArchiveFormat::TarXz => &[".tar.xz", ".txz"],
+            ArchiveFormat::TarBz2 => &[".tar.bz2", ".tbz2", ".tbz"],
+            ArchiveFormat::Zip => &[".zip"],
+            ArchiveFormat::Gzip => &[".gz"],
+            ArchiveFormat::Binary => &[".exe"],
+        }
+    }
+
+    /// Detect the format of an asset or archive from its file name (case-insensitive)
+    ///
+    /// A name without any extension is taken to be a bare binary.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extensions().iter().any(|ext| name.ends_with(ext)))
+            .or_else(|| Path::new(&name).extension().is_none().then_some(ArchiveFormat::Binary))
+    }
+
+    /// Detect the format from an HTTP content type, for assets whose name has no recognized extension
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        match mime.as_str() {
+            "application/gzip" | "application/x-gzip" | "application/x-gtar" | "application/x-compressed-tar" => {
+                Some(ArchiveFormat::TarGz)
+            }
+            "application/x-xz" | "application/x-xz-compressed-tar" => Some(ArchiveFormat::TarXz),
+            "application/x-bzip2" | "application/x-bzip" | "application/x-bzip-compressed-tar" => {
+                Some(ArchiveFormat::TarBz2)
+            }
+            "application/zip" | "application/x-zip-compressed" => Some(ArchiveFormat::Zip),
+            "application/x-executable" | "application/x-msdownload" | "application/x-mach-binary" => {
+                Some(ArchiveFormat::Binary)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when an archive expands past the configured `max_uncompressed_size`
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<DecompressionLimitExceeded>()`.
+#[derive(Debug, Clone)]
+pub struct DecompressionLimitExceeded {
+    pub limit: u64,
+}
+
+impl fmt::Display for DecompressionLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Archive expands to more than the configured limit of {} bytes; extraction was aborted",
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for DecompressionLimitExceeded {}
+
+/// Running total of bytes written during one extraction, checked against an optional cap
+pub(crate) struct ExtractionBudget {
+    limit: Option<u64>,
+    used: u64,
+}
+
+impl ExtractionBudget {
+    pub fn new(limit: Option<u64>) -> Self {
+        Self { limit, used: 0 }
+    }
+
+    /// Account for `bytes` more output, failing if that would exceed the cap
+    pub fn charge(&mut self, bytes: u64) -> Result<(), DecompressionLimitExceeded> {
+        self.used = self.used.saturating_add(bytes);
+        match self.limit {
+            Some(limit) if self.used > limit => Err(DecompressionLimitExceeded { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Bytes that may still be written, or None without a cap
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.used))
+    }
+}
+
+/// Fraction of an extraction that's done, advanced once per entry
+///
+/// Measured in bytes when the archive declares any, so one large file moves the bar as much as
+/// many small ones; archives of only empty files and directories fall back to counting entries.
+pub(crate) struct ExtractionProgress {
+    total_bytes: u64,
+    total_entries: u64,
+    done_bytes: u64,
+    done_entries: u64,
+}
+
+impl ExtractionProgress {
+    pub fn new(total_bytes: u64, total_entries: u64) -> Self {
+        Self { total_bytes, total_entries, done_bytes: 0, done_entries: 0 }
+    }
+
+    /// Record one more entry of `bytes` written and return the new fraction
+    pub fn advance(&mut self, bytes: u64) -> f32 {
+        self.done_bytes += bytes;
+        self.done_entries += 1;
+
+        let fraction = if self.total_bytes > 0 {
+            self.done_bytes as f32 / self.total_bytes as f32
+        } else if self.total_entries > 0 {
+            self.done_entries as f32 / self.total_entries as f32
+        } else {
+            1.0
+        };
+        fraction.min(1.0)
+    }
+}
+
+/// Error returned when an archive entry would be written outside the extraction directory
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<UnsafeArchiveEntry>()`.
+#[derive(Debug, Clone)]
+pub struct UnsafeArchiveEntry {
+    /// The entry's path as stored in the archive
+    pub entry: PathBuf,
+}
+
+impl fmt::Display for UnsafeArchiveEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Archive entry '{}' points outside the extraction directory; refusing to extract it",
+            self.entry.display()
+        )
+    }
+}
+
+impl std::error::Error for UnsafeArchiveEntry {}
+
+/// Resolve where an archive entry should be written, refusing entries that escape `extract_to`
+///
+/// Absolute paths and `..` components are rejected outright. The deepest directory of the
+/// destination that already exists is then canonicalized and must still lie inside the
+/// canonicalized `extract_to`, which catches symlinks extracted earlier that point elsewhere.
+pub(crate) fn safe_entry_path(extract_to: &Path, entry: &Path) -> anyhow::Result<PathBuf> {
+    let unsafe_entry = || UnsafeArchiveEntry { entry: entry.to_path_buf() };
+
+    let mut relative = PathBuf::new();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return Err(unsafe_entry().into()),
+        }
+    }
+
+    let destination = extract_to.join(&relative);
+    let root = extract_to.canonicalize()?;
+    let existing = destination
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .find(|dir| dir.exists())
+        .unwrap_or(extract_to);
+
+    if !existing.canonicalize()?.starts_with(&root) {
+        return Err(unsafe_entry().into());
+    }
+
+    Ok(destination)
+}
+
+/// Check that a symlink entry at `link` (relative to the extraction root) pointing at `target`
+/// stays inside the extraction root
+///
+/// The target is resolved lexically from the link's directory, so it doesn't need to exist yet.
+/// Absolute targets are always rejected.
+pub(crate) fn check_link_target(link: &Path, target: &Path) -> Result<(), UnsafeArchiveEntry> {
+    let unsafe_entry = || UnsafeArchiveEntry { entry: link.to_path_buf() };
+
+    let mut depth = link.parent().map_or(0, |parent| {
+        parent.components().filter(|component| matches!(component, Component::Normal(_))).count()
+    });
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(unsafe_entry)?,
+            Component::RootDir | Component::Prefix(_) => return Err(unsafe_entry()),
+        }
+    }
+    Ok(())
+}
+
+/// Whether a zip entry's unix mode marks it as a symlink, whose contents are the link target
+pub(crate) fn is_symlink_mode(mode: u32) -> bool {
+    mode & 0o170000 == 0o120000
+}
+
+/// Create a symlink extracted from an archive at `destination`, the resolved path of `link`,
+/// failing with `UnsafeArchiveEntry` if `target` would lead outside the extraction root
+#[cfg(unix)]
+pub(crate) fn create_symlink(link: &Path, target: &Path, destination: &Path) -> anyhow::Result<()> {
+    check_link_target(link, target)?;
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Replace a link or file left by a previous install, as `symlink` won't overwrite
+    if destination.symlink_metadata().is_ok_and(|metadata| !metadata.is_dir()) {
+        std::fs::remove_file(destination)?;
+    }
+    std::os::unix::fs::symlink(target, destination)?;
+    Ok(())
+}
+
+/// Turn an absolute path into its `\\?\` form so deeply nested entries aren't limited to
+/// `MAX_PATH` (260 characters)
+#[cfg(target_os = "windows")]
+pub(crate) fn long_path(path: &Path) -> PathBuf {
+    use std::path::Prefix;
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let mut components = absolute.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return absolute;
+    };
+    let mut long = match prefix.kind() {
+        Prefix::Disk(_) => {
+            let mut long = std::ffi::OsString::from(r"\\?\");
+            long.push(prefix.as_os_str());
+            long.push(r"\");
+            PathBuf::from(long)
+        }
+        Prefix::UNC(server, share) => {
+            let mut long = std::ffi::OsString::from(r"\\?\UNC\");
+            long.push(server);
+            long.push(r"\");
+            long.push(share);
+            long.push(r"\");
+            PathBuf::from(long)
+        }
+        // Already a verbatim or device path
+        _ => return absolute,
+    };
+    // `absolute` has already resolved any `.` and `..`, which verbatim paths don't allow
+    long.extend(components.filter(|component| matches!(component, Component::Normal(_))));
+    long
+}
+
+/// Remove the first `components` directories from an archive entry's path
+///
+/// Returns None for entries that are used up by the stripping, such as the top-level folder
+/// itself, which are skipped. A `..` or absolute prefix is left in place so that
+/// `safe_entry_path` still rejects the entry.
+pub(crate) fn strip_components(entry: &Path, components: usize) -> Option<PathBuf> {
+    let mut remaining = entry.components().filter(|component| *component != Component::CurDir);
+    for _ in 0..components {
+        match remaining.next()? {
+            Component::Normal(_) => {}
+            _ => return Some(entry.to_path_buf()),
+        }
+    }
+
+    let stripped: PathBuf = remaining.collect();
+    (!stripped.as_os_str().is_empty()).then_some(stripped)
+}
+
+/// Whether every entry sits inside the same top-level directory, e.g. `app-1.2.3/`
+///
+/// An archive holding a single file at its root doesn't count, as stripping would leave nothing.
+pub(crate) fn has_single_root<'a>(entries: impl IntoIterator<Item = &'a Path>) -> bool {
+    let mut root = None;
+    let mut nested = false;
+    for entry in entries {
+        let mut components = entry.components().filter(|component| *component != Component::CurDir);
+        let Some(Component::Normal(first)) = components.next() else {
+            return false;
+        };
+        if *root.get_or_insert(first) != first {
+            return false;
+        }
+        nested |= components.next().is_some();
+    }
+    nested
+}
+
+/// Unpack one tar entry below `extract_to` with its first `strip` directories removed, failing
+/// with `UnsafeArchiveEntry` if it would escape
+///
+/// With `skip_existing` set, files and links that already exist are left as they are. Files
+/// `state` has as extracted are skipped, and the files written are recorded in it.
+///
+/// Symlink targets must stay inside `extract_to` (see `check_link_target`), and hard links
+/// must name another entry of the archive, which is resolved below `extract_to` the same way.
+pub(crate) fn unpack_tar_entry<R: std::io::Read>(
+    entry: &mut tar::Entry<R>,
+    extract_to: &Path,
+    strip: usize,
+    skip_existing: bool,
+    state: &mut ExtractState,
+    budget: &mut ExtractionBudget,
+) -> anyhow::Result<()> {
+    let Some(path) = strip_components(&entry.path()?, strip) else {
+        return Ok(());
+    };
+    if crate::extract_state::is_state_file(&path) {
+        return Err(UnsafeArchiveEntry { entry: path }.into());
+    }
+    let destination = safe_entry_path(extract_to, &path)?;
+    let entry_type = entry.header().entry_type();
+    if skip_existing && !entry_type.is_dir() && destination.symlink_metadata().is_ok() {
+        return Ok(());
+    }
+    if state.is_extracted(&destination) {
+        return Ok(());
+    }
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if entry_type.is_symlink() || entry_type.is_hard_link() {
+        let target = entry.link_name()?.ok_or_else(|| UnsafeArchiveEntry { entry: path.clone() })?.into_owned();
+        if entry_type.is_symlink() {
+            check_link_target(&path, &target)?;
+        } else {
+            // tar would resolve the link against the working directory, or take it as absolute
+            let Some(source) = strip_components(&target, strip) else {
+                return Ok(());
+            };
+            let source = safe_entry_path(extract_to, &source)?;
+            if destination.symlink_metadata().is_ok_and(|metadata| !metadata.is_dir()) {
+                std::fs::remove_file(&destination)?;
+            }
+            std::fs::hard_link(&source, &destination)?;
+            return Ok(());
+        }
+    }
+
+    if !(entry_type.is_file() || entry_type.is_gnu_sparse() || entry_type.is_contiguous()) {
+        entry.unpack(&destination)?;
+        return Ok(());
+    }
+
+    // Copied here rather than by tar so the budget counts what sparse and PAX-sized entries
+    // expand to, not the size in their header
+    if destination.symlink_metadata().is_ok_and(|metadata| !metadata.is_dir()) {
+        std::fs::remove_file(&destination)?;
+    }
+    let mut file = std::fs::File::create(&destination)?;
+    // Read at most one byte past the remaining budget to detect an overrun
+    let written = match budget.remaining() {
+        Some(remaining) => std::io::copy(&mut std::io::Read::take(&mut *entry, remaining.saturating_add(1)), &mut file)?,
+        None => std::io::copy(entry, &mut file)?,
+    };
+    if let Err(e) = budget.charge(written) {
+        drop(file);
+        let _ = std::fs::remove_file(&destination);
+        return Err(e.into());
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(mode) = entry.header().mode() {
+            std::fs::set_permissions(&destination, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+    state.record(&destination)?;
+    Ok(())
+}
+
+/// Comma-separated list of the extensions of `formats`, for error messages
+pub(crate) fn describe_extensions(formats: &[ArchiveFormat]) -> String {
+    formats
+        .iter()
+        .flat_map(|f| f.extensions())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extraction_progress() {
+        let mut progress = ExtractionProgress::new(1000, 3);
+        assert_eq!(progress.advance(0), 0.0);
+        assert_eq!(progress.advance(250), 0.25);
+        assert_eq!(progress.advance(750), 1.0);
+
+        // Only directories: count entries instead
+        let mut progress = ExtractionProgress::new(0, 4);
+        assert_eq!(progress.advance(0), 0.25);
+        assert_eq!(progress.advance(0), 0.5);
+    }
+
+    #[test]
+    fn test_from_file_name() {
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.tar.xz"), Some(ArchiveFormat::TarXz));
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.tbz2"), Some(ArchiveFormat::TarBz2));
+        assert_eq!(ArchiveFormat::from_file_name("app-windows-x64.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_file_name("app-windows-x64.msi"), None);
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.gz"), Some(ArchiveFormat::Gzip));
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64"), Some(ArchiveFormat::Binary));
+        assert_eq!(ArchiveFormat::from_file_name("app-windows-x64.exe"), Some(ArchiveFormat::Binary));
+        assert_eq!(ArchiveFormat::from_file_name("app-linux-x64.tar.gz.sha256"), None);
+    }
+
+    #[test]
+    fn test_extraction_budget() {
+        let mut budget = ExtractionBudget::new(Some(100));
+        assert!(budget.charge(60).is_ok());
+        assert_eq!(budget.remaining(), Some(40));
+        assert!(budget.charge(40).is_ok());
+        assert_eq!(budget.charge(1).unwrap_err().limit, 100);
+
+        let mut unlimited = ExtractionBudget::new(None);
+        assert!(unlimited.charge(u64::MAX).is_ok());
+        assert_eq!(unlimited.remaining(), None);
+    }
+
+    #[test]
+    fn test_safe_entry_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        assert_eq!(safe_entry_path(root, Path::new("./bin/app")).unwrap(), root.join("bin/app"));
+
+        for entry in ["../evil", "bin/../../evil", "/etc/passwd"] {
+            let err = safe_entry_path(root, Path::new(entry)).unwrap_err();
+            assert!(err.downcast_ref::<UnsafeArchiveEntry>().is_some(), "{} was accepted", entry);
+        }
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+            let err = safe_entry_path(root, Path::new("link/evil")).unwrap_err();
+            assert!(err.downcast_ref::<UnsafeArchiveEntry>().is_some());
+        }
+    }
+
+    #[test]
+    fn test_check_link_target() {
+        assert!(check_link_target(Path::new("bin/app"), Path::new("../lib/app-1.2.3")).is_ok());
+        assert!(check_link_target(Path::new("bin/app"), Path::new("./app-real")).is_ok());
+        assert!(check_link_target(Path::new("bin/app"), Path::new("../../etc/passwd")).is_err());
+        assert!(check_link_target(Path::new("app"), Path::new("lib/../../app")).is_err());
+        assert!(check_link_target(Path::new("app"), Path::new("/usr/bin/app")).is_err());
+        assert!(is_symlink_mode(0o120777) && !is_symlink_mode(0o100755));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unpack_tar_links_stay_inside() {
+        fn archive(links: &[(tar::EntryType, &str, &str)]) -> Vec<u8> {
+            let mut builder = tar::Builder::new(Vec::new());
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, "app-1.2.3/bin/real", &b"real"[..]).unwrap();
+            for (entry_type, path, target) in links {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(*entry_type);
+                header.set_size(0);
+                header.set_cksum();
+                builder.append_link(&mut header, path, target).unwrap();
+            }
+            builder.into_inner().unwrap()
+        }
+        fn unpack(archive: &[u8], extract_to: &Path) -> anyhow::Result<()> {
+            let mut archive = tar::Archive::new(archive);
+            for entry in archive.entries()? {
+                unpack_tar_entry(&mut entry?, extract_to, 1, false, &mut ExtractState::disabled(), &mut ExtractionBudget::new(None))?;
+            }
+            Ok(())
+        }
+
+        // A hard link names another entry, with the same components stripped
+        let dir = tempfile::tempdir().unwrap();
+        let links = [(tar::EntryType::Link, "app-1.2.3/bin/hard", "app-1.2.3/bin/real")];
+        unpack(&archive(&links), dir.path()).unwrap();
+        assert_eq!(std::fs::read(dir.path().join("bin/hard")).unwrap(), b"real");
+
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret"), b"secret").unwrap();
+        let secret = outside.path().join("secret").to_string_lossy().into_owned();
+        for link in [
+            (tar::EntryType::Link, "app-1.2.3/bin/hard", secret.as_str()),
+            (tar::EntryType::Link, "app-1.2.3/bin/hard", "../../secret"),
+            (tar::EntryType::Symlink, "app-1.2.3/bin/soft", "../../secret"),
+            (tar::EntryType::Symlink, "app-1.2.3/bin/soft", secret.as_str()),
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let err = unpack(&archive(&[link]), dir.path()).unwrap_err();
+            assert!(err.downcast_ref::<UnsafeArchiveEntry>().is_some(), "{:?} was accepted", link);
+            assert!(!dir.path().join("bin/hard").exists() && !dir.path().join("bin/soft").exists());
+        }
+    }
+
+    #[test]
+    fn test_unpack_tar_charges_written_bytes() {
+        // The header claims an empty file; the PAX record holds the real size
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut pax = tar::Header::new_ustar();
+        pax.set_entry_type(tar::EntryType::XHeader);
+        pax.set_path("PaxHeaders/app").unwrap();
+        pax.set_size(13);
+        pax.set_cksum();
+        builder.append(&pax, &b"13 size=2048\n"[..]).unwrap();
+        let mut header = tar::Header::new_ustar();
+        header.set_path("app-1.2.3/app").unwrap();
+        header.set_size(0);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append(&header, &[7u8; 2048][..]).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let unpack = |limit: Option<u64>, extract_to: &Path| -> anyhow::Result<()> {
+            let mut budget = ExtractionBudget::new(limit);
+            let mut archive = tar::Archive::new(&archive[..]);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                assert_eq!(entry.header().size()?, 0);
+                unpack_tar_entry(&mut entry, extract_to, 1, false, &mut ExtractState::disabled(), &mut budget)?;
+            }
+            Ok(())
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let err = unpack(Some(1024), dir.path()).unwrap_err();
+        assert_eq!(err.downcast_ref::<DecompressionLimitExceeded>().unwrap().limit, 1024);
+        assert!(!dir.path().join("app").exists());
+
+        let dir = tempfile::tempdir().unwrap();
+        unpack(Some(4096), dir.path()).unwrap();
+        assert_eq!(std::fs::read(dir.path().join("app")).unwrap(), vec![7u8; 2048]);
+    }
+
+    #[test]
+    fn test_strip_components() {
+        assert_eq!(strip_components(Path::new("./app-1.2.3/bin/app"), 1), Some(PathBuf::from("bin/app")));
+        assert_eq!(strip_components(Path::new("app-1.2.3/bin/app"), 0), Some(PathBuf::from("app-1.2.3/bin/app")));
+        assert_eq!(strip_components(Path::new("app-1.2.3/"), 1), None);
+        assert_eq!(strip_components(Path::new("app-1.2.3/bin"), 2), None);
+        assert_eq!(strip_components(Path::new("../evil"), 1), Some(PathBuf::from("../evil")));
+    }
+
+    #[test]
+    fn test_has_single_root() {
+        let paths = |entries: &'static [&'static str]| entries.iter().map(Path::new);
+
+        assert!(has_single_root(paths(&["app-1.2.3/", "app-1.2.3/bin/app", "./app-1.2.3/README.md"])));
+        assert!(!has_single_root(paths(&["app-1.2.3/bin/app", "LICENSE"])));
+        assert!(!has_single_root(paths(&["bin/app", "lib/libapp.so"])));
+        assert!(!has_single_root(paths(&["app"])));
+        assert!(!has_single_root(paths(&[])));
+    }
+
+    #[test]
+    fn test_from_content_type() {
+        assert_eq!(ArchiveFormat::from_content_type("application/gzip"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_content_type("Application/Zip; charset=binary"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_content_type("application/octet-stream"), None);
+    }
+}
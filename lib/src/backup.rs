@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Copy of an install directory taken before an update so a failed update can be undone
+pub(crate) struct InstallBackup {
+    install_path: PathBuf,
+    backup_path: PathBuf,
+}
+
+impl InstallBackup {
+    /// Copy `install_path` to a sibling `<install_path>.bak`, replacing any stale backup
+    pub fn create(install_path: &Path) -> Result<Self> {
+        let backup_path = backup_path(install_path);
+
+        if backup_path.exists() {
+            std::fs::remove_dir_all(&backup_path)
+                .context(format!("Failed to remove stale backup '{}'", backup_path.display()))?;
+        }
+
+        if let Err(e) = copy_tree(install_path, &backup_path) {
+            let _ = std::fs::remove_dir_all(&backup_path);
+            return Err(e.context(format!("Failed to back up '{}'", install_path.display())));
+        }
+
+        Ok(Self {
+            install_path: install_path.to_path_buf(),
+            backup_path,
+        })
+    }
+
+    /// Put the backed up files back in place of whatever the failed update left behind
+    ///
+    /// The files in `keep` that lie inside the install directory, such as the install log, are
+    /// carried over from the failed update instead, so what it recorded isn't rolled back too.
+    pub fn restore(self, keep: &[PathBuf]) -> Result<()> {
+        for path in keep.iter().filter(|path| path.is_file()) {
+            let Ok(relative) = path.strip_prefix(&self.install_path) else {
+                continue;
+            };
+            let target = self.backup_path.join(relative);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(path, &target).context(format!("Failed to keep '{}' across the rollback", path.display()))?;
+        }
+
+        if self.install_path.exists() {
+            std::fs::remove_dir_all(&self.install_path)
+                .context(format!("Failed to remove '{}' before restoring the backup", self.install_path.display()))?;
+        }
+
+        std::fs::rename(&self.backup_path, &self.install_path).context(format!(
+            "Failed to restore '{}' from '{}'",
+            self.install_path.display(),
+            self.backup_path.display()
+        ))
+    }
+
+    /// Delete the backup once the update is known to be good
+    pub fn discard(self) -> Result<()> {
+        std::fs::remove_dir_all(&self.backup_path)
+            .context(format!("Failed to remove backup '{}'", self.backup_path.display()))
+    }
+}
+
+/// Where the backup of `install_path` is kept
+pub(crate) fn backup_path(install_path: &Path) -> PathBuf {
+    let mut name = install_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    install_path.with_file_name(name)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            copy_tree(&source, &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&source, &target)?;
+        } else {
+            std::fs::copy(&source, &target)
+                .context(format!("Failed to copy '{}'", source.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy everything in `from` over `to`, leaving files that already exist under the `keep` paths
+/// (relative to `to`) untouched
+pub(crate) fn overlay_tree(from: &Path, to: &Path, keep: &[PathBuf]) -> Result<()> {
+    overlay_dir(from, to, Path::new(""), keep)
+}
+
+fn overlay_dir(from: &Path, to: &Path, relative: &Path, keep: &[PathBuf]) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let relative = relative.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            overlay_dir(&source, &target, &relative, keep)?;
+        } else if target.exists() && keep.iter().any(|kept| relative.starts_with(kept)) {
+            continue;
+        } else if file_type.is_symlink() {
+            let _ = std::fs::remove_file(&target);
+            copy_symlink(&source, &target)?;
+        } else {
+            std::fs::copy(&source, &target)
+                .context(format!("Failed to copy '{}'", source.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, target)
+        .context(format!("Failed to copy symlink '{}'", source.display()))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> Result<()> {
+    // Creating symlinks needs extra privileges on Windows, so copy what the link points to
+    if source.is_dir() {
+        copy_tree(source, target)
+    } else {
+        std::fs::copy(source, target)
+            .map(|_| ())
+            .context(format!("Failed to copy '{}'", source.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_replaces_failed_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(install_path.join("bin")).unwrap();
+        std::fs::write(install_path.join("bin/server"), b"old").unwrap();
+
+        let backup = InstallBackup::create(&install_path).unwrap();
+        assert!(dir.path().join("app.bak/bin/server").is_file());
+
+        // A half-finished update: one file replaced, one added
+        std::fs::write(install_path.join("bin/server"), b"new").unwrap();
+        std::fs::write(install_path.join("partial"), b"").unwrap();
+        std::fs::write(install_path.join("install.log"), b"update failed").unwrap();
+
+        backup.restore(&[install_path.join("install.log"), install_path.join("missing")]).unwrap();
+        assert_eq!(std::fs::read(install_path.join("bin/server")).unwrap(), b"old");
+        assert!(!install_path.join("partial").exists());
+        assert_eq!(std::fs::read(install_path.join("install.log")).unwrap(), b"update failed");
+        assert!(!dir.path().join("app.bak").exists());
+    }
+
+    #[test]
+    fn test_overlay_keeps_existing_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(staging.join("config")).unwrap();
+        std::fs::write(staging.join("server"), b"fresh").unwrap();
+        std::fs::write(staging.join("config/settings.toml"), b"default").unwrap();
+        std::fs::write(staging.join("config/extra.toml"), b"default").unwrap();
+
+        std::fs::create_dir_all(install_path.join("config")).unwrap();
+        std::fs::write(install_path.join("server"), b"corrupt").unwrap();
+        std::fs::write(install_path.join("config/settings.toml"), b"edited").unwrap();
+
+        overlay_tree(&staging, &install_path, &[PathBuf::from("config")]).unwrap();
+        assert_eq!(std::fs::read(install_path.join("server")).unwrap(), b"fresh");
+        assert_eq!(std::fs::read(install_path.join("config/settings.toml")).unwrap(), b"edited");
+        // Missing files in a data dir are still put back
+        assert_eq!(std::fs::read(install_path.join("config/extra.toml")).unwrap(), b"default");
+    }
+
+    #[test]
+    fn test_discard_removes_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(&install_path).unwrap();
+        std::fs::write(install_path.join("server"), b"old").unwrap();
+
+        InstallBackup::create(&install_path).unwrap().discard().unwrap();
+        assert!(!dir.path().join("app.bak").exists());
+        assert!(install_path.join("server").is_file());
+    }
+}
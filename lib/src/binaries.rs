@@ -0,0 +1,324 @@
+use anyhow::Result;
+#[cfg(unix)]
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::InstallationConfig;
+#[cfg(unix)]
+use crate::InstallScope;
+
+/// One executable shipped by the release, and how install exposes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinarySpec {
+    /// File name of the executable in install_path or its `bin/` (`.exe` is added on Windows)
+    pub name: String,
+    /// Whether the service, `launch` and shortcuts run this one (defaults to false; the first
+    /// binary is primary when none is marked)
+    #[serde(default)]
+    pub primary: bool,
+    /// Symlink the executable into /usr/local/bin, or ~/.local/bin for user installs (Unix only, defaults to false)
+    #[serde(default)]
+    pub link_on_path: bool,
+    /// Add a Start Menu shortcut (Windows) or `.desktop` launcher (Linux) for it (defaults to false)
+    #[serde(default)]
+    pub shortcut: bool,
+}
+
+impl BinarySpec {
+    /// Describe the executable called `name`, with no link or shortcut
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            primary: false,
+            link_on_path: false,
+            shortcut: false,
+        }
+    }
+
+    /// Set whether this is the binary the service runs
+    pub fn primary(mut self, primary: bool) -> Self {
+        self.primary = primary;
+        self
+    }
+
+    /// Set whether install links the executable into a directory on PATH (Unix only)
+    pub fn link_on_path(mut self, enabled: bool) -> Self {
+        self.link_on_path = enabled;
+        self
+    }
+
+    /// Set whether install adds a shortcut or launcher for the executable
+    pub fn shortcut(mut self, enabled: bool) -> Self {
+        self.shortcut = enabled;
+        self
+    }
+}
+
+impl InstallationConfig {
+    /// The binary the service runs: the one marked primary, else the first of `binaries`
+    pub fn get_primary_binary(&self) -> Option<&BinarySpec> {
+        self.binaries.iter().find(|spec| spec.primary).or(self.binaries.first())
+    }
+
+    /// Get the name of the executable the service runs (the primary binary, or binary_name when
+    /// no binaries are configured)
+    pub fn get_binary_name(&self) -> Option<&str> {
+        match self.get_primary_binary() {
+            Some(spec) => Some(&spec.name),
+            None => self.binary_name.as_deref(),
+        }
+    }
+
+    /// Names of every configured executable, the primary one first
+    pub(crate) fn binary_names(&self) -> Vec<&str> {
+        let primary = self.get_binary_name();
+        primary
+            .into_iter()
+            .chain(self.binaries.iter().map(|spec| spec.name.as_str()).filter(|name| Some(*name) != primary))
+            .collect()
+    }
+
+    /// Binaries besides the primary one that get their own shortcut, with the shortcut's name
+    pub(crate) fn secondary_shortcuts(&self) -> Vec<(&BinarySpec, String)> {
+        let primary = self.get_primary_binary();
+        self.binaries
+            .iter()
+            .filter(|spec| spec.shortcut && !primary.is_some_and(|primary| std::ptr::eq(*spec, primary)))
+            .map(|spec| (spec, format!("{} ({})", self.get_display_name(), spec.name)))
+            .collect()
+    }
+}
+
+/// Error returned when an extracted release doesn't contain the executable the service would run
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<BinaryNotFound>()`; the
+/// operation fails with `InstallError::BinaryNotFound`. It usually means the wrong asset was
+/// selected or the archive is laid out differently than expected (see `strip_components`).
+#[derive(Debug, Clone)]
+pub struct BinaryNotFound {
+    /// The configured binary name, or None when any executable would have done
+    pub name: Option<String>,
+    pub install_path: PathBuf,
+    /// Set when the file is there but lacks execute permission
+    pub not_executable: Option<PathBuf>,
+}
+
+impl fmt::Display for BinaryNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.not_executable) {
+            (_, Some(path)) => write!(f, "'{}' was extracted but isn't executable", path.display()),
+            (Some(name), None) => write!(
+                f,
+                "Binary '{}' not found in '{}' or its bin directory after extraction",
+                name,
+                self.install_path.display()
+            ),
+            (None, None) => write!(f, "No executable found in '{}' after extraction", self.install_path.display()),
+        }
+    }
+}
+
+impl std::error::Error for BinaryNotFound {}
+
+/// Check that every configured executable landed in install_path (or any executable at all
+/// when none is configured) and can be run
+pub(crate) fn verify_extracted(config: &InstallationConfig, find_any: impl FnOnce() -> Result<PathBuf>) -> Result<()> {
+    let install_path = &config.install_path;
+    let names = config.binary_names();
+    if names.is_empty() {
+        find_any().map_err(|_| BinaryNotFound {
+            name: None,
+            install_path: install_path.clone(),
+            not_executable: None,
+        })?;
+        return Ok(());
+    }
+
+    for name in names {
+        let missing = |not_executable| BinaryNotFound {
+            name: Some(name.to_string()),
+            install_path: install_path.clone(),
+            not_executable,
+        };
+        let path = locate(install_path, name).ok_or_else(|| missing(None))?;
+        if !is_executable(&path) {
+            return Err(missing(Some(path)).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// `locate` only finds `.exe` files on Windows
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+/// Find the executable called `name` in install_path or its `bin/`
+pub(crate) fn locate(install_path: &Path, name: &str) -> Option<PathBuf> {
+    let file_name = if cfg!(target_os = "windows") && !name.to_lowercase().ends_with(".exe") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    };
+    [install_path.join(&file_name), install_path.join("bin").join(&file_name)]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Directory the `link_on_path` symlinks go in for the configured install scope
+#[cfg(unix)]
+fn link_dir(config: &InstallationConfig) -> PathBuf {
+    match config.install_scope {
+        InstallScope::System => PathBuf::from("/usr/local/bin"),
+        InstallScope::User => std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(".local/bin"),
+    }
+}
+
+/// Paths of the symlinks install creates for binaries with `link_on_path` set
+#[cfg(unix)]
+pub(crate) fn path_links(config: &InstallationConfig) -> Vec<PathBuf> {
+    links_in(config, &link_dir(config))
+}
+
+#[cfg(unix)]
+fn links_in(config: &InstallationConfig, dir: &Path) -> Vec<PathBuf> {
+    config
+        .binaries
+        .iter()
+        .filter(|spec| spec.link_on_path)
+        .map(|spec| dir.join(&spec.name))
+        .collect()
+}
+
+/// Symlink every `link_on_path` binary into the PATH directory, replacing links left by an
+/// earlier install but never a file that isn't a symlink
+#[cfg(unix)]
+pub(crate) fn link_binaries(config: &InstallationConfig) -> Result<()> {
+    link_into(config, &link_dir(config))
+}
+
+#[cfg(unix)]
+fn link_into(config: &InstallationConfig, dir: &Path) -> Result<()> {
+    for (spec, link) in config.binaries.iter().filter(|spec| spec.link_on_path).zip(links_in(config, dir)) {
+        let target = locate(&config.install_path, &spec.name)
+            .context(format!("Binary '{}' not found in '{}'", spec.name, config.install_path.display()))?;
+
+        match std::fs::symlink_metadata(&link) {
+            Ok(metadata) if metadata.file_type().is_symlink() => std::fs::remove_file(&link)?,
+            Ok(_) => anyhow::bail!("Refusing to replace '{}' with a link to {}: it isn't a symlink", link.display(), spec.name),
+            Err(_) => {}
+        }
+        if let Some(parent) = link.parent() {
+            std::fs::create_dir_all(parent).context(format!("Failed to create '{}'", parent.display()))?;
+        }
+        std::os::unix::fs::symlink(&target, &link)
+            .context(format!("Failed to link '{}' to '{}'", link.display(), target.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Remove the symlinks created by `link_binaries`, leaving anything that doesn't point into install_path
+#[cfg(unix)]
+pub(crate) fn unlink_binaries(config: &InstallationConfig) -> Result<()> {
+    unlink_from(config, &link_dir(config))
+}
+
+#[cfg(unix)]
+fn unlink_from(config: &InstallationConfig, dir: &Path) -> Result<()> {
+    for link in links_in(config, dir) {
+        if points_into(&link, &config.install_path) {
+            std::fs::remove_file(&link).context(format!("Failed to remove '{}'", link.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn points_into(link: &Path, dir: &Path) -> bool {
+    std::fs::read_link(link).is_ok_and(|target| target.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primary_binary() {
+        let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string())
+            .binary_name("legacy".to_string());
+        assert_eq!(config.get_binary_name(), Some("legacy"));
+
+        let config = config
+            .binary(BinarySpec::new("app-cli".to_string()).shortcut(true))
+            .binary(BinarySpec::new("app-server".to_string()).primary(true).shortcut(true));
+        assert_eq!(config.get_binary_name(), Some("app-server"));
+        assert_eq!(config.binary_names(), ["app-server", "app-cli"]);
+
+        let shortcuts = config.secondary_shortcuts();
+        assert_eq!(shortcuts.len(), 1);
+        assert_eq!(shortcuts[0].0.name, "app-cli");
+        assert_eq!(shortcuts[0].1, "app (app-cli)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_binaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(install_path.join("bin")).unwrap();
+        std::fs::write(install_path.join("bin").join("app-cli"), b"#!/bin/sh\n").unwrap();
+
+        let bin_dir = dir.path().join("local/bin");
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .binary(BinarySpec::new("app-cli".to_string()).link_on_path(true));
+
+        link_into(&config, &bin_dir).unwrap();
+        let link = bin_dir.join("app-cli");
+        assert_eq!(std::fs::read_link(&link).unwrap(), install_path.join("bin").join("app-cli"));
+        // Linking again replaces the old link
+        link_into(&config, &bin_dir).unwrap();
+
+        unlink_from(&config, &bin_dir).unwrap();
+        assert!(std::fs::symlink_metadata(&link).is_err());
+
+        std::fs::write(&link, b"someone else's").unwrap();
+        assert!(link_into(&config, &bin_dir).is_err());
+        unlink_from(&config, &bin_dir).unwrap();
+        assert!(link.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_extracted() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(install_path.join("bin")).unwrap();
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .binary_name("app-server".to_string());
+        let unused = || anyhow::bail!("not consulted when a binary is configured");
+
+        let err = verify_extracted(&config, unused).unwrap_err();
+        assert!(err.downcast_ref::<BinaryNotFound>().unwrap().not_executable.is_none());
+
+        let binary = install_path.join("bin/app-server");
+        std::fs::write(&binary, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let err = verify_extracted(&config, unused).unwrap_err();
+        assert_eq!(err.downcast_ref::<BinaryNotFound>().unwrap().not_executable.as_ref(), Some(&binary));
+
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        verify_extracted(&config, unused).unwrap();
+    }
+}
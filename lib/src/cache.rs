@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::GitHubAsset;
+
+/// Download cache shared between services, keyed by asset SHA-256 and name
+///
+/// Only assets whose release metadata includes a SHA-256 digest are cached, since cached
+/// bytes are always re-verified against that digest before being reused.
+pub(crate) struct DownloadCache<'a> {
+    dir: &'a Path,
+    max_bytes: Option<u64>,
+}
+
+impl<'a> DownloadCache<'a> {
+    pub fn new(dir: &'a Path, max_bytes: Option<u64>) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    fn entry_path(&self, asset: &GitHubAsset) -> Option<PathBuf> {
+        let sha256 = asset.sha256()?;
+        Some(self.dir.join(format!("{}-{}", sha256, asset.name)))
+    }
+
+    /// Copy a cached, verified copy of the asset to `dest_path`, returning false on a cache miss
+    pub fn restore(&self, asset: &GitHubAsset, dest_path: &Path) -> Result<bool> {
+        let (Some(path), Some(expected)) = (self.entry_path(asset), asset.sha256()) else {
+            return Ok(false);
+        };
+
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        if sha256_file(&path)? != expected {
+            // Corrupted entry, drop it and fall back to the network
+            let _ = std::fs::remove_file(&path);
+            return Ok(false);
+        }
+
+        std::fs::copy(&path, dest_path)
+            .context(format!("Failed to copy cached asset to '{}'", dest_path.display()))?;
+
+        // Mark as recently used for LRU eviction
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Ok(true)
+    }
+
+    /// Store a downloaded asset that has already been verified against its digest
+    pub fn store(&self, asset: &GitHubAsset, source: &Path) -> Result<()> {
+        let Some(path) = self.entry_path(asset) else {
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(self.dir)
+            .context(format!("Failed to create cache directory '{}'", self.dir.display()))?;
+
+        // Copy under a temporary name and rename so other services never see a partial entry
+        let partial = self.dir.join(format!(".{}.{}.partial", asset.name, std::process::id()));
+        std::fs::copy(source, &partial).context("Failed to copy asset into the download cache")?;
+        std::fs::rename(&partial, &path).context("Failed to finalize download cache entry")?;
+
+        self.evict()
+    }
+
+    /// Remove least recently used entries until the cache fits within max_bytes
+    fn evict(&self) -> Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && !entry.file_name().to_string_lossy().starts_with('.') {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((modified, metadata.len(), entry.path()));
+            }
+        }
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort_by_key(|(modified, _, _)| *modified);
+
+        for (_, len, path) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Remove every entry from the download cache directory
+pub(crate) fn clear(dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            std::fs::remove_file(&path)
+                .context(format!("Failed to remove cached file '{}'", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Compute the lowercase hex SHA-256 of a file
+pub(crate) fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)
+        .context(format!("Failed to open '{}' for hashing", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .context(format!("Failed to read '{}' for hashing", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset_for(content: &[u8], name: &str) -> GitHubAsset {
+        GitHubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+            size: content.len() as u64,
+            digest: Some(format!("sha256:{:x}", Sha256::digest(content))),
+            content_type: None,
+            state: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_store_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache = DownloadCache::new(&cache_dir, None);
+
+        let source = dir.path().join("download.zip");
+        std::fs::write(&source, b"archive bytes").unwrap();
+        let asset = asset_for(b"archive bytes", "app.zip");
+
+        let restored = dir.path().join("restored.zip");
+        assert!(!cache.restore(&asset, &restored).unwrap());
+
+        cache.store(&asset, &source).unwrap();
+        assert!(cache.restore(&asset, &restored).unwrap());
+        assert_eq!(std::fs::read(&restored).unwrap(), b"archive bytes");
+
+        clear(&cache_dir).unwrap();
+        assert!(!cache.restore(&asset, &restored).unwrap());
+    }
+
+    #[test]
+    fn test_corrupted_entry_is_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path(), None);
+        let asset = asset_for(b"good", "app.zip");
+
+        std::fs::write(cache.entry_path(&asset).unwrap(), b"bad").unwrap();
+        assert!(!cache.restore(&asset, &dir.path().join("out")).unwrap());
+        assert!(!cache.entry_path(&asset).unwrap().exists());
+    }
+
+    #[test]
+    fn test_eviction_removes_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache = DownloadCache::new(&cache_dir, Some(10));
+
+        let old = asset_for(b"12345678", "old.zip");
+        let new = asset_for(b"abcdefgh", "new.zip");
+        let source = dir.path().join("src");
+
+        std::fs::write(&source, b"12345678").unwrap();
+        cache.store(&old, &source).unwrap();
+        let old_path = cache.entry_path(&old).unwrap();
+        std::fs::File::options().write(true).open(&old_path).unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH).unwrap();
+
+        std::fs::write(&source, b"abcdefgh").unwrap();
+        cache.store(&new, &source).unwrap();
+
+        assert!(!old_path.exists());
+        assert!(cache.entry_path(&new).unwrap().exists());
+    }
+
+    #[test]
+    fn test_assets_without_digest_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path(), None);
+        let mut asset = asset_for(b"data", "app.zip");
+        asset.digest = None;
+
+        let source = dir.path().join("src");
+        std::fs::write(&source, b"data").unwrap();
+        cache.store(&asset, &source).unwrap();
+        assert!(!cache.restore(&asset, &dir.path().join("out")).unwrap());
+    }
+}
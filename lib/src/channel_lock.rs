@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::ReleaseChannel;
+
+/// Source an installation is pinned to, recorded at first install
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelLock {
+    pub github_repo: String,
+    pub channel: ReleaseChannel,
+}
+
+impl fmt::Display for ChannelLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?} channel)", self.github_repo, self.channel)
+    }
+}
+
+/// Error returned when an install or update targets a different repo or channel than the one
+/// the installation is locked to
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<ChannelLockMismatch>()`,
+/// and call `override_lock` to deliberately switch sources.
+#[derive(Debug, Clone)]
+pub struct ChannelLockMismatch {
+    pub locked: ChannelLock,
+    pub requested: ChannelLock,
+}
+
+impl fmt::Display for ChannelLockMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "This installation is locked to {} but {} was requested. Use override_lock() to switch intentionally.",
+            self.locked, self.requested
+        )
+    }
+}
+
+impl std::error::Error for ChannelLockMismatch {}
+
+/// Read the lock record, if one has been written
+pub(crate) fn read(path: &Path) -> Result<Option<ChannelLock>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read channel lock '{}'", path.display()))?;
+    let lock = serde_json::from_str(&content)
+        .context(format!("Malformed channel lock '{}'", path.display()))?;
+    Ok(Some(lock))
+}
+
+/// Write (or replace) the lock record
+pub(crate) fn write(path: &Path, lock: &ChannelLock) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .context(format!("Failed to create directory for channel lock '{}'", path.display()))?;
+    }
+
+    let content = serde_json::to_string_pretty(lock)?;
+    std::fs::write(path, content)
+        .context(format!("Failed to write channel lock '{}'", path.display()))
+}
+
+/// Fail with `ChannelLockMismatch` if a lock exists and differs from the requested source
+pub(crate) fn verify(path: &Path, requested: &ChannelLock) -> Result<()> {
+    match read(path)? {
+        Some(locked) if &locked != requested => Err(ChannelLockMismatch {
+            locked,
+            requested: requested.clone(),
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_against_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.channel.json");
+        let stable = ChannelLock {
+            github_repo: "owner/repo".to_string(),
+            channel: ReleaseChannel::Release,
+        };
+
+        // No lock yet, anything goes
+        assert!(verify(&path, &stable).is_ok());
+
+        write(&path, &stable).unwrap();
+        assert_eq!(read(&path).unwrap(), Some(stable.clone()));
+        assert!(verify(&path, &stable).is_ok());
+
+        let beta = ChannelLock { channel: ReleaseChannel::Beta, ..stable.clone() };
+        let err = verify(&path, &beta).unwrap_err();
+        let mismatch = err.downcast_ref::<ChannelLockMismatch>().unwrap();
+        assert_eq!(mismatch.locked, stable);
+        assert_eq!(mismatch.requested, beta);
+
+        let other_repo = ChannelLock { github_repo: "other/repo".to_string(), ..stable };
+        assert!(verify(&path, &other_repo).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+use std::path::Path;
+
+/// Expected digest of a downloaded asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    Sha256([u8; 32]),
+    Sha512([u8; 64]),
+}
+
+impl Checksum {
+    /// Parse a hex digest, picking the algorithm from its length (64 or 128 hex digits)
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        match hex.len() {
+            64 => decode_hex(hex).map(Checksum::Sha256),
+            128 => decode_hex(hex).map(Checksum::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Parse a GitHub asset digest such as "sha256:..."
+    pub fn from_digest(digest: &str) -> Option<Self> {
+        let (algorithm, hex) = digest.split_once(':')?;
+        match (algorithm.to_ascii_lowercase().as_str(), Self::from_hex(hex)?) {
+            ("sha256", checksum @ Checksum::Sha256(_)) | ("sha512", checksum @ Checksum::Sha512(_)) => Some(checksum),
+            _ => None,
+        }
+    }
+
+    /// Hash a file with the same algorithm as this checksum
+    pub(crate) fn compute_for(&self, path: &Path) -> Result<Checksum> {
+        let mut file = std::fs::File::open(path)
+            .context(format!("Failed to open '{}' for hashing", path.display()))?;
+
+        let checksum = match self {
+            Checksum::Sha256(_) => {
+                let mut hasher = Sha256::new();
+                std::io::copy(&mut file, &mut hasher)
+                    .context(format!("Failed to read '{}' for hashing", path.display()))?;
+                Checksum::Sha256(hasher.finalize().into())
+            }
+            Checksum::Sha512(_) => {
+                let mut hasher = Sha512::new();
+                std::io::copy(&mut file, &mut hasher)
+                    .context(format!("Failed to read '{}' for hashing", path.display()))?;
+                Checksum::Sha512(hasher.finalize().into())
+            }
+        };
+        Ok(checksum)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Checksum::Sha256(bytes) => bytes,
+            Checksum::Sha512(bytes) => bytes,
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let algorithm = match self {
+            Checksum::Sha256(_) => "sha256",
+            Checksum::Sha512(_) => "sha512",
+        };
+        write!(f, "{}:", algorithm)?;
+        self.bytes().iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Error returned when a downloaded file doesn't match its expected checksum
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<ChecksumMismatch>()`.
+#[derive(Debug, Clone)]
+pub struct ChecksumMismatch {
+    pub asset: String,
+    pub expected: Checksum,
+    pub actual: Checksum,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch for '{}': expected {}, got {}. The download may be corrupted or tampered with.",
+            self.asset, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+/// Check a downloaded file against `expected`, deleting it on a mismatch
+pub(crate) fn verify_file(path: &Path, asset_name: &str, expected: &Checksum) -> Result<()> {
+    let actual = expected.compute_for(path)?;
+    if &actual != expected {
+        let _ = std::fs::remove_file(path);
+        return Err(ChecksumMismatch {
+            asset: asset_name.to_string(),
+            expected: *expected,
+            actual,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether an asset name looks like a checksum file that may cover `asset_name`
+pub(crate) fn is_checksum_file_for(candidate: &str, asset_name: &str) -> bool {
+    let lower = candidate.to_lowercase();
+    let asset_lower = asset_name.to_lowercase();
+
+    lower == format!("{}.sha256", asset_lower)
+        || lower == format!("{}.sha512", asset_lower)
+        || lower.ends_with("checksums.txt")
+        || matches!(lower.as_str(), "sha256sums" | "sha256sums.txt" | "sha512sums" | "sha512sums.txt")
+}
+
+/// Find the checksum for `asset_name` in the contents of a checksum file
+///
+/// Understands `sha256sum`-style lines (`<hex>  <name>` or `<hex> *<name>`), BSD-style lines
+/// (`SHA256 (<name>) = <hex>`) and sidecar files holding nothing but the digest.
+pub(crate) fn parse_checksum_file(content: &str, asset_name: &str) -> Option<Checksum> {
+    let lines: Vec<&str> = content.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+    for line in &lines {
+        if let Some((hex, name)) = line.split_once(char::is_whitespace) {
+            let name = name.trim().trim_start_matches('*');
+            if name == asset_name {
+                return Checksum::from_hex(hex);
+            }
+        }
+
+        if let Some((name, hex)) = line.split_once(") = ")
+            && name.split_once(" (").is_some_and(|(_, name)| name == asset_name)
+        {
+            return Checksum::from_hex(hex);
+        }
+    }
+
+    // A per-asset sidecar may contain just the digest
+    match lines.as_slice() {
+        [only] => Checksum::from_hex(only.split_whitespace().next()?),
+        _ => None,
+    }
+}
+
+pub(crate) fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 {
+        return None;
+    }
+
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn test_parse_checksum_file_formats() {
+        let sums = format!("{}  other.zip\n{} *app-linux-x64.tar.gz\n", "0".repeat(64), HELLO_SHA256);
+        let expected = Checksum::from_hex(HELLO_SHA256).unwrap();
+        assert_eq!(parse_checksum_file(&sums, "app-linux-x64.tar.gz"), Some(expected));
+        assert_eq!(parse_checksum_file(&sums, "missing.zip"), None);
+
+        let bsd = format!("SHA256 (app-linux-x64.tar.gz) = {}\n", HELLO_SHA256);
+        assert_eq!(parse_checksum_file(&bsd, "app-linux-x64.tar.gz"), Some(expected));
+
+        assert_eq!(parse_checksum_file(&format!("{}\n", HELLO_SHA256), "app-linux-x64.tar.gz"), Some(expected));
+        assert!(matches!(parse_checksum_file(&"ab".repeat(64), "x"), Some(Checksum::Sha512(_))));
+    }
+
+    #[test]
+    fn test_verify_file_deletes_on_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.tar.gz");
+        std::fs::write(&path, b"hello").unwrap();
+
+        let good = Checksum::from_digest(&format!("sha256:{}", HELLO_SHA256)).unwrap();
+        assert!(verify_file(&path, "app.tar.gz", &good).is_ok());
+
+        let bad = Checksum::Sha256([0; 32]);
+        let err = verify_file(&path, "app.tar.gz", &bad).unwrap_err();
+        assert_eq!(err.downcast_ref::<ChecksumMismatch>().unwrap().actual, good);
+        assert!(!path.exists());
+    }
+}
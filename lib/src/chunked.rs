@@ -0,0 +1,240 @@
+use anyhow::{Context, Result};
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::cancel::CancellationToken;
+use crate::transfer::{Throttle, TransferRate};
+use crate::{preflight, GitHubAsset, InstallationManager, State};
+
+/// Smallest asset worth splitting across connections; anything less downloads as one stream
+pub(crate) const MIN_PARALLEL_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Progress shared by the connections of one download
+struct SharedProgress {
+    downloaded: AtomicU64,
+    rate: Mutex<TransferRate>,
+}
+
+impl InstallationManager {
+    /// Whether `asset` is downloaded over several connections at once
+    pub(crate) fn use_parallel_download(&self, asset: &GitHubAsset) -> bool {
+        self.config.parallel_download && self.config.download_connections > 1 && asset.size >= MIN_PARALLEL_SIZE
+    }
+
+    /// Download `asset` to `dest_path` with one ranged request per connection, each writing its
+    /// own region of the preallocated file
+    ///
+    /// The first range is requested alone; a server that answers it with the whole file (200)
+    /// doesn't support ranges, and false is returned without writing anything so the caller can
+    /// fall back to a single stream. A failed or cancelled download deletes the file.
+    pub(crate) async fn download_in_chunks(&self, asset: &GitHubAsset, dest_path: &Path, cancel: &CancellationToken) -> Result<bool> {
+        let ranges = split_ranges(asset.size, self.config.download_connections);
+        let (first_start, first_end) = ranges[0];
+        let first = self.request_asset_range(asset, first_start, Some(first_end)).await?;
+        if first.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            self.observer.message(format_args!(
+                "The server doesn't support ranged requests; downloading {} over one connection",
+                asset.name
+            ));
+            return Ok(false);
+        }
+
+        let file = std::fs::File::create(dest_path).context(format!(
+            "Failed to create file at '{}'. Check disk space and write permissions.",
+            dest_path.display()
+        ))?;
+        preflight::preallocate(&file, asset.size).context(format!(
+            "Not enough disk space to download '{}' ({} bytes) to '{}'.",
+            asset.name,
+            asset.size,
+            dest_path.display()
+        ))?;
+        file.set_len(asset.size)?;
+        drop(file);
+
+        self.observer.message(format_args!("Downloading {} over {} connections...", asset.name, ranges.len()));
+        let progress = SharedProgress { downloaded: AtomicU64::new(0), rate: Mutex::new(TransferRate::new()) };
+        self.broadcast_download(0, asset.size, &mut progress.rate.lock().unwrap());
+
+        // Split the speed cap between the connections so their sum stays under it
+        let limit = self.config.max_download_bytes_per_sec.map(|limit| (limit / ranges.len() as u64).max(1));
+        let mut first = Some(first);
+        let workers = ranges.iter().map(|&(start, end)| {
+            let response = first.take();
+            self.download_range(asset, dest_path, (start, end), response, &progress, limit, cancel)
+        });
+
+        if let Err(e) = futures::future::try_join_all(workers).await {
+            let _ = std::fs::remove_file(dest_path);
+            return Err(e);
+        }
+
+        self.broadcast_progress(State::Downloading, 1.0);
+        Ok(true)
+    }
+
+    /// Write the inclusive byte range `start..=end` of `asset` into place in `dest_path`, using
+    /// `response` if the request was already sent
+    #[allow(clippy::too_many_arguments)]
+    async fn download_range(
+        &self,
+        asset: &GitHubAsset,
+        dest_path: &Path,
+        (start, end): (u64, u64),
+        response: Option<reqwest::Response>,
+        progress: &SharedProgress,
+        limit: Option<u64>,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        use futures::StreamExt;
+        use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+        let response = match response {
+            Some(response) => response,
+            None => self.request_asset_range(asset, start, Some(end)).await?,
+        };
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            anyhow::bail!("The server stopped honoring ranged requests for '{}' (status {})", asset.name, response.status());
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(dest_path)
+            .await
+            .context(format!("Failed to open '{}'", dest_path.display()))?;
+        file.seek(SeekFrom::Start(start)).await?;
+
+        let expected = end - start + 1;
+        let mut received: u64 = 0;
+        let mut throttle = Throttle::new(limit);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            cancel.check()?;
+            let chunk = chunk.context(format!(
+                "Network error while downloading '{}'. The connection may have been interrupted.",
+                asset.name
+            ))?;
+            let len = chunk.len() as u64;
+            if received + len > expected {
+                anyhow::bail!("The server sent more than the requested range of '{}'", asset.name);
+            }
+
+            file.write_all(&chunk)
+                .await
+                .context(format!("Failed to write to '{}'. Check available disk space.", dest_path.display()))?;
+            received += len;
+
+            let done = progress.downloaded.fetch_add(len, Ordering::Relaxed) + len;
+            {
+                let mut rate = progress.rate.lock().unwrap();
+                rate.record(len);
+                self.broadcast_download(done, asset.size, &mut rate);
+            }
+            throttle.pace(len, cancel).await;
+        }
+        file.flush().await.context(format!("Failed to write to '{}'.", dest_path.display()))?;
+
+        if received != expected {
+            anyhow::bail!(
+                "Bytes {}-{} of '{}' ended after {} of {} bytes",
+                start,
+                end,
+                asset.name,
+                received,
+                expected
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Split `size` bytes into up to `connections` inclusive ranges of near-equal length
+fn split_ranges(size: u64, connections: usize) -> Vec<(u64, u64)> {
+    let count = (connections.max(1) as u64).min(size.max(1));
+    let chunk = size.div_ceil(count);
+    (0..count)
+        .map(|i| (i * chunk, ((i + 1) * chunk).min(size).saturating_sub(1)))
+        .filter(|(start, end)| start <= end && *start < size)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InstallationConfig;
+    use sha2::{Digest, Sha256};
+    use std::io::{Read, Write};
+    use std::path::PathBuf;
+
+    /// Serve `content` over HTTP, honoring `Range: bytes=a-b` unless `ranges` is false
+    fn serve(content: Vec<u8>, ranges: bool) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/app.tar.gz", listener.local_addr().unwrap());
+        let content = std::sync::Arc::new(content);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let content = content.clone();
+                std::thread::spawn(move || {
+                    let mut request = Vec::new();
+                    let mut byte = [0; 1];
+                    while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap_or(0) == 1 {
+                        request.push(byte[0]);
+                    }
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let range = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim().split_once('-'))
+                        .map(|(start, end)| (start.parse::<usize>().unwrap(), end.parse::<usize>().unwrap()));
+
+                    let (status, body) = match range.filter(|_| ranges) {
+                        Some((start, end)) => ("206 Partial Content", &content[start..=end]),
+                        None => ("200 OK", &content[..]),
+                    };
+                    let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+                    let _ = stream.write_all(body);
+                });
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_split_ranges() {
+        assert_eq!(split_ranges(10, 3), [(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(split_ranges(2, 4), [(0, 0), (1, 1)]);
+        assert_eq!(split_ranges(8, 1), [(0, 7)]);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_download_reassembles_file() {
+        let content: Vec<u8> = (0..MIN_PARALLEL_SIZE + 12345).map(|i| (i * 7 % 251) as u8).collect();
+        let digest = format!("{:x}", Sha256::digest(&content));
+        let dir = tempfile::tempdir().unwrap();
+
+        for ranges in [true, false] {
+            let asset = GitHubAsset {
+                name: "app.tar.gz".to_string(),
+                browser_download_url: serve(content.clone(), ranges),
+                size: content.len() as u64,
+                digest: None,
+                content_type: None,
+                state: None,
+                updated_at: None,
+            };
+            let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string())
+                .parallel_download(true)
+                .download_connections(4);
+            let manager = InstallationManager::new(config);
+            assert!(manager.use_parallel_download(&asset));
+
+            // A server without range support falls back to one stream
+            let dest = dir.path().join(format!("app-{}.tar.gz", ranges));
+            manager.download_asset(&asset, &dest, &CancellationToken::new()).await.unwrap();
+            assert_eq!(crate::cache::sha256_file(&dest).unwrap(), digest, "ranges: {}", ranges);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+/// Removes a directory and everything in it when dropped, unless disarmed first
+///
+/// Holding one across an operation makes every early return clean up after it, whether the
+/// operation failed with `?` or was cancelled.
+pub(crate) struct DirGuard {
+    path: PathBuf,
+    /// Empty parent directories created for `path`, deepest first, removed after it
+    created_parents: Vec<PathBuf>,
+    armed: bool,
+}
+
+impl DirGuard {
+    /// Remove `path` when the guard is dropped
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            created_parents: Vec::new(),
+            armed: true,
+        }
+    }
+
+    /// Remove the directories in `created` (as recorded by `missing_ancestors`, deepest first)
+    /// when the guard is dropped; the first with everything in it, the others only while empty
+    pub fn for_created_dirs(created: &[PathBuf]) -> Option<Self> {
+        let (path, parents) = created.split_first()?;
+        Some(Self {
+            path: path.clone(),
+            created_parents: parents.to_vec(),
+            armed: true,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keep the directory; the operation succeeded
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for DirGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let _ = std::fs::remove_dir_all(&self.path);
+        crate::uninstall_manifest::remove_created_dirs(&self.created_parents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_guard() {
+        let dir = tempfile::tempdir().unwrap();
+        let created = crate::uninstall_manifest::missing_ancestors(&dir.path().join("opt/app"));
+        std::fs::create_dir_all(dir.path().join("opt/app/bin")).unwrap();
+        std::fs::write(dir.path().join("opt/app/bin/server"), b"partial").unwrap();
+
+        drop(DirGuard::for_created_dirs(&created).unwrap());
+        assert!(!dir.path().join("opt").exists());
+
+        let kept = dir.path().join("kept");
+        std::fs::create_dir(&kept).unwrap();
+        DirGuard::new(kept.clone()).disarm();
+        assert!(kept.exists());
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io::Read;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Timeout applied to every external command the library runs
+pub(crate) const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Extra attempts made for idempotent commands that fail
+pub(crate) const COMMAND_RETRIES: u32 = 2;
+
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Captured result of a finished command
+#[derive(Debug, Clone)]
+pub(crate) struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Error returned when an external command exits unsuccessfully or times out
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<CommandError>()`.
+#[derive(Debug, Clone)]
+pub struct CommandError {
+    /// The program and arguments that were run
+    pub command: String,
+    /// Exit status, or `None` if the command was killed after timing out
+    pub status: Option<ExitStatus>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "`{}` failed ({})", self.command, status)?,
+            None => write!(f, "`{}` timed out and was killed", self.command)?,
+        }
+        if !self.stdout.is_empty() {
+            write!(f, "\nstdout:\n{}", self.stdout)?;
+        }
+        if !self.stderr.is_empty() {
+            write!(f, "\nstderr:\n{}", self.stderr)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Run `cmd` to completion, retrying up to `retries` more times if it fails
+///
+/// Fails with `CommandError` on a non-zero exit or when the command outlives `timeout`.
+pub(crate) fn run_command_with(cmd: &mut Command, timeout: Duration, retries: u32) -> Result<CommandOutput> {
+    let mut attempt = 0;
+    loop {
+        let result = capture_command(cmd, timeout).and_then(|output| {
+            if output.status.success() {
+                Ok(output)
+            } else {
+                Err(CommandError {
+                    command: describe(cmd),
+                    status: Some(output.status),
+                    stdout: output.stdout,
+                    stderr: output.stderr,
+                }
+                .into())
+            }
+        });
+
+        match result {
+            Err(_) if attempt < retries => {
+                attempt += 1;
+                std::thread::sleep(RETRY_DELAY * attempt);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Run `cmd` once and capture its output whatever its exit status
+///
+/// Only fails if the command can't be spawned or outlives `timeout`, in which case it is killed.
+pub(crate) fn capture_command(cmd: &mut Command, timeout: Duration) -> Result<CommandOutput> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!("Failed to run `{}`", describe(cmd)))?;
+
+    // Drain both pipes on their own threads so a chatty child can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    match status {
+        Some(status) => Ok(CommandOutput { status, stdout, stderr }),
+        None => Err(CommandError {
+            command: describe(cmd),
+            status: None,
+            stdout,
+            stderr,
+        }
+        .into()),
+    }
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        String::from_utf8_lossy(&buffer).trim().to_string()
+    })
+}
+
+fn describe(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_is_structured() {
+        let err = run_command_with(
+            Command::new("sh").args(["-c", "echo out; echo err >&2; exit 3"]),
+            COMMAND_TIMEOUT,
+            0,
+        )
+        .unwrap_err();
+
+        let err = err.downcast_ref::<CommandError>().unwrap();
+        assert_eq!(err.status.and_then(|s| s.code()), Some(3));
+        assert_eq!(err.stdout, "out");
+        assert_eq!(err.stderr, "err");
+    }
+
+    #[test]
+    fn test_timeout_kills_child() {
+        let started = Instant::now();
+        let err = capture_command(Command::new("sleep").arg("10"), Duration::from_millis(200)).unwrap_err();
+
+        assert!(err.downcast_ref::<CommandError>().unwrap().status.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_retries_until_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("attempted");
+        let script = format!("[ -f '{0}' ] || {{ touch '{0}'; exit 1; }}", marker.display());
+
+        let output = run_command_with(Command::new("sh").args(["-c", &script]), COMMAND_TIMEOUT, 1).unwrap();
+        assert!(output.status.success());
+    }
+}
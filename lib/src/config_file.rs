@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::{InstallError, InstallationConfig};
+
+impl InstallationConfig {
+    /// Load a configuration from a TOML file such as `oim.toml`
+    ///
+    /// Fields other than `install_path`, `github_repo` and `service_name` may be left out and
+    /// take their usual defaults.
+    pub fn from_toml_path(path: impl AsRef<Path>) -> Result<Self, InstallError> {
+        let path = path.as_ref();
+        let config: Self = toml::from_str(&read(path)?)
+            .with_context(|| format!("Failed to parse configuration file '{}'", path.display()))?;
+        Ok(config.validated(path)?)
+    }
+
+    /// Load a configuration from a JSON file
+    ///
+    /// Fields other than `install_path`, `github_repo` and `service_name` may be left out and
+    /// take their usual defaults.
+    pub fn from_json_path(path: impl AsRef<Path>) -> Result<Self, InstallError> {
+        let path = path.as_ref();
+        let config: Self = serde_json::from_str(&read(path)?)
+            .with_context(|| format!("Failed to parse configuration file '{}'", path.display()))?;
+        Ok(config.validated(path)?)
+    }
+
+    /// Write the configuration to `path`, as TOML for a `.toml` extension and JSON otherwise
+    ///
+    /// The GitHub token is never written.
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<(), InstallError> {
+        let path = path.as_ref();
+        let content = if is_toml(path) {
+            toml::to_string_pretty(self).context("Failed to serialize the configuration as TOML")?
+        } else {
+            serde_json::to_string_pretty(self).context("Failed to serialize the configuration as JSON")?
+        };
+
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write configuration file '{}'", path.display()))?;
+        Ok(())
+    }
+
+    /// Reject a loaded configuration whose required fields are empty or malformed
+    fn validated(self, path: &Path) -> Result<Self> {
+        let mut problems = Vec::new();
+        if self.install_path.as_os_str().is_empty() {
+            problems.push("install_path must not be empty".to_string());
+        }
+        let repo_parts: Vec<&str> = self.github_repo.split('/').collect();
+        if repo_parts.len() != 2 || repo_parts.iter().any(|part| part.trim().is_empty()) {
+            problems.push(format!("github_repo must look like \"owner/repo\", got \"{}\"", self.github_repo));
+        }
+        if self.service_name.trim().is_empty() {
+            problems.push("service_name must not be empty".to_string());
+        }
+        if let Err(e) = self.validate_urls() {
+            problems.push(e.to_string());
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("Invalid configuration file '{}': {}", path.display(), problems.join("; "));
+        }
+        Ok(self)
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read configuration file '{}'", path.display()))
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_config_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string())
+            .binary_name("app-server".to_string())
+            .asset_filter(regex::Regex::new("-release").unwrap())
+            .data_dir(PathBuf::from("worlds"))
+            .github_token("secret".to_string());
+
+        for file_name in ["oim.toml", "oim.json"] {
+            let path = dir.path().join(file_name);
+            config.save_to_path(&path).unwrap();
+            assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+
+            let loaded = if file_name.ends_with(".toml") {
+                InstallationConfig::from_toml_path(&path).unwrap()
+            } else {
+                InstallationConfig::from_json_path(&path).unwrap()
+            };
+            assert_eq!(loaded.install_path, config.install_path);
+            assert_eq!(loaded.binary_name.as_deref(), Some("app-server"));
+            assert_eq!(loaded.asset_filter.unwrap().as_str(), "-release");
+            assert_eq!(loaded.data_dirs, [PathBuf::from("worlds")]);
+            assert_eq!(loaded.stop_timeout, config.stop_timeout);
+            assert!(loaded.github_token.is_none());
+        }
+    }
+
+    #[test]
+    fn test_config_file_requires_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oim.toml");
+
+        std::fs::write(&path, "install_path = \"/opt/app\"\nservice_name = \"app\"\n").unwrap();
+        let err = InstallationConfig::from_toml_path(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("github_repo"));
+
+        std::fs::write(&path, "install_path = \"/opt/app\"\ngithub_repo = \"repo\"\nservice_name = \"\"\n").unwrap();
+        let err = InstallationConfig::from_toml_path(&path).unwrap_err().to_string();
+        assert!(err.contains("owner/repo") && err.contains("service_name"));
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use semver::Version;
+use std::io::Read;
+use std::path::Path;
+
+use crate::{cache, checksum, CancellationToken, GitHubAsset, GitHubRelease, InstallationManager};
+
+impl InstallationManager {
+    /// Rebuild `asset` at `dest_path` from the installed version's archive and a patch asset,
+    /// returning false when no patch applies so the caller downloads it in full
+    ///
+    /// A patch is a `zstd --patch-from=<old archive>` file named `<asset>.from-<installed version>.zst`
+    /// (the version with or without a leading `v`). The old archive is taken from the download
+    /// cache, so this needs `cache_dir`, and the asset needs a digest to check the rebuilt
+    /// archive against; a rebuilt archive that doesn't match is discarded with a warning.
+    pub(crate) async fn fetch_by_patch(
+        &self,
+        release: &GitHubRelease,
+        asset: &GitHubAsset,
+        dest_path: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<bool> {
+        let (Some(cache_dir), Some(expected), Some(installed)) =
+            (&self.config.cache_dir, asset.checksum(), &self.current_version)
+        else {
+            return Ok(false);
+        };
+        let Some(patch) = find_patch(release, asset, installed) else {
+            return Ok(false);
+        };
+
+        // The patch is only useful against the exact archive the installed version came from
+        let previous = match self.get_release_by_tag(&installed.to_string()).await {
+            Ok(previous) => previous,
+            Err(_) => return Ok(false),
+        };
+        let Ok(base_asset) = self.select_asset(&previous) else {
+            return Ok(false);
+        };
+        let base_path = dest_path.with_extension("base");
+        let cache = cache::DownloadCache::new(cache_dir, self.config.cache_max_bytes);
+        if !cache.restore(&base_asset, &base_path)? {
+            return Ok(false);
+        }
+
+        self.observer.message(format_args!("Downloading {} to update from {}...", patch.name, installed));
+        let patch_path = dest_path.with_extension("patch");
+        let patched = async {
+            self.download_asset(patch, &patch_path, cancel).await?;
+            let (base, patch, dest) = (base_path.clone(), patch_path.clone(), dest_path.to_path_buf());
+            let size = asset.size;
+            tokio::task::spawn_blocking(move || apply_patch(&base, &patch, &dest, size)).await??;
+            checksum::verify_file(dest_path, &asset.name, &expected)
+        }
+        .await;
+        let _ = std::fs::remove_file(&base_path);
+        let _ = std::fs::remove_file(&patch_path);
+
+        match patched {
+            Ok(()) => {
+                if let Err(e) = cache.store(asset, dest_path) {
+                    self.observer.warning(format_args!("Failed to store {} in download cache: {:#}", asset.name, e));
+                }
+                Ok(true)
+            }
+            // A cancelled download shouldn't turn into a full one
+            Err(e) if cancel.is_cancelled() => Err(e),
+            Err(e) => {
+                self.observer.warning(format_args!("Failed to apply {}, downloading {} in full: {:#}", patch.name, asset.name, e));
+                let _ = std::fs::remove_file(dest_path);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// The uploaded patch asset in `release` that turns the `installed` version's archive into `asset`
+fn find_patch<'a>(release: &'a GitHubRelease, asset: &GitHubAsset, installed: &Version) -> Option<&'a GitHubAsset> {
+    let names = [
+        format!("{}.from-{}.zst", asset.name, installed),
+        format!("{}.from-v{}.zst", asset.name, installed),
+    ];
+    release
+        .assets
+        .iter()
+        .find(|candidate| candidate.is_uploaded() && names.contains(&candidate.name))
+}
+
+/// Write the archive `patch` rebuilds from `base` to `dest`, failing unless it is exactly `size` bytes
+///
+/// The whole base archive is held in memory, as zstd needs it as one contiguous reference.
+fn apply_patch(base: &Path, patch: &Path, dest: &Path, size: u64) -> Result<()> {
+    let base = std::fs::read(base).context(format!("Failed to read '{}'", base.display()))?;
+    let patch_file = std::fs::File::open(patch).context(format!("Failed to open '{}'", patch.display()))?;
+
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(std::io::BufReader::new(patch_file), &base)?;
+    // `--patch-from` sizes the window to the old archive, beyond the decoder's default limit
+    decoder.window_log_max(if cfg!(target_pointer_width = "64") { 31 } else { 30 })?;
+
+    let mut output = std::fs::File::create(dest).context(format!("Failed to create '{}'", dest.display()))?;
+    // Read one byte past the expected size, so an oversized result is noticed without writing it all
+    let written = std::io::copy(&mut decoder.take(size + 1), &mut output).context("Failed to apply the patch")?;
+    if written != size {
+        anyhow::bail!("The patched archive is {} bytes instead of {}", written, size);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        let old: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut new = old.clone();
+        new[1000..1010].copy_from_slice(b"new bytes!");
+        new.extend_from_slice(b"appended");
+
+        // What `zstd --patch-from=old new` produces
+        let mut encoder = zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 19, &old).unwrap();
+        std::io::Write::write_all(&mut encoder, &new).unwrap();
+        let patch = encoder.finish().unwrap();
+        assert!(patch.len() < new.len() / 10);
+
+        let (base_path, patch_path, dest) = (dir.path().join("old"), dir.path().join("patch"), dir.path().join("new"));
+        std::fs::write(&base_path, &old).unwrap();
+        std::fs::write(&patch_path, &patch).unwrap();
+
+        apply_patch(&base_path, &patch_path, &dest, new.len() as u64).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), new);
+        assert!(apply_patch(&base_path, &patch_path, &dest, 100).is_err());
+    }
+
+    #[test]
+    fn test_find_patch() {
+        let asset = |name: &str| GitHubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let release = GitHubRelease {
+            tag_name: "v1.3.0".to_string(),
+            name: "Release 1.3.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![asset("app.tar.gz"), asset("app.tar.gz.from-v1.1.0.zst"), asset("app.tar.gz.from-1.2.0.zst")],
+        };
+        let installed = Version::new(1, 2, 0);
+        assert_eq!(find_patch(&release, &release.assets[0], &installed).unwrap().name, "app.tar.gz.from-1.2.0.zst");
+        assert_eq!(find_patch(&release, &release.assets[0], &Version::new(1, 1, 0)).unwrap().name, "app.tar.gz.from-v1.1.0.zst");
+        assert!(find_patch(&release, &release.assets[0], &Version::new(1, 0, 0)).is_none());
+    }
+}
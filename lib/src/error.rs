@@ -0,0 +1,311 @@
+use std::fmt;
+
+use crate::archive::{DecompressionLimitExceeded, UnsafeArchiveEntry};
+use crate::binaries::BinaryNotFound;
+use crate::cancel::Cancelled;
+use crate::checksum::ChecksumMismatch;
+use crate::command::CommandError;
+use crate::health::HealthCheckFailed;
+use crate::preflight::{InsufficientInodes, InsufficientSpace};
+use crate::retry::TransientStatus;
+use crate::service_manager::ServiceManagerUnavailable;
+use crate::signature::SignatureInvalid;
+
+/// Error returned by the public operations of this library, grouped by what went wrong
+///
+/// Every variant wraps the full error chain, so `to_string()` reads the same as before and the
+/// detailed error types (`ChecksumMismatch`, `OperationInProgress`, `CommandError`, ...) can
+/// still be retrieved with `err.downcast_ref::<T>()`.
+#[derive(Debug, thiserror::Error)]
+pub enum InstallError {
+    /// A request to GitHub or a download failed at the network level, including a proxy that
+    /// couldn't be reached (`ProxyUnreachable`); usually worth retrying
+    #[error(transparent)]
+    Network(anyhow::Error),
+    /// The release or an asset for this platform couldn't be found
+    #[error(transparent)]
+    AssetNotFound(anyhow::Error),
+    /// The asset isn't an archive this build can extract, or the archive is malformed or unsafe
+    #[error(transparent)]
+    UnsupportedArchive(anyhow::Error),
+    /// The extracted release doesn't contain the expected executable, or it can't be run
+    /// (`BinaryNotFound`); checked before the service is registered
+    #[error(transparent)]
+    BinaryNotFound(anyhow::Error),
+    /// A downloaded file didn't match its expected checksum
+    #[error(transparent)]
+    ChecksumMismatch(anyhow::Error),
+    /// A signing key is configured and the asset's signature is missing or doesn't verify
+    #[error(transparent)]
+    SignatureInvalid(anyhow::Error),
+    /// The service manager (systemd, launchd, Windows SCM) or the service itself failed
+    #[error(transparent)]
+    ServiceFailure(anyhow::Error),
+    /// The service started but didn't pass its configured health check in time
+    #[error(transparent)]
+    HealthCheckFailed(anyhow::Error),
+    /// Reading or writing files failed
+    #[error(transparent)]
+    Io(anyhow::Error),
+    /// The download or extraction wouldn't fit in the free space on its volume
+    #[error(transparent)]
+    InsufficientSpace(anyhow::Error),
+    /// A version string couldn't be parsed as semver
+    #[error(transparent)]
+    VersionParse(anyhow::Error),
+    /// The operation was stopped through its `CancellationToken`
+    #[error(transparent)]
+    Cancelled(anyhow::Error),
+    /// Anything else, such as a channel lock mismatch or an operation already in progress
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl InstallError {
+    /// Whether retrying the same operation may succeed (only network failures)
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, InstallError::Network(_))
+    }
+
+    /// Look for an error of type `E` anywhere in the chain
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        let inner = self.inner();
+        inner.downcast_ref::<E>().or_else(|| {
+            // An error from one public operation may have been wrapped by another
+            inner
+                .chain()
+                .filter_map(|e| e.downcast_ref::<InstallError>())
+                .find_map(|nested| nested.downcast_ref::<E>())
+        })
+    }
+
+    /// The wrapped error chain
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            InstallError::Network(e)
+            | InstallError::AssetNotFound(e)
+            | InstallError::UnsupportedArchive(e)
+            | InstallError::BinaryNotFound(e)
+            | InstallError::ChecksumMismatch(e)
+            | InstallError::SignatureInvalid(e)
+            | InstallError::ServiceFailure(e)
+            | InstallError::HealthCheckFailed(e)
+            | InstallError::Io(e)
+            | InstallError::InsufficientSpace(e)
+            | InstallError::VersionParse(e)
+            | InstallError::Cancelled(e)
+            | InstallError::Other(e) => e,
+        }
+    }
+
+    /// The variant of `self`, for wrapping another error the same way
+    fn constructor(&self) -> fn(anyhow::Error) -> InstallError {
+        match self {
+            InstallError::Network(_) => InstallError::Network,
+            InstallError::AssetNotFound(_) => InstallError::AssetNotFound,
+            InstallError::UnsupportedArchive(_) => InstallError::UnsupportedArchive,
+            InstallError::BinaryNotFound(_) => InstallError::BinaryNotFound,
+            InstallError::ChecksumMismatch(_) => InstallError::ChecksumMismatch,
+            InstallError::SignatureInvalid(_) => InstallError::SignatureInvalid,
+            InstallError::ServiceFailure(_) => InstallError::ServiceFailure,
+            InstallError::HealthCheckFailed(_) => InstallError::HealthCheckFailed,
+            InstallError::Io(_) => InstallError::Io,
+            InstallError::InsufficientSpace(_) => InstallError::InsufficientSpace,
+            InstallError::VersionParse(_) => InstallError::VersionParse,
+            InstallError::Cancelled(_) => InstallError::Cancelled,
+            InstallError::Other(_) => InstallError::Other,
+        }
+    }
+}
+
+impl From<anyhow::Error> for InstallError {
+    fn from(error: anyhow::Error) -> Self {
+        // Keep the classification of an error that already passed through a public operation
+        let nested = error.chain().find_map(|e| e.downcast_ref::<InstallError>()).map(InstallError::constructor);
+        if let Some(constructor) = nested {
+            return constructor(error);
+        }
+
+        let has = |check: fn(&(dyn std::error::Error + 'static)) -> bool| error.chain().any(check);
+
+        if has(|e| e.is::<Cancelled>()) {
+            InstallError::Cancelled(error)
+        } else if has(|e| e.is::<InsufficientSpace>() || e.is::<InsufficientInodes>()) {
+            InstallError::InsufficientSpace(error)
+        } else if has(|e| e.is::<HealthCheckFailed>()) {
+            InstallError::HealthCheckFailed(error)
+        } else if has(|e| e.is::<ChecksumMismatch>()) {
+            InstallError::ChecksumMismatch(error)
+        } else if has(|e| e.is::<SignatureInvalid>()) {
+            InstallError::SignatureInvalid(error)
+        } else if has(|e| e.is::<BinaryNotFound>()) {
+            InstallError::BinaryNotFound(error)
+        } else if has(|e| e.is::<AssetNotFound>()) {
+            InstallError::AssetNotFound(error)
+        } else if has(|e| {
+            e.is::<UnsupportedArchive>()
+                || e.is::<DecompressionLimitExceeded>()
+                || e.is::<UnsafeArchiveEntry>()
+                || e.is::<zip::result::ZipError>()
+        }) {
+            InstallError::UnsupportedArchive(error)
+        } else if has(|e| e.is::<reqwest::Error>() || e.is::<RateLimitExceeded>() || e.is::<TransientStatus>()) {
+            InstallError::Network(error)
+        } else if has(|e| e.is::<CommandError>() || e.is::<ServiceManagerUnavailable>() || e.is::<ServiceFailure>()) {
+            InstallError::ServiceFailure(error)
+        } else if has(|e| e.is::<semver::Error>()) {
+            InstallError::VersionParse(error)
+        } else if has(|e| e.is::<std::io::Error>()) {
+            InstallError::Io(error)
+        } else {
+            InstallError::Other(error)
+        }
+    }
+}
+
+macro_rules! from_source {
+    ($($source:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$source> for InstallError {
+                fn from(error: $source) -> Self {
+                    InstallError::$variant(error.into())
+                }
+            }
+        )*
+    };
+}
+
+from_source! {
+    std::io::Error => Io,
+    reqwest::Error => Network,
+    RateLimitExceeded => Network,
+    semver::Error => VersionParse,
+    Cancelled => Cancelled,
+    AssetNotFound => AssetNotFound,
+    UnsupportedArchive => UnsupportedArchive,
+    BinaryNotFound => BinaryNotFound,
+    InsufficientSpace => InsufficientSpace,
+    ServiceFailure => ServiceFailure,
+    HealthCheckFailed => HealthCheckFailed,
+    SignatureInvalid => SignatureInvalid,
+}
+
+/// Error returned when GitHub refuses a request because the API rate limit is used up
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<RateLimitExceeded>()`.
+/// Unauthenticated requests are limited to 60 per hour; set `github_token` (or the
+/// `GITHUB_TOKEN` environment variable) for a much higher limit.
+#[derive(Debug, Clone)]
+pub struct RateLimitExceeded {
+    /// When the limit resets, if GitHub said
+    pub reset_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether the request carried a GitHub token
+    pub authenticated: bool,
+}
+
+impl RateLimitExceeded {
+    /// Recognize a rate-limit rejection from GitHub's `x-ratelimit-*` response headers
+    pub(crate) fn from_headers(headers: &reqwest::header::HeaderMap, authenticated: bool) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        if header("x-ratelimit-remaining")? != "0" {
+            return None;
+        }
+
+        let reset_at = header("x-ratelimit-reset")
+            .and_then(|reset| reset.parse::<i64>().ok())
+            .and_then(|reset| chrono::DateTime::from_timestamp(reset, 0));
+        Some(Self { reset_at, authenticated })
+    }
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GitHub API rate limit exceeded")?;
+        if let Some(reset_at) = self.reset_at {
+            write!(f, "; it resets at {}", reset_at.format("%Y-%m-%d %H:%M:%S UTC"))?;
+        }
+        if !self.authenticated {
+            write!(f, ". Set a GitHub token (github_token or the GITHUB_TOKEN environment variable) to raise the limit")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimitExceeded {}
+
+/// No release or asset matched what was asked for
+#[derive(Debug)]
+pub(crate) struct AssetNotFound(pub String);
+
+/// The asset or archive isn't in a format this build can extract
+#[derive(Debug)]
+pub(crate) struct UnsupportedArchive(pub String);
+
+/// The service didn't come up or stop as expected
+#[derive(Debug)]
+pub(crate) struct ServiceFailure(pub String);
+
+macro_rules! message_error {
+    ($($name:ident),*) => {
+        $(
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str(&self.0)
+                }
+            }
+
+            impl std::error::Error for $name {}
+        )*
+    };
+}
+
+message_error!(AssetNotFound, UnsupportedArchive, ServiceFailure);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classification_survives_nesting() {
+        let mismatch = ChecksumMismatch {
+            asset: "app.zip".to_string(),
+            expected: crate::Checksum::Sha256([0; 32]),
+            actual: crate::Checksum::Sha256([1; 32]),
+        };
+        let first: InstallError = anyhow::Error::from(mismatch).context("Failed to fetch app.zip").into();
+        assert!(matches!(first, InstallError::ChecksumMismatch(_)));
+
+        // Passed back through an internal anyhow error and out of another public operation
+        let second: InstallError = Err::<(), _>(first).context("Install failed").unwrap_err().into();
+        assert!(matches!(second, InstallError::ChecksumMismatch(_)));
+        assert!(second.downcast_ref::<ChecksumMismatch>().is_some());
+        assert!(second.to_string().starts_with("Install failed"));
+
+        let io: InstallError = std::io::Error::other("disk on fire").into();
+        assert!(matches!(io, InstallError::Io(_)));
+        assert!(!io.is_retryable());
+    }
+
+    #[test]
+    fn test_rate_limit_from_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("12"));
+        assert!(RateLimitExceeded::from_headers(&headers, false).is_none());
+
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1700000000"));
+        let limited = RateLimitExceeded::from_headers(&headers, false).unwrap();
+        assert_eq!(limited.reset_at.unwrap().timestamp(), 1_700_000_000);
+        assert!(limited.to_string().contains("GITHUB_TOKEN"));
+        assert!(!RateLimitExceeded::from_headers(&headers, true).unwrap().to_string().contains("GITHUB_TOKEN"));
+
+        let error: InstallError = anyhow::Error::from(limited).into();
+        assert!(error.is_retryable());
+    }
+}
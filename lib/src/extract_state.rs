@@ -0,0 +1,209 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// File in the extraction directory listing the entries written so far, while an extraction
+/// is running or after it was interrupted
+pub(crate) const STATE_FILE_NAME: &str = ".oim-extract-state";
+
+/// First line of the state file: the archive being extracted
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Header {
+    archive: String,
+    archive_size: u64,
+}
+
+/// One line per extracted file, with its size and modification time once written
+#[derive(Debug, Serialize, Deserialize)]
+struct Written {
+    path: PathBuf,
+    size: u64,
+    mtime_ns: u64,
+}
+
+/// Records which entries of an archive have been extracted, so an interrupted extraction of
+/// the same archive can skip them instead of starting over
+///
+/// Only regular files are recorded; directories and symlinks are cheap to create again. A file
+/// counts as extracted only while its size and modification time match what was recorded, so
+/// anything changed or truncated since is written again.
+pub(crate) struct ExtractState {
+    extract_to: PathBuf,
+    journal: Option<std::fs::File>,
+    written: HashMap<PathBuf, (u64, u64)>,
+}
+
+impl ExtractState {
+    /// A state that records nothing and skips nothing, for extractions that can't be resumed
+    pub fn disabled() -> Self {
+        Self {
+            extract_to: PathBuf::new(),
+            journal: None,
+            written: HashMap::new(),
+        }
+    }
+
+    /// Start recording the extraction of `archive` into `extract_to`, keeping what an
+    /// interrupted extraction of the same archive (same file name and size) recorded
+    pub fn open(extract_to: &Path, archive: &Path) -> Result<Self> {
+        let header = Header {
+            archive: archive.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            archive_size: std::fs::metadata(archive)?.len(),
+        };
+        let path = extract_to.join(STATE_FILE_NAME);
+        let written = read_written(&path, &header).unwrap_or_default();
+
+        let journal = if written.is_empty() {
+            let mut journal = std::fs::File::create(&path).context(format!("Failed to create '{}'", path.display()))?;
+            writeln!(journal, "{}", serde_json::to_string(&header)?)?;
+            journal
+        } else {
+            std::fs::OpenOptions::new().append(true).open(&path)?
+        };
+
+        Ok(Self {
+            extract_to: extract_to.to_path_buf(),
+            journal: Some(journal),
+            written,
+        })
+    }
+
+    /// Whether `destination` was extracted by the interrupted run and hasn't changed since
+    pub fn is_extracted(&self, destination: &Path) -> bool {
+        let Ok(relative) = destination.strip_prefix(&self.extract_to) else {
+            return false;
+        };
+        match (self.written.get(relative), file_stamp(destination)) {
+            (Some(recorded), Some(current)) => *recorded == current,
+            _ => false,
+        }
+    }
+
+    /// Record that the regular file `destination` has been written in full
+    pub fn record(&mut self, destination: &Path) -> Result<()> {
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
+        };
+        let (Ok(relative), Some((size, mtime_ns))) = (destination.strip_prefix(&self.extract_to), file_stamp(destination)) else {
+            return Ok(());
+        };
+        let line = serde_json::to_string(&Written {
+            path: relative.to_path_buf(),
+            size,
+            mtime_ns,
+        })?;
+        // One write per line, so an interruption leaves at most the last line incomplete
+        journal.write_all(format!("{}\n", line).as_bytes())?;
+        Ok(())
+    }
+
+    /// The extraction completed; delete the state file
+    pub fn finish(self) -> Result<()> {
+        if self.journal.is_some() {
+            drop(self.journal);
+            let path = self.extract_to.join(STATE_FILE_NAME);
+            std::fs::remove_file(&path).context(format!("Failed to remove '{}'", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// End the extraction with its `result`, keeping the state file only when it was cancelled
+    ///
+    /// Any other failure deletes it, so a later install doesn't mistake the directory for an
+    /// extraction to resume and skip cleaning it.
+    pub fn conclude(self, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => self.finish(),
+            Err(e) if e.chain().any(|e| e.is::<crate::Cancelled>()) => Err(e),
+            Err(e) => {
+                let _ = self.finish();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Whether an archive entry at `relative` (inside the extraction directory) would overwrite the
+/// state file
+pub(crate) fn is_state_file(relative: &Path) -> bool {
+    relative == Path::new(STATE_FILE_NAME)
+}
+
+/// Whether an interrupted extraction left its state in `extract_to`
+pub(crate) fn is_pending(extract_to: &Path) -> bool {
+    extract_to.join(STATE_FILE_NAME).is_file()
+}
+
+/// The files recorded in the state file at `path`, if it belongs to the archive in `header`
+fn read_written(path: &Path, header: &Header) -> Option<HashMap<PathBuf, (u64, u64)>> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut lines = std::io::BufReader::new(file).lines();
+    let recorded: Header = serde_json::from_str(&lines.next()?.ok()?).ok()?;
+    if recorded != *header {
+        return None;
+    }
+    // A line cut short by the interruption ends the list
+    Some(
+        lines
+            .map_while(|line| serde_json::from_str::<Written>(&line.ok()?).ok())
+            .map(|written| (written.path, (written.size, written.mtime_ns)))
+            .collect(),
+    )
+}
+
+/// Size and modification time of a regular file
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    let mtime = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((metadata.len(), mtime.as_nanos() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_state_resumes_same_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.gz");
+        std::fs::write(&archive, b"archive").unwrap();
+        let extract_to = dir.path().join("app");
+        std::fs::create_dir(&extract_to).unwrap();
+        let (done, changed) = (extract_to.join("done"), extract_to.join("changed"));
+
+        let mut state = ExtractState::open(&extract_to, &archive).unwrap();
+        for file in [&done, &changed] {
+            std::fs::write(file, b"contents").unwrap();
+            state.record(file).unwrap();
+        }
+        drop(state);
+        assert!(is_pending(&extract_to));
+        std::fs::write(&changed, b"edited contents").unwrap();
+
+        let state = ExtractState::open(&extract_to, &archive).unwrap();
+        assert!(state.is_extracted(&done));
+        assert!(!state.is_extracted(&changed));
+        state.finish().unwrap();
+        assert!(!is_pending(&extract_to));
+
+        // A different archive starts over
+        let mut state = ExtractState::open(&extract_to, &archive).unwrap();
+        state.record(&done).unwrap();
+        drop(state);
+        std::fs::write(&archive, b"another archive").unwrap();
+        assert!(!ExtractState::open(&extract_to, &archive).unwrap().is_extracted(&done));
+
+        // Only a cancelled extraction is left to resume
+        let state = ExtractState::open(&extract_to, &archive).unwrap();
+        assert!(state.conclude(Err(crate::Cancelled.into())).is_err());
+        assert!(is_pending(&extract_to));
+        let state = ExtractState::open(&extract_to, &archive).unwrap();
+        assert!(state.conclude(Err(anyhow::anyhow!("corrupt archive"))).is_err());
+        assert!(!is_pending(&extract_to));
+    }
+}
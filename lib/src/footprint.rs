@@ -0,0 +1,169 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{ArchiveFormat, GitHubAsset, InstallError, InstallationManager, ReleaseChannel};
+
+/// The zip end of central directory record, without its trailing comment
+const EOCD_LEN: usize = 22;
+/// The longest comment a zip end of central directory record can carry
+const MAX_ZIP_COMMENT: usize = u16::MAX as usize;
+const CENTRAL_HEADER_LEN: usize = 46;
+
+/// What installing a release takes, as found by `estimate_footprint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Footprint {
+    /// Size of the asset that would be downloaded
+    pub download_bytes: u64,
+    /// Space the extracted files would take up
+    pub estimated_installed_bytes: u64,
+    /// Whether `estimated_installed_bytes` was read from the archive itself rather than guessed
+    /// from the download size
+    pub exact: bool,
+}
+
+impl InstallationManager {
+    /// Work out how much an install would download and how much disk space it would use,
+    /// without downloading the asset
+    ///
+    /// Picks the release `install` would (the Alpha channel's newest with `include_prerelease`,
+    /// else the Release channel's). The installed size is read from the zip central directory
+    /// or the gzip footer with a ranged request for the end of the asset; for other formats,
+    /// or when the server doesn't allow ranged requests, it is the download size times the
+    /// margin the disk-space check uses, and `exact` is false. A gzip footer only holds the
+    /// size modulo 4 GiB, so it is trusted for smaller downloads only.
+    pub async fn estimate_footprint(&mut self, include_prerelease: bool) -> Result<Footprint, InstallError> {
+        let channel = if include_prerelease { ReleaseChannel::Alpha } else { ReleaseChannel::Release };
+        let (_, asset) = self.resolve_release(channel).await?;
+
+        let exact = match asset.format() {
+            Some(ArchiveFormat::Binary) => Some(asset.size),
+            Some(ArchiveFormat::Zip) => self.zip_uncompressed_size(&asset).await.ok().flatten(),
+            Some(ArchiveFormat::TarGz | ArchiveFormat::Gzip) if asset.size < 1 << 32 => {
+                self.asset_tail(&asset, 4).await.ok().flatten().map(|tail| gzip_uncompressed_size(&tail))
+            }
+            _ => None,
+        };
+
+        Ok(Footprint {
+            download_bytes: asset.size,
+            estimated_installed_bytes: exact.unwrap_or_else(|| asset.size.saturating_mul(crate::EXTRACTION_SPACE_MULTIPLIER)),
+            exact: exact.is_some(),
+        })
+    }
+
+    /// Total uncompressed size of the entries in a zip asset, from its central directory
+    async fn zip_uncompressed_size(&self, asset: &GitHubAsset) -> Result<Option<u64>> {
+        let Some(tail) = self.asset_tail(asset, (EOCD_LEN + MAX_ZIP_COMMENT) as u64).await? else {
+            return Ok(None);
+        };
+        let Some((offset, len)) = central_directory_location(&tail) else {
+            return Ok(None);
+        };
+
+        // The central directory usually sits right before the record, inside the tail already fetched
+        let tail_start = asset.size - tail.len() as u64;
+        let directory = if offset >= tail_start {
+            let start = (offset - tail_start) as usize;
+            tail.get(start..start + len as usize).map(<[u8]>::to_vec)
+        } else {
+            self.asset_bytes(asset, offset, len).await?
+        };
+        Ok(directory.and_then(|directory| sum_central_directory(&directory)))
+    }
+
+    /// The last `len` bytes of an asset (all of it when smaller), or None when the server
+    /// doesn't answer the ranged request
+    async fn asset_tail(&self, asset: &GitHubAsset, len: u64) -> Result<Option<Vec<u8>>> {
+        let len = len.min(asset.size);
+        self.asset_bytes(asset, asset.size - len, len).await
+    }
+
+    /// `len` bytes of an asset starting at `offset`, or None when the server doesn't answer the
+    /// ranged request with exactly those bytes
+    async fn asset_bytes(&self, asset: &GitHubAsset, offset: u64, len: u64) -> Result<Option<Vec<u8>>> {
+        if len == 0 {
+            return Ok(None);
+        }
+        let response = self.request_asset_range(asset, offset, Some(offset + len - 1)).await?;
+        // A server that ignores the range would send the whole asset
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Ok(None);
+        }
+        let bytes = response.bytes().await?;
+        Ok((bytes.len() as u64 == len).then(|| bytes.to_vec()))
+    }
+}
+
+/// The uncompressed size a gzip file records in its last four bytes, modulo 4 GiB
+fn gzip_uncompressed_size(tail: &[u8]) -> u64 {
+    let footer: [u8; 4] = tail[tail.len() - 4..].try_into().unwrap();
+    u32::from_le_bytes(footer) as u64
+}
+
+/// Offset and length of the central directory, from the end of central directory record in
+/// `tail`; None for zip64 archives, whose sizes don't fit the record
+fn central_directory_location(tail: &[u8]) -> Option<(u64, u64)> {
+    let record = (0..=tail.len().checked_sub(EOCD_LEN)?)
+        .rev()
+        .find(|&i| tail[i..i + 4] == [0x50, 0x4b, 0x05, 0x06])?;
+    let len = u32::from_le_bytes(tail[record + 12..record + 16].try_into().unwrap());
+    let offset = u32::from_le_bytes(tail[record + 16..record + 20].try_into().unwrap());
+    if len == u32::MAX || offset == u32::MAX {
+        return None;
+    }
+    Some((offset as u64, len as u64))
+}
+
+/// Sum the uncompressed sizes in a zip central directory; None if it is malformed or an entry
+/// has its size in a zip64 extra field
+fn sum_central_directory(directory: &[u8]) -> Option<u64> {
+    let mut total: u64 = 0;
+    let mut pos = 0;
+    while pos < directory.len() {
+        let header = directory.get(pos..pos + CENTRAL_HEADER_LEN)?;
+        if header[..4] != [0x50, 0x4b, 0x01, 0x02] {
+            return None;
+        }
+        let field = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as usize;
+        let size = u32::from_le_bytes(header[24..28].try_into().unwrap());
+        if size == u32::MAX {
+            return None;
+        }
+        total += size as u64;
+        pos += CENTRAL_HEADER_LEN + field(28) + field(30) + field(32);
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_zip_central_directory_size() {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.set_comment("built by ci");
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("bin/myapp", options).unwrap();
+        writer.write_all(&vec![b'a'; 10_000]).unwrap();
+        writer.add_directory("config/", options).unwrap();
+        writer.start_file("config/app.toml", options).unwrap();
+        writer.write_all(b"port = 8080\n").unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        let tail = &archive[archive.len().saturating_sub(EOCD_LEN + MAX_ZIP_COMMENT)..];
+        let (offset, len) = central_directory_location(tail).unwrap();
+        let directory = &archive[offset as usize..(offset + len) as usize];
+        assert_eq!(sum_central_directory(directory), Some(10_012));
+        assert_eq!(sum_central_directory(&directory[1..]), None);
+    }
+
+    #[test]
+    fn test_gzip_uncompressed_size() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&vec![0; 123_456]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(gzip_uncompressed_size(&compressed), 123_456);
+    }
+}
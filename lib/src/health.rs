@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+
+/// Pause between failed probes
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest a single probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How to tell that a started service is actually working
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthProbe {
+    /// Accept a TCP connection on this port of the local machine
+    Tcp { port: u16 },
+    /// Answer a GET to this URL with the expected status code
+    Http { url: String, expected_status: u16 },
+}
+
+impl fmt::Display for HealthProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthProbe::Tcp { port } => write!(f, "TCP port {}", port),
+            HealthProbe::Http { url, expected_status } => write!(f, "GET {} (expecting {})", url, expected_status),
+        }
+    }
+}
+
+/// Probe run after the service starts during install and update, retried until it passes or
+/// `timeout` runs out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub probe: HealthProbe,
+    /// How long the service gets to pass the probe (defaults to 30 seconds)
+    #[serde(default = "default_timeout")]
+    pub timeout: Duration,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl HealthCheck {
+    /// Check that the service accepts connections on a local TCP port
+    pub fn tcp(port: u16) -> Self {
+        Self {
+            probe: HealthProbe::Tcp { port },
+            timeout: default_timeout(),
+        }
+    }
+
+    /// Check that a GET to `url` answers with `expected_status`
+    pub fn http(url: String, expected_status: u16) -> Self {
+        Self {
+            probe: HealthProbe::Http { url, expected_status },
+            timeout: default_timeout(),
+        }
+    }
+
+    /// Set how long the service gets to pass the probe
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Error returned when a started service doesn't pass its health check in time
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<HealthCheckFailed>()`.
+#[derive(Debug, Clone)]
+pub struct HealthCheckFailed {
+    pub service_name: String,
+    pub probe: HealthProbe,
+    pub timeout: Duration,
+    /// Why the last probe failed
+    pub last_error: String,
+}
+
+impl fmt::Display for HealthCheckFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Service '{}' did not pass its health check ({}) within {} seconds: {}",
+            self.service_name,
+            self.probe,
+            self.timeout.as_secs(),
+            self.last_error
+        )
+    }
+}
+
+impl std::error::Error for HealthCheckFailed {}
+
+/// Probe until the check passes, failing with `HealthCheckFailed` once its timeout runs out
+///
+/// An HTTP probe goes through `proxy` unless it targets this machine.
+pub(crate) async fn wait_until_healthy(service_name: &str, check: &HealthCheck, proxy: Option<&str>, cancel: &CancellationToken) -> Result<()> {
+    let builder = reqwest::Client::builder()
+        .user_agent("obsidian-installation-manager")
+        .timeout(PROBE_TIMEOUT);
+    let client = crate::proxy::apply(builder, proxy, true)?
+        .build()
+        .context("Failed to create HTTP client for the health check")?;
+    let deadline = Instant::now() + check.timeout;
+
+    loop {
+        cancel.check()?;
+
+        let error = match probe(&client, &check.probe).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        if Instant::now() + PROBE_INTERVAL > deadline {
+            return Err(HealthCheckFailed {
+                service_name: service_name.to_string(),
+                probe: check.probe.clone(),
+                timeout: check.timeout,
+                last_error: format!("{:#}", error),
+            }
+            .into());
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
+
+async fn probe(client: &reqwest::Client, probe: &HealthProbe) -> Result<()> {
+    match probe {
+        HealthProbe::Tcp { port } => {
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(("127.0.0.1", *port)))
+                .await
+                .context("Connection timed out")??;
+            Ok(())
+        }
+        HealthProbe::Http { url, expected_status } => {
+            let status = client.get(url).send().await?.status();
+            if status.as_u16() != *expected_status {
+                anyhow::bail!("Got status {}", status);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[tokio::test]
+    async fn test_tcp_probe() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let check = HealthCheck::tcp(port).timeout(Duration::ZERO);
+        wait_until_healthy("app", &check, None, &CancellationToken::new()).await.unwrap();
+
+        drop(listener);
+        let err = wait_until_healthy("app", &check, None, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.is::<HealthCheckFailed>());
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_checks_status() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for status in ["200 OK", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]);
+                let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            }
+        });
+
+        let check = HealthCheck::http(url, 200).timeout(Duration::ZERO);
+        wait_until_healthy("app", &check, None, &CancellationToken::new()).await.unwrap();
+
+        let err = wait_until_healthy("app", &check, None, &CancellationToken::new()).await.unwrap_err();
+        let failed = err.downcast_ref::<HealthCheckFailed>().unwrap();
+        assert!(failed.last_error.contains("503"));
+    }
+}
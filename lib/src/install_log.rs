@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::State;
+
+/// A single entry in the persisted install log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Wall-clock time of the entry in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Milliseconds elapsed since the operation started
+    pub elapsed_ms: u64,
+    pub event: LogEvent,
+}
+
+/// Events recorded in the install log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogEvent {
+    /// An install, update or repair operation started
+    Started { operation: String },
+    /// The release that will be installed was resolved
+    ReleaseResolved { tag_name: String },
+    /// The asset that will be downloaded was selected
+    AssetSelected { name: String, size: u64 },
+    /// The operation moved into a new state
+    Phase { state: State },
+    /// A progress milestone (every 25%) was reached within a state
+    Progress { state: State, progress: f32 },
+    /// A pre- or post-install hook ran, with its combined stdout and stderr
+    HookRan {
+        hook: String,
+        command: String,
+        success: bool,
+        output: String,
+    },
+    /// The operation finished
+    Finished {
+        success: bool,
+        error: Option<String>,
+        #[serde(default)]
+        reboot_required: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Session {
+    file: std::fs::File,
+    started: Instant,
+    last_state: Option<State>,
+    last_milestone: u8,
+}
+
+/// Append-only JSON lines writer for install diagnostics
+///
+/// Logging is best effort: failures to open or write the log never fail the operation.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InstallLog {
+    session: Arc<Mutex<Option<Session>>>,
+}
+
+impl InstallLog {
+    /// Start a new session appended to the log at `path`
+    pub fn begin(&self, path: &Path, operation: &str) {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path);
+        let mut session = self.session.lock().unwrap();
+        *session = file.ok().map(|file| Session {
+            file,
+            started: Instant::now(),
+            last_state: None,
+            last_milestone: 0,
+        });
+        drop(session);
+
+        self.record(LogEvent::Started { operation: operation.to_string() });
+    }
+
+    /// Append an event to the current session, if any
+    pub fn record(&self, event: LogEvent) {
+        let mut session = self.session.lock().unwrap();
+        if let Some(session) = session.as_mut() {
+            write_entry(session, event);
+        }
+    }
+
+    /// Record phase transitions and 25% milestones from a progress update
+    pub fn record_progress(&self, state: &State, progress: f32) {
+        let mut session = self.session.lock().unwrap();
+        let Some(session) = session.as_mut() else {
+            return;
+        };
+
+        if session.last_state.as_ref() != Some(state) {
+            session.last_state = Some(state.clone());
+            session.last_milestone = 0;
+            write_entry(session, LogEvent::Phase { state: state.clone() });
+        }
+
+        let milestone = (progress * 4.0).floor() as u8;
+        if milestone > session.last_milestone {
+            session.last_milestone = milestone;
+            write_entry(session, LogEvent::Progress { state: state.clone(), progress });
+        }
+    }
+
+    /// Record the final result and close the session
+    pub fn finish(&self, result: &Result<()>, reboot_required: bool) {
+        let mut session = self.session.lock().unwrap();
+        if let Some(mut current) = session.take() {
+            write_entry(
+                &mut current,
+                LogEvent::Finished {
+                    success: result.is_ok(),
+                    error: result.as_ref().err().map(|e| format!("{:#}", e)),
+                    reboot_required,
+                },
+            );
+        }
+    }
+}
+
+fn write_entry(session: &mut Session, event: LogEvent) {
+    let entry = LogEntry {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        elapsed_ms: session.started.elapsed().as_millis() as u64,
+        event,
+    };
+
+    if let Ok(line) = serde_json::to_string(&entry) {
+        let _ = writeln!(session.file, "{}", line);
+    }
+}
+
+/// Read the entries belonging to the most recent session in the log at `path`
+pub(crate) fn read_last_session(path: &Path) -> Result<Vec<LogEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read install log '{}'", path.display()))?;
+
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: LogEntry = serde_json::from_str(line)
+            .context(format!("Malformed install log entry: {}", line))?;
+        if matches!(entry.event, LogEvent::Started { .. }) {
+            entries.clear();
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_round_trip_returns_last_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("app.install.log");
+        let log = InstallLog::default();
+
+        log.begin(&path, "install");
+        log.finish(&Err(anyhow::anyhow!("network down")), false);
+
+        log.begin(&path, "repair");
+        log.record(LogEvent::ReleaseResolved { tag_name: "v1.2.0".to_string() });
+        log.record_progress(&State::Downloading, 0.1);
+        log.record_progress(&State::Downloading, 0.3);
+        log.record_progress(&State::Downloading, 0.35);
+        log.record_progress(&State::Extracting, 1.0);
+        log.finish(&Ok(()), true);
+
+        let entries = read_last_session(&path).unwrap();
+        let events: Vec<&LogEvent> = entries.iter().map(|e| &e.event).collect();
+
+        assert!(matches!(events[0], LogEvent::Started { operation } if operation == "repair"));
+        assert!(matches!(events[1], LogEvent::ReleaseResolved { .. }));
+        assert!(matches!(events[2], LogEvent::Phase { state: State::Downloading }));
+        assert!(matches!(events[3], LogEvent::Progress { state: State::Downloading, .. }));
+        assert!(matches!(events[4], LogEvent::Phase { state: State::Extracting }));
+        assert!(matches!(events[5], LogEvent::Progress { state: State::Extracting, .. }));
+        assert!(matches!(events[6], LogEvent::Finished { success: true, reboot_required: true, .. }));
+        assert_eq!(events.len(), 7);
+    }
+
+    #[test]
+    fn test_missing_log_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_last_session(&dir.path().join("none.log")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Checksum, GitHubRelease, InstallError, InstallationManager, State};
+
+/// Name of the file manifest, whether shipped in the archive or as a release asset
+pub(crate) const FILE_MANIFEST_NAME: &str = "manifest.sha256";
+
+impl InstallationManager {
+    /// Re-hash the installed files listed in the release's `manifest.sha256` and return those
+    /// that are missing or no longer match
+    ///
+    /// The manifest holds `sha256sum`-style lines (`<hex>  <path>`, SHA-256 or SHA-512) with
+    /// paths relative to install_path. It is read from install_path, where the archive put it or
+    /// install saved the release asset of that name. Broadcasts `State::Verifying` progress while
+    /// hashing. An empty list means the installation is intact; otherwise `repair` restores
+    /// them.
+    pub fn verify_installation(&self) -> Result<Vec<PathBuf>, InstallError> {
+        Ok(self.find_modified_files()?)
+    }
+
+    fn find_modified_files(&self) -> Result<Vec<PathBuf>> {
+        let install_path = &self.config.install_path;
+        let manifest_path = install_path.join(FILE_MANIFEST_NAME);
+        let content = std::fs::read_to_string(&manifest_path).context(format!(
+            "Failed to read the file manifest '{}'; the release may not ship one",
+            manifest_path.display()
+        ))?;
+        let entries = parse_file_manifest(&content)?;
+
+        self.observer.message(format_args!("Verifying {} installed files...", entries.len()));
+        self.broadcast_progress(State::Verifying, 0.0);
+
+        let mut modified = Vec::new();
+        let mut reported = 0;
+        for (index, (expected, relative)) in entries.iter().enumerate() {
+            let path = install_path.join(relative);
+            let intact = path.is_file() && expected.compute_for(&path).is_ok_and(|actual| &actual == expected);
+            if !intact {
+                modified.push(path);
+            }
+
+            // One update per whole percent is plenty for a progress bar
+            let percent = (index + 1) * 100 / entries.len();
+            if percent > reported {
+                reported = percent;
+                self.broadcast_progress(State::Verifying, percent as f32 / 100.0);
+            }
+        }
+
+        if modified.is_empty() {
+            self.observer.message(format_args!("All installed files match the manifest"));
+        } else {
+            self.observer.warning(format_args!("{} installed files are missing or modified", modified.len()));
+        }
+        Ok(modified)
+    }
+
+    /// Save the release's `manifest.sha256` asset into install_path, unless the archive already
+    /// brought one
+    pub(crate) async fn fetch_file_manifest(&self, release: &GitHubRelease) -> Result<()> {
+        let destination = self.config.install_path.join(FILE_MANIFEST_NAME);
+        let Some(asset) = release.assets.iter().find(|asset| asset.name == FILE_MANIFEST_NAME) else {
+            return Ok(());
+        };
+        if destination.exists() {
+            return Ok(());
+        }
+
+        let content = self
+            .request_asset(asset)
+            .await?
+            .bytes()
+            .await
+            .context(format!("Failed to download '{}'", asset.name))?;
+        parse_file_manifest(&String::from_utf8_lossy(&content))?;
+        tokio::fs::write(&destination, &content)
+            .await
+            .context(format!("Failed to write '{}'", destination.display()))?;
+        Ok(())
+    }
+}
+
+/// Read the digest and relative path from each line of a file manifest
+fn parse_file_manifest(content: &str) -> Result<Vec<(Checksum, PathBuf)>> {
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let malformed = || format!("Line {} of {} is malformed: '{}'", number, FILE_MANIFEST_NAME, line);
+
+        let (hex, name) = line.split_once(char::is_whitespace).with_context(malformed)?;
+        let checksum = Checksum::from_hex(hex).with_context(malformed)?;
+        let listed = Path::new(name.trim().trim_start_matches('*'));
+        if !is_contained(listed) {
+            anyhow::bail!("{} lists '{}', which is outside the installation", FILE_MANIFEST_NAME, listed.display());
+        }
+        // Drop the `./` some tools put in front of every path
+        let relative: PathBuf = listed.components().filter(|component| *component != Component::CurDir).collect();
+        entries.push((checksum, relative));
+    }
+    Ok(entries)
+}
+
+/// Whether a manifest path stays inside install_path once joined to it
+fn is_contained(relative: &Path) -> bool {
+    relative.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InstallationConfig;
+
+    #[test]
+    fn test_verify_installation() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(install_path.join("lib")).unwrap();
+        std::fs::write(install_path.join("server"), b"server").unwrap();
+        std::fs::write(install_path.join("lib/data.bin"), b"corrupted").unwrap();
+
+        // sha256 of "server" and "data"
+        let manifest = "\
+b3eacd33433b31b5252351032c9b3e7a2e7aa7738d5decdf0dd6c62680853c06  server
+3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7 *./lib/data.bin
+87ef2c0f0c1f9d1ebd2b5e8e11a5b5e69cbd57e0c31b4b3a6a05e7f4c4ba6bc1  missing.txt
+";
+        std::fs::write(install_path.join(FILE_MANIFEST_NAME), manifest).unwrap();
+
+        let manager = InstallationManager::new(InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string()));
+        let mut progress = manager.subscribe();
+        let modified = manager.verify_installation().unwrap();
+        assert_eq!(modified, [install_path.join("lib/data.bin"), install_path.join("missing.txt")]);
+        assert!(matches!(progress.try_recv().unwrap().state, State::Verifying));
+
+        assert!(parse_file_manifest("0000  ../etc/passwd").is_err());
+        assert!(parse_file_manifest("not-a-digest  server").is_err());
+    }
+}
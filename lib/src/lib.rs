@@ -1,20 +1,133 @@
 use semver::Version;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use anyhow::{Context, Result};
-use tokio::sync::broadcast;
+use futures::Stream;
+use tokio::sync::{broadcast, watch};
 
 #[cfg(target_os = "linux")]
 mod nix;
+#[cfg(target_os = "macos")]
+mod mac;
+#[cfg(target_os = "linux")]
+mod package;
 #[cfg(target_os = "windows")]
 mod win;
+mod archive;
+mod backup;
+mod binaries;
+mod cache;
+mod cancel;
+mod channel_lock;
+mod checksum;
+mod chunked;
+mod cleanup;
+mod command;
+mod config_file;
+mod delta;
+mod error;
+mod extract_state;
+mod footprint;
+mod health;
+mod install_log;
+mod integrity;
+mod lock;
+mod observer;
+mod origin;
+mod permissions;
+mod plan;
+mod preflight;
+mod progress;
+mod proxy;
+mod quarantine;
+mod release_cache;
+mod retry;
+mod self_update;
+mod service_manager;
+mod signature;
+mod streaming;
+mod transfer;
+mod uninstall;
+mod uninstall_manifest;
+mod update_kind;
+
+pub use archive::{ArchiveFormat, DecompressionLimitExceeded, UnsafeArchiveEntry};
+pub use binaries::{BinaryNotFound, BinarySpec};
+pub use cancel::{CancellationToken, Cancelled};
+pub use channel_lock::{ChannelLock, ChannelLockMismatch};
+pub use checksum::{Checksum, ChecksumMismatch};
+pub use command::CommandError;
+pub use error::{InstallError, RateLimitExceeded};
+pub use footprint::Footprint;
+pub use health::{HealthCheck, HealthCheckFailed, HealthProbe};
+pub use install_log::{LogEntry, LogEvent};
+pub use lock::OperationInProgress;
+pub use observer::{ConsoleObserver, InstallObserver};
+use observer::Observer;
+pub use origin::{ForeignInstallation, InstallationOrigin};
+pub use permissions::FilePermissionPolicy;
+pub use plan::{InstallPlan, PlannedOperation};
+pub use preflight::{InsufficientInodes, InsufficientSpace, InvalidInstallPath};
+pub use proxy::ProxyUnreachable;
+pub use service_manager::ServiceManagerUnavailable;
+pub use signature::{SignatureConfig, SignatureInvalid, SignatureScheme};
+pub use uninstall::UninstallOptions;
+pub use uninstall_manifest::{RegistryValue, UninstallManifest};
+pub use update_kind::{classify_update, UpdateInfo, UpdateKind};
+
+/// How long to wait for a started service to come up before failing
+const SERVICE_START_TIMEOUT_SECS: u32 = 30;
+
+/// How long to wait for a service to exit after it has been force-killed
+const FORCE_STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// GitHub's public REST API, used unless `api_base_url` points elsewhere
+const DEFAULT_API_BASE_URL: &str = "https://api.github.com";
+
+/// Free space assumed necessary to extract an archive, as a multiple of its download size
+const EXTRACTION_SPACE_MULTIPLIER: u64 = 3;
 
 /// GitHub release information
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GitHubRelease {
     pub tag_name: String,
     pub name: String,
+    /// Release notes in Markdown (empty if the release has none)
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub body: String,
+    pub prerelease: bool,
+    /// Unpublished draft (only visible to authenticated collaborators); never installed
+    #[serde(default)]
+    pub draft: bool,
+    /// When the release was published (absent for drafts)
+    #[serde(default)]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub assets: Vec<GitHubAsset>,
+}
+
+/// Deserialize a JSON `null` as the type's default, for fields GitHub sends as null when empty
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// A published release with its tag parsed as a version, as listed by `list_versions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseInfo {
+    pub version: Version,
+    pub tag: String,
     pub prerelease: bool,
+    /// The most stable channel the release belongs to
+    pub channel: ReleaseChannel,
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
     pub assets: Vec<GitHubAsset>,
 }
 
@@ -24,6 +137,61 @@ pub struct GitHubAsset {
     pub name: String,
     pub browser_download_url: String,
     pub size: u64,
+    /// Content digest reported by GitHub, e.g. "sha256:..." (absent on older releases)
+    #[serde(default)]
+    pub digest: Option<String>,
+    /// MIME type the asset was uploaded with, e.g. "application/gzip"
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Upload state reported by GitHub: "uploaded" once complete, "starter" while still uploading
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl GitHubAsset {
+    /// Checksum from the asset digest, if GitHub reported one
+    pub fn checksum(&self) -> Option<Checksum> {
+        self.digest.as_deref().and_then(Checksum::from_digest)
+    }
+
+    /// Whether the asset has finished uploading (assets without a reported state are assumed complete)
+    pub fn is_uploaded(&self) -> bool {
+        self.state.as_deref().is_none_or(|state| state == "uploaded")
+    }
+
+    /// Archive format from the file name, falling back to the content type
+    ///
+    /// A name without an extension only means a bare binary when the content type doesn't say otherwise.
+    pub fn format(&self) -> Option<ArchiveFormat> {
+        let by_type = || self.content_type.as_deref().and_then(ArchiveFormat::from_content_type);
+        match ArchiveFormat::from_file_name(&self.name) {
+            Some(ArchiveFormat::Binary) if !self.name.to_lowercase().ends_with(".exe") => {
+                by_type().or(Some(ArchiveFormat::Binary))
+            }
+            Some(format) => Some(format),
+            None => by_type(),
+        }
+    }
+
+    /// File name to download the asset as, with an extension matching its format so it can be extracted
+    pub(crate) fn local_file_name(&self) -> String {
+        match self.format() {
+            Some(format) if ArchiveFormat::from_file_name(&self.name) != Some(format) => {
+                format!("{}{}", self.name, format.extensions()[0])
+            }
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Lowercase hex SHA-256 from the asset digest, if GitHub reported one
+    pub fn sha256(&self) -> Option<String> {
+        self.digest
+            .as_deref()
+            .and_then(|d| d.strip_prefix("sha256:"))
+            .map(|hex| hex.to_ascii_lowercase())
+    }
 }
 
 /// Release channel for version filtering
@@ -70,6 +238,55 @@ impl ReleaseChannel {
     }
 }
 
+/// Release channel identified by the prerelease suffix of the release's tag
+///
+/// Unlike `ReleaseChannel`, whose wider channels also take in the more stable releases, each of
+/// these only follows the releases tagged for it, e.g. `v1.4.0-beta.2` or `v1.4.0-nightly.20240611`.
+/// Tags that aren't semantic versions are never part of a channel.
+#[derive(Debug, Clone)]
+pub enum Channel {
+    /// Tags without a prerelease suffix that GitHub doesn't mark as a prerelease
+    Stable,
+    /// Prereleases whose tag suffix starts with "beta" or "rc"
+    Beta,
+    /// Prereleases whose tag suffix starts with "nightly"
+    Nightly,
+    /// Tags matching the regex, e.g. `-canary\.` (matched against the whole tag name)
+    Custom(Regex),
+}
+
+impl Channel {
+    /// Parse a release's version if its tag belongs to this channel
+    pub fn matches(&self, release: &GitHubRelease) -> Option<Version> {
+        if release.draft {
+            return None;
+        }
+
+        let version = Version::parse(release.tag_name.trim_start_matches('v')).ok()?;
+        let suffix = version.pre.split('.').next().unwrap_or_default().to_lowercase();
+
+        let matches = match self {
+            Channel::Stable => !release.prerelease && version.pre.is_empty(),
+            Channel::Beta => release.prerelease && (suffix.starts_with("beta") || suffix.starts_with("rc")),
+            Channel::Nightly => release.prerelease && suffix.starts_with("nightly"),
+            Channel::Custom(pattern) => pattern.is_match(&release.tag_name),
+        };
+
+        matches.then_some(version)
+    }
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Channel::Stable => f.write_str("Stable"),
+            Channel::Beta => f.write_str("Beta"),
+            Channel::Nightly => f.write_str("Nightly"),
+            Channel::Custom(pattern) => write!(f, "tags matching /{}/", pattern),
+        }
+    }
+}
+
 /// Platform architecture information
 #[derive(Debug, Clone, PartialEq)]
 pub enum Architecture {
@@ -82,8 +299,18 @@ pub enum Architecture {
 }
 
 impl Architecture {
+    /// Every platform assets can be selected for
+    pub const ALL: [Architecture; 6] = [
+        Architecture::WindowsX64,
+        Architecture::WindowsArm64,
+        Architecture::LinuxX64,
+        Architecture::LinuxArm64,
+        Architecture::MacOSX64,
+        Architecture::MacOSArm64,
+    ];
+
     /// Detect current system architecture
-    pub fn detect() -> Result<Self> {
+    pub fn detect() -> Result<Self, InstallError> {
         let os = std::env::consts::OS;
         let arch = std::env::consts::ARCH;
 
@@ -94,35 +321,110 @@ impl Architecture {
             ("linux", "aarch64") => Ok(Architecture::LinuxArm64),
             ("macos", "x86_64") => Ok(Architecture::MacOSX64),
             ("macos", "aarch64") => Ok(Architecture::MacOSArm64),
-            _ => anyhow::bail!("Unsupported platform: {} {}", os, arch),
+            _ => Err(anyhow::anyhow!("Unsupported platform: {} {}", os, arch).into()),
         }
     }
 
     /// Get patterns to match against asset names
     pub fn asset_patterns(&self) -> Vec<&str> {
+        let mut patterns = self.os_patterns().to_vec();
+        patterns.extend_from_slice(self.cpu_patterns());
+        patterns
+    }
+
+    /// Names of this platform's operating system in asset names
+    fn os_patterns(&self) -> &'static [&'static str] {
+        match self {
+            Architecture::WindowsX64 | Architecture::WindowsArm64 => &["windows", "win"],
+            Architecture::LinuxX64 | Architecture::LinuxArm64 => &["linux"],
+            Architecture::MacOSX64 | Architecture::MacOSArm64 => &["macos", "darwin"],
+        }
+    }
+
+    /// Names of this platform's CPU architecture in asset names
+    fn cpu_patterns(&self) -> &'static [&'static str] {
         match self {
-            Architecture::WindowsX64 => vec!["windows", "win", "x64", "x86_64", "amd64"],
-            Architecture::WindowsArm64 => vec!["windows", "win", "arm64", "aarch64"],
-            Architecture::LinuxX64 => vec!["linux", "x64", "x86_64", "amd64"],
-            Architecture::LinuxArm64 => vec!["linux", "arm64", "aarch64"],
-            Architecture::MacOSX64 => vec!["macos", "darwin", "x64", "x86_64"],
-            Architecture::MacOSArm64 => vec!["macos", "darwin", "arm64", "aarch64"],
+            Architecture::WindowsX64 | Architecture::LinuxX64 => &["x64", "x86_64", "amd64"],
+            Architecture::MacOSX64 => &["x64", "x86_64"],
+            Architecture::WindowsArm64 | Architecture::LinuxArm64 | Architecture::MacOSArm64 => &["arm64", "aarch64"],
+        }
+    }
+
+    /// How well an asset name fits this platform: 3 for both OS and CPU, 2 for the OS alone,
+    /// 1 for the CPU alone, 0 for neither
+    fn match_score(&self, asset_name: &str) -> u8 {
+        if self.is_foreign(asset_name) {
+            return 0;
         }
+        let name = asset_name.to_lowercase();
+        // "darwin" contains "win"
+        let os_name = if self.is_windows() { name.replace("darwin", "") } else { name.clone() };
+        let os = self.os_patterns().iter().any(|p| os_name.contains(p));
+        let cpu = self.cpu_patterns().iter().any(|p| name.contains(p));
+        u8::from(os) * 2 + u8::from(cpu)
+    }
+
+    /// Whether an asset name names another operating system and not this one, e.g.
+    /// `server-win-x64.zip` on Linux
+    ///
+    /// Matches whole words of the name (optionally followed by digits, as in `win64`), so
+    /// `darwin` doesn't count as Windows.
+    fn is_foreign(&self, asset_name: &str) -> bool {
+        let name = asset_name.to_lowercase();
+        let names_os = |patterns: &[&str]| {
+            name.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| {
+                patterns.iter().any(|p| word.strip_prefix(p).is_some_and(|rest| rest.bytes().all(|b| b.is_ascii_digit())))
+            })
+        };
+        !names_os(self.os_patterns())
+            && Architecture::ALL.iter()
+                .filter(|other| other.os_name() != self.os_name())
+                .any(|other| names_os(other.os_patterns()))
     }
 
     /// Check if this is a Windows platform
     pub fn is_windows(&self) -> bool {
         matches!(self, Architecture::WindowsX64 | Architecture::WindowsArm64)
     }
+
+    /// Get the operating system name used in asset templates
+    pub fn os_name(&self) -> &'static str {
+        match self {
+            Architecture::WindowsX64 | Architecture::WindowsArm64 => "windows",
+            Architecture::LinuxX64 | Architecture::LinuxArm64 => "linux",
+            Architecture::MacOSX64 | Architecture::MacOSArm64 => "macos",
+        }
+    }
+
+    /// Get the CPU architecture name used in asset templates
+    pub fn arch_name(&self) -> &'static str {
+        match self {
+            Architecture::WindowsX64 | Architecture::LinuxX64 | Architecture::MacOSX64 => "x86_64",
+            Architecture::WindowsArm64 | Architecture::LinuxArm64 | Architecture::MacOSArm64 => "aarch64",
+        }
+    }
+
+    /// Get the archive extension conventionally used for this platform's releases
+    pub fn archive_extension(&self) -> &'static str {
+        if self.is_windows() { "zip" } else { "tar.gz" }
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[repr(u8)]
 pub enum State{
     Downloading,
     Extracting,
     Installing,
-    Updating
+    Updating,
+    /// Starting the service and waiting for it to come up
+    Finalizing,
+    /// Checking a download against its checksum
+    Verifying,
+    /// The install, update or repair finished successfully
+    Complete,
+    /// Nothing is running; never broadcast, but useful as a UI's initial state
+    Idle,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -130,11 +432,116 @@ pub struct StateProgress{
     pub state: State,
     /// The progress from 0.0 to 1.0
     pub progress: f32,
+    /// Bytes received so far (downloads only)
+    #[serde(default)]
+    pub bytes_done: Option<u64>,
+    /// Size of the download in bytes, if known (downloads only)
+    #[serde(default)]
+    pub bytes_total: Option<u64>,
+    /// Download speed averaged over the last second, once it can be measured (downloads only)
+    #[serde(default)]
+    pub bytes_per_sec: Option<f64>,
+    /// Estimated time until the download finishes, from a smoothed speed (downloads of known size only)
+    #[serde(default)]
+    pub eta: Option<Duration>,
 }
 
 impl StateProgress {
     pub fn new(state: State, progress: f32) -> Self {
-        Self { state, progress: progress.clamp(0.0, 1.0) }
+        Self {
+            state,
+            progress: progress.clamp(0.0, 1.0),
+            bytes_done: None,
+            bytes_total: None,
+            bytes_per_sec: None,
+            eta: None,
+        }
+    }
+}
+
+/// Whether the service is installed for the whole system or only the current user
+///
+/// Only Linux honors `User`: the unit goes to `~/.config/systemd/user/` and is managed with
+/// `systemctl --user`, so no root privileges are needed. A user service only runs while the user
+/// is logged in unless lingering is enabled (`loginctl enable-linger`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstallScope {
+    #[default]
+    System,
+    User,
+}
+
+/// What the service manager does when the service's process exits
+///
+/// Maps to systemd's `Restart=`, launchd's `KeepAlive` and the Windows service recovery actions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Restart whenever the process exits, even cleanly
+    #[default]
+    Always,
+    /// Restart only after a crash or a non-zero exit code
+    OnFailure,
+    /// Leave the service stopped
+    Never,
+}
+
+/// What extraction does with files already in install_path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractPolicy {
+    /// Write every file from the archive over what's there, leaving files the archive doesn't contain
+    Overwrite,
+    /// Delete everything in install_path except `data_dirs` first, so files removed from the
+    /// release don't linger
+    CleanThenExtract,
+    /// Only write files that don't exist yet, e.g. to put back missing files without touching edited ones
+    SkipExisting,
+}
+
+/// Account a Windows service runs under
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceAccount {
+    /// The all-powerful local system account (what services get when no account is given)
+    LocalSystem,
+    /// Minimal local privileges, anonymous on the network
+    LocalService,
+    /// Minimal local privileges, the computer's identity on the network
+    NetworkService,
+    /// A named user such as `.\panel` or `DOMAIN\panel`, which needs the "Log on as a service" right
+    User {
+        name: String,
+        /// Only held in memory for the install; never serialized or stored
+        #[serde(default, skip_serializing)]
+        password: Option<String>,
+    },
+}
+
+impl ServiceAccount {
+    /// The name passed to the Service Control Manager, None for LocalSystem
+    pub fn account_name(&self) -> Option<&str> {
+        match self {
+            ServiceAccount::LocalSystem => None,
+            ServiceAccount::LocalService => Some(r"NT AUTHORITY\LocalService"),
+            ServiceAccount::NetworkService => Some(r"NT AUTHORITY\NetworkService"),
+            ServiceAccount::User { name, .. } => Some(name),
+        }
+    }
+}
+
+impl std::fmt::Debug for ServiceAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceAccount::LocalSystem => f.write_str("LocalSystem"),
+            ServiceAccount::LocalService => f.write_str("LocalService"),
+            ServiceAccount::NetworkService => f.write_str("NetworkService"),
+            ServiceAccount::User { name, password } => f
+                .debug_struct("User")
+                .field("name", name)
+                .field("password", &password.as_ref().map(|_| "<redacted>"))
+                .finish(),
+        }
     }
 }
 
@@ -153,12 +560,191 @@ pub struct InstallationConfig {
     pub service_description: Option<String>,
     /// Custom binary name to look for (optional)
     pub binary_name: Option<String>,
+    /// Executables shipped by the release, with the one the service runs marked primary; takes
+    /// the place of binary_name when set (optional, empty by default)
+    #[serde(default)]
+    pub binaries: Vec<BinarySpec>,
     /// Custom registry path for Windows (optional, defaults to SOFTWARE\ObsidianInstallationManager)
     pub registry_path: Option<String>,
-    /// Custom version file directory for Linux (optional, defaults to /var/lib/oim)
+    /// Custom version file directory for Linux (optional, defaults to /var/lib/oim, or $XDG_DATA_HOME/oim for user scope)
     pub version_file_dir: Option<String>,
+    /// Install a system-wide or per-user service (defaults to system; user scope is Linux only)
+    #[serde(default)]
+    pub install_scope: InstallScope,
     /// Working directory for the service (optional, defaults to install_path)
     pub working_directory: Option<PathBuf>,
+    /// Arguments for a self-test run against the installed binary after install (e.g. "--self-check")
+    pub post_install_verify_command: Option<String>,
+    /// Program and arguments run before anything is changed by an install or update, e.g. to stop dependent
+    /// services; a failure aborts the operation (optional, none by default)
+    #[serde(default)]
+    pub pre_install_hook: Option<Vec<String>>,
+    /// Program and arguments run once the installed service has started, e.g. to run migrations; a failure
+    /// fails the operation, rolling an update back if `rollback_on_failure` is set (optional, none by default)
+    #[serde(default)]
+    pub post_install_hook: Option<Vec<String>>,
+    /// Exact asset name template, e.g. "panel-{version}-{os}-{arch}.{ext}" (optional, bypasses pattern matching)
+    pub asset_template: Option<String>,
+    /// Fail if the newest release has no asset for this platform instead of falling back to an older one (defaults to true)
+    #[serde(default = "default_true")]
+    pub require_latest: bool,
+    /// Hold a cross-process lock keyed by service_name during install/update/repair/uninstall (defaults to true)
+    #[serde(default = "default_true")]
+    pub operation_lock: bool,
+    /// Directory for a download cache shared across services (optional, disabled by default)
+    pub cache_dir: Option<PathBuf>,
+    /// Maximum size of the download cache in bytes; least recently used entries are evicted (optional, unbounded)
+    pub cache_max_bytes: Option<u64>,
+    /// Extra environment variables the service runs with (systemd `Environment=`, launchd `EnvironmentVariables`, the
+    /// service's registry key on Windows), also passed to hooks and probes run by the library
+    #[serde(default)]
+    pub service_env: HashMap<String, String>,
+    /// Unix modes applied to the install tree after extraction (optional, archive modes are kept by default)
+    pub file_permission_policy: Option<FilePermissionPolicy>,
+    /// Path to systemctl for Linux (optional, searched on PATH by default)
+    pub systemctl_path: Option<PathBuf>,
+    /// Minimum time between update checks made through check_for_updates/update (optional, no debouncing by default)
+    pub update_check_interval: Option<Duration>,
+    /// How long to wait for the service to stop before an update gives up (defaults to 30 seconds)
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: Duration,
+    /// Force-kill the service if it is still running this long after the stop request (optional, never by default)
+    pub force_stop_after: Option<Duration>,
+    /// Remove macOS quarantine xattrs / Windows Mark-of-the-Web from installed files (defaults to true)
+    #[serde(default = "default_true")]
+    pub clear_quarantine: bool,
+    /// Resolve the stable channel's latest release via GitHub's `/releases/latest` endpoint (defaults to false)
+    #[serde(default)]
+    pub use_latest_endpoint: bool,
+    /// Skip releases published more recently than this when resolving the latest release (optional, no minimum by default)
+    pub min_release_age: Option<Duration>,
+    /// Abort extraction once an archive expands past this many bytes (optional, unlimited by default)
+    pub max_uncompressed_size: Option<u64>,
+    /// Back up install_path before an update and restore it if the update fails (defaults to true)
+    #[serde(default = "default_true")]
+    pub rollback_on_failure: bool,
+    /// GitHub token sent with API requests and downloads (optional, falls back to the GITHUB_TOKEN environment variable)
+    #[serde(default, skip_serializing)]
+    pub github_token: Option<String>,
+    /// How long to wait for a connection to GitHub before giving up (defaults to 30 seconds)
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: Duration,
+    /// Maximum time for a GitHub API request, and the longest a download may go without receiving data (defaults to 60 seconds)
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: Duration,
+    /// Extra attempts, with exponential backoff, for requests and downloads that time out, drop the connection or get a 5xx (defaults to 3)
+    #[serde(default = "default_retry_count")]
+    pub retry_count: u32,
+    /// Download large assets (4 MiB and up) over several connections at once, each fetching a byte range; servers without range support get one stream (defaults to false)
+    #[serde(default)]
+    pub parallel_download: bool,
+    /// How many connections a parallel download uses (defaults to 4)
+    #[serde(default = "default_download_connections")]
+    pub download_connections: usize,
+    /// How many progress updates a `subscribe` receiver can fall behind before it lags (defaults to 100)
+    #[serde(default = "default_progress_channel_capacity")]
+    pub progress_channel_capacity: usize,
+    /// Free bytes required on the install_path volume before extracting (optional, defaults to 3x the asset size)
+    pub min_free_bytes: Option<u64>,
+    /// Directory downloads are kept in until they are extracted (optional, defaults to one next to install_path, on the same volume)
+    pub temp_dir: Option<PathBuf>,
+    /// Only select assets whose name matches this pattern (optional, the platform heuristic picks among all assets by default)
+    #[serde(default, with = "regex_serde")]
+    pub asset_filter: Option<Regex>,
+    /// Files or directories, relative to install_path, holding user data that uninstall leaves in place (e.g. "worlds", "config")
+    #[serde(default)]
+    pub data_dirs: Vec<PathBuf>,
+    /// Probe the service must pass after starting during install and update; a failed update is rolled back (optional, none by default)
+    pub health_check: Option<HealthCheck>,
+    /// Leading path components removed from every archive entry, like `tar --strip-components` (defaults to 0)
+    #[serde(default)]
+    pub strip_components: usize,
+    /// Drop the top-level folder of archives whose entries all sit inside one; ignored when strip_components is set (defaults to false)
+    #[serde(default)]
+    pub strip_single_root: bool,
+    /// How extraction treats files already in install_path (optional, defaults to Overwrite for installs and CleanThenExtract for updates)
+    pub extract_policy: Option<ExtractPolicy>,
+    /// Add a Start Menu shortcut (Windows) or a `.desktop` launcher (Linux) for the executable on install (defaults to false)
+    #[serde(default)]
+    pub create_shortcuts: bool,
+    /// Also put a shortcut on the Desktop when create_shortcuts is set (Windows only, defaults to false)
+    #[serde(default)]
+    pub desktop_shortcut: bool,
+    /// Command Apps & features runs to remove the installation; when set, install lists it there (Windows only, optional)
+    pub uninstall_command: Option<String>,
+    /// Publisher shown in Apps & features (optional, defaults to the owner of github_repo)
+    pub publisher: Option<String>,
+    /// Public key assets must be signed with; unsigned or badly signed releases are refused (optional, not checked by default)
+    pub signature: Option<SignatureConfig>,
+    /// Best-effort cap on the average speed of each download, in bytes per second (optional, unlimited by default)
+    pub max_download_bytes_per_sec: Option<u64>,
+    /// HTTP or HTTPS proxy URL for GitHub requests and downloads, e.g. "http://proxy.corp:3128" (optional, defaults to the HTTPS_PROXY/HTTP_PROXY/NO_PROXY environment variables)
+    pub proxy: Option<String>,
+    /// Serve the releases list from a disk cache for this long before asking GitHub again (optional, every lookup hits the API by default)
+    pub release_cache_ttl: Option<Duration>,
+    /// Base URL of the GitHub REST API, e.g. "https://github.example.com/api/v3" for GitHub Enterprise Server (optional, defaults to https://api.github.com)
+    pub api_base_url: Option<String>,
+    /// Host (and optional path prefix) to download assets from instead of the one in their download URL, for a CDN or mirror (optional)
+    pub download_base_url: Option<String>,
+    /// When the service manager restarts the service after it exits (defaults to Always)
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    /// Seconds to wait before restarting the service (optional, defaults to 10)
+    pub restart_sec: Option<u64>,
+    /// Account the Windows service runs under (optional, defaults to LocalSystem)
+    pub service_account: Option<ServiceAccount>,
+    /// Install a release's `.deb` or `.rpm` asset through the host's package manager instead of extracting an archive into install_path, when the release ships one in the host's format (Linux only, defaults to false)
+    #[serde(default)]
+    pub system_package: bool,
+    /// Name of the package in the package database (optional, defaults to service_name)
+    pub package_name: Option<String>,
+}
+
+/// Serialize the asset filter as its pattern string
+mod regex_serde {
+    use regex::Regex;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(filter: &Option<Regex>, serializer: S) -> Result<S::Ok, S::Error> {
+        match filter {
+            Some(filter) => serializer.serialize_some(filter.as_str()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|pattern| Regex::new(&pattern).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_stop_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_connect_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_request_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_retry_count() -> u32 {
+    3
+}
+
+fn default_download_connections() -> usize {
+    4
+}
+
+fn default_progress_channel_capacity() -> usize {
+    100
 }
 
 impl InstallationConfig {
@@ -175,9 +761,60 @@ impl InstallationConfig {
             service_display_name: None,
             service_description: None,
             binary_name: None,
+            binaries: Vec::new(),
             registry_path: None,
             version_file_dir: None,
+            install_scope: InstallScope::System,
             working_directory: None,
+            post_install_verify_command: None,
+            pre_install_hook: None,
+            post_install_hook: None,
+            asset_template: None,
+            require_latest: true,
+            operation_lock: true,
+            cache_dir: None,
+            cache_max_bytes: None,
+            service_env: HashMap::new(),
+            file_permission_policy: None,
+            systemctl_path: None,
+            update_check_interval: None,
+            stop_timeout: default_stop_timeout(),
+            force_stop_after: None,
+            clear_quarantine: true,
+            use_latest_endpoint: false,
+            min_release_age: None,
+            max_uncompressed_size: None,
+            rollback_on_failure: true,
+            github_token: None,
+            connect_timeout: default_connect_timeout(),
+            request_timeout: default_request_timeout(),
+            retry_count: default_retry_count(),
+            parallel_download: false,
+            download_connections: default_download_connections(),
+            progress_channel_capacity: default_progress_channel_capacity(),
+            min_free_bytes: None,
+            temp_dir: None,
+            asset_filter: None,
+            data_dirs: Vec::new(),
+            health_check: None,
+            strip_components: 0,
+            strip_single_root: false,
+            extract_policy: None,
+            create_shortcuts: false,
+            desktop_shortcut: false,
+            uninstall_command: None,
+            publisher: None,
+            signature: None,
+            max_download_bytes_per_sec: None,
+            proxy: None,
+            release_cache_ttl: None,
+            api_base_url: None,
+            download_base_url: None,
+            restart_policy: RestartPolicy::default(),
+            restart_sec: None,
+            service_account: None,
+            system_package: false,
+            package_name: None,
         }
     }
 
@@ -199,883 +836,5290 @@ impl InstallationConfig {
         self
     }
 
+    /// Add an executable the release ships, e.g. a CLI next to the server the service runs
+    pub fn binary(mut self, spec: BinarySpec) -> Self {
+        self.binaries.push(spec);
+        self
+    }
+
     /// Set a custom registry path (Windows only)
     pub fn registry_path(mut self, path: String) -> Self {
         self.registry_path = Some(path);
         self
     }
 
-    /// Set a custom version file directory (Linux only)
+    /// Set a custom version file directory (Linux and macOS)
     pub fn version_file_dir(mut self, dir: String) -> Self {
         self.version_file_dir = Some(dir);
         self
     }
 
+    /// Set whether the service is installed system-wide or for the current user (Linux only)
+    pub fn install_scope(mut self, scope: InstallScope) -> Self {
+        self.install_scope = scope;
+        self
+    }
+
     /// Set a custom working directory for the service
     pub fn working_directory(mut self, dir: PathBuf) -> Self {
         self.working_directory = Some(dir);
         self
     }
 
-    /// Get the service display name (returns service_name if not set)
-    pub fn get_display_name(&self) -> &str {
-        self.service_display_name.as_deref().unwrap_or(&self.service_name)
+    /// Set a self-test command to run against the installed binary before declaring success
+    pub fn post_install_verify_command(mut self, command: String) -> Self {
+        self.post_install_verify_command = Some(command);
+        self
     }
 
-    /// Get the service description (returns a default if not set)
-    pub fn get_description(&self) -> String {
-        self.service_description.clone().unwrap_or_else(|| {
-            format!("{} Service", self.get_display_name())
-        })
+    /// Set a command (program followed by its arguments) to run before an install or update changes anything
+    pub fn pre_install_hook(mut self, command: Vec<String>) -> Self {
+        self.pre_install_hook = Some(command);
+        self
     }
 
-    /// Get the working directory (returns install_path if not set)
-    pub fn get_working_directory(&self) -> &PathBuf {
-        self.working_directory.as_ref().unwrap_or(&self.install_path)
+    /// Set a command (program followed by its arguments) to run after an install or update has started the service
+    pub fn post_install_hook(mut self, command: Vec<String>) -> Self {
+        self.post_install_hook = Some(command);
+        self
     }
 
-    /// Get the registry path (Windows)
-    pub fn get_registry_path(&self) -> &str {
-        self.registry_path.as_deref().unwrap_or(r"SOFTWARE\ObsidianInstallationManager")
+    /// Set an exact asset name template; `{version}`, `{os}`, `{arch}` and `{ext}` are substituted
+    pub fn asset_template(mut self, template: String) -> Self {
+        self.asset_template = Some(template);
+        self
     }
 
-    /// Get the version file directory (Linux)
-    pub fn get_version_file_dir(&self) -> &str {
-        self.version_file_dir.as_deref().unwrap_or("/var/lib/oim")
+    /// Set whether the newest release is required, or older releases may be used when it lacks an asset for this platform
+    pub fn require_latest(mut self, require: bool) -> Self {
+        self.require_latest = require;
+        self
     }
-}
 
-/// Check if an installation exists by querying the system (Windows registry or Linux version file)
-#[cfg(target_os = "windows")]
-pub fn check_installation_exists(config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>> {
-    let version = win::get_installed_version(config)?;
-    let path = win::get_install_path(config)?;
+    /// Set whether operations take a cross-process lock so two installs of the same service can't race
+    pub fn operation_lock(mut self, enabled: bool) -> Self {
+        self.operation_lock = enabled;
+        self
+    }
 
-    match (version, path) {
-        (Some(v), Some(p)) => Ok(Some((v, p))),
-        _ => Ok(None),
+    /// Set a download cache directory; verified assets are stored there and reused by any service sharing it
+    pub fn cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
     }
-}
 
-/// Check if an installation exists by querying the system (Windows registry or Linux version file)
-#[cfg(target_os = "linux")]
-pub fn check_installation_exists(config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>> {
-    let version = nix::get_installed_version(config)?;
+    /// Set the maximum download cache size in bytes
+    pub fn cache_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.cache_max_bytes = Some(max_bytes);
+        self
+    }
 
-    match version {
-        Some(v) => Ok(Some((v, config.install_path.clone()))),
-        None => Ok(None),
+    /// Add an environment variable for the service, its hooks and probes
+    pub fn service_env_var(mut self, key: String, value: String) -> Self {
+        self.service_env.insert(key, value);
+        self
     }
-}
 
-/// Check if an installation exists (stub for unsupported platforms)
-#[cfg(not(any(target_os = "windows", target_os = "linux")))]
-pub fn check_installation_exists(_config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>> {
-    Ok(None)
-}
+    /// Set the path to systemctl (Linux only)
+    pub fn systemctl_path(mut self, path: PathBuf) -> Self {
+        self.systemctl_path = Some(path);
+        self
+    }
 
-#[derive(Debug, Clone, Serialize)]
-/// Installation manager for handling application installations
-pub struct InstallationManager {
-    is_installed: bool,
-    current_version: Option<Version>,
-    latest_version: Option<Version>,
-    config: InstallationConfig,
-    #[serde(skip)]
-    progress_tx: broadcast::Sender<StateProgress>,
-}
+    /// Set the minimum time between update checks made through check_for_updates/update
+    pub fn update_check_interval(mut self, interval: Duration) -> Self {
+        self.update_check_interval = Some(interval);
+        self
+    }
 
-impl InstallationManager {
-    /// Create a new installation manager with configuration
-    pub fn new(config: InstallationConfig) -> Self {
-        let (tx, _) = broadcast::channel(100);
-        Self {
-            is_installed: false,
-            current_version: None,
-            latest_version: None,
-            config,
-            progress_tx: tx,
-        }
+    /// Set how long to wait for the service to stop gracefully before an update
+    pub fn stop_timeout(mut self, timeout: Duration) -> Self {
+        self.stop_timeout = timeout;
+        self
     }
 
-    /// Create a new installation manager with basic parameters
-    pub fn with_defaults(
-        install_path: PathBuf,
-        github_repo: String,
-        service_name: String,
-    ) -> Self {
-        Self::new(InstallationConfig::new(install_path, github_repo, service_name))
+    /// Force-kill the service if it hasn't stopped this long after the stop request
+    pub fn force_stop_after(mut self, after: Duration) -> Self {
+        self.force_stop_after = Some(after);
+        self
     }
 
-    /// Get a reference to the configuration
-    pub fn config(&self) -> &InstallationConfig {
-        &self.config
+    /// Set whether OS quarantine markers are cleared from installed files so they aren't blocked from running
+    pub fn clear_quarantine(mut self, clear: bool) -> Self {
+        self.clear_quarantine = clear;
+        self
     }
 
-    /// Subscribe to progress updates
-    pub fn subscribe(&self) -> broadcast::Receiver<StateProgress> {
-        self.progress_tx.subscribe()
+    /// Set whether the stable channel follows the release the repository marks as "latest" rather than the newest by date
+    pub fn use_latest_endpoint(mut self, enabled: bool) -> Self {
+        self.use_latest_endpoint = enabled;
+        self
     }
 
-    /// Broadcast progress update (internal helper)
-    fn broadcast_progress(&self, state: State, progress: f32) {
-        let _ = self.progress_tx.send(StateProgress::new(state, progress));
+    /// Set how long a release must have been published before it is installed or offered as an update
+    pub fn min_release_age(mut self, age: Duration) -> Self {
+        self.min_release_age = Some(age);
+        self
     }
 
-    /// Check if the application is currently installed
-    pub fn is_installed(&self) -> bool {
-        self.is_installed
+    /// Set the most bytes an archive may expand to, guarding against decompression bombs
+    pub fn max_uncompressed_size(mut self, bytes: u64) -> Self {
+        self.max_uncompressed_size = Some(bytes);
+        self
     }
 
-    /// Get the current installed version
-    pub fn current_version(&self) -> Option<&Version> {
-        self.current_version.as_ref()
+    /// Set whether a failed update restores the previous files and restarts the old service
+    pub fn rollback_on_failure(mut self, enabled: bool) -> Self {
+        self.rollback_on_failure = enabled;
+        self
     }
 
-    /// Get the latest available version
-    pub fn latest_version(&self) -> Option<&Version> {
-        self.latest_version.as_ref()
+    /// Set the GitHub token used to authenticate requests, raising the API rate limit and allowing private repositories
+    pub fn github_token(mut self, token: String) -> Self {
+        self.github_token = Some(token);
+        self
     }
 
-    /// Get the install path from registry (Windows) or config file (Linux)
-    pub fn get_install_path(&self) -> Option<PathBuf> {
-        #[cfg(target_os = "windows")]
-        {
-            win::get_install_path(&self.config).ok().flatten()
-        }
+    /// Set how long to wait for a connection to GitHub
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
 
-        #[cfg(target_os = "linux")]
-        {
-            // For Linux, return the configured install path if installed
-            if self.is_installed {
-                Some(self.config.install_path.clone())
-            } else {
-                None
-            }
-        }
+    /// Set the time limit for API requests, which is also the longest a download may stall
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
 
-        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
-        {
-            None
-        }
+    /// Set how many times a request or download that fails transiently is retried (0 disables retries)
+    pub fn retry_count(mut self, retries: u32) -> Self {
+        self.retry_count = retries;
+        self
     }
 
-    /// Fetch releases from GitHub
-    pub async fn fetch_releases(&self) -> Result<Vec<GitHubRelease>> {
-        let url = format!(
-            "https://api.github.com/repos/{}/releases",
-            self.config.github_repo
-        );
+    /// Set the free space required on the install volume, for archives whose compression ratio is known
+    pub fn min_free_bytes(mut self, bytes: u64) -> Self {
+        self.min_free_bytes = Some(bytes);
+        self
+    }
 
-        let client = reqwest::Client::builder()
-            .user_agent("obsidian-installation-manager")
-            .build()
-            .context("Failed to create HTTP client")?;
+    /// Set where downloads are kept until extracted, e.g. when the volume next to install_path is too small
+    pub fn temp_dir(mut self, dir: PathBuf) -> Self {
+        self.temp_dir = Some(dir);
+        self
+    }
 
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .context(format!(
-                "Failed to connect to GitHub API. Please check your internet connection and try again. URL: {}",
-                url
-            ))?;
+    /// Set a pattern asset names must match, e.g. to skip `-debug` builds next to the release build
+    pub fn asset_filter(mut self, filter: Regex) -> Self {
+        self.asset_filter = Some(filter);
+        self
+    }
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
+    /// Add a file or directory (relative to install_path) of user data that uninstall should keep
+    pub fn data_dir(mut self, dir: PathBuf) -> Self {
+        self.data_dirs.push(dir);
+        self
+    }
 
-            let error_message = match status.as_u16() {
-                404 => format!(
-                    "Repository '{}' not found. Please verify the repository name is correct.",
-                    self.config.github_repo
-                ),
-                403 => format!(
-                    "GitHub API rate limit exceeded or access denied. Please try again later. Details: {}",
-                    error_body
-                ),
-                401 => "GitHub API authentication failed. The repository may be private.".to_string(),
-                _ => format!(
-                    "GitHub API error (status {}): {}",
-                    status,
-                    if error_body.is_empty() { "No additional details" } else { &error_body }
-                ),
-            };
+    /// Set when the service is restarted after it exits
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
 
-            anyhow::bail!(error_message);
-        }
+    /// Set how many seconds to wait before restarting the service
+    pub fn restart_sec(mut self, seconds: u64) -> Self {
+        self.restart_sec = Some(seconds);
+        self
+    }
 
-        let releases: Vec<GitHubRelease> = response
-            .json()
-            .await
-            .context("Failed to parse GitHub API response. The API response format may have changed.")?;
+    /// Run the Windows service under this account instead of LocalSystem
+    pub fn service_account(mut self, account: ServiceAccount) -> Self {
+        self.service_account = Some(account);
+        self
+    }
 
-        Ok(releases)
+    /// Install `.deb` or `.rpm` assets through the package manager on Linux hosts that use it
+    pub fn system_package(mut self, enabled: bool) -> Self {
+        self.system_package = enabled;
+        self
     }
 
-    /// Get the latest version for each channel without fetching releases multiple times
-    pub async fn get_channel_versions(&mut self) -> Result<(Option<Version>, Option<Version>, Option<Version>)> {
-        let releases = self.fetch_releases().await?;
+    /// Set the name of the package in the package database, if it differs from the service name
+    pub fn package_name(mut self, name: String) -> Self {
+        self.package_name = Some(name);
+        self
+    }
 
-        println!("Found {} releases from GitHub", releases.len());
+    /// Set a health check the service must pass after starting during install and update
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.health_check = Some(check);
+        self
+    }
 
-        if releases.is_empty() {
-            return Ok((None, None, None));
-        }
+    /// Set how many leading path components to remove from archive entries during extraction
+    pub fn strip_components(mut self, components: usize) -> Self {
+        self.strip_components = components;
+        self
+    }
 
-        let mut release_version: Option<Version> = None;
-        let mut beta_version: Option<Version> = None;
-        let mut alpha_version: Option<Version> = None;
+    /// Set whether to drop the top-level folder of archives that wrap everything in one, e.g. `app-1.2.3/`
+    pub fn strip_single_root(mut self, enabled: bool) -> Self {
+        self.strip_single_root = enabled;
+        self
+    }
 
-        // Parse all releases and categorize them
-        for release in &releases {
-            let version_str = release.tag_name.trim_start_matches('v');
-            println!("Parsing release: {} (prerelease: {})", release.tag_name, release.prerelease);
+    /// Set how installs and updates treat files already in install_path
+    pub fn extract_policy(mut self, policy: ExtractPolicy) -> Self {
+        self.extract_policy = Some(policy);
+        self
+    }
 
-            match Version::parse(version_str) {
-                Ok(version) => {
-                    println!("  Parsed as semver: {} (pre: {:?})", version, version.pre);
+    /// Set whether install adds a Start Menu shortcut (Windows) or application launcher (Linux), removed again on uninstall
+    pub fn create_shortcuts(mut self, enabled: bool) -> Self {
+        self.create_shortcuts = enabled;
+        self
+    }
 
-                    // If GitHub marks this as a prerelease, it should NOT match Release channel
-                    // Check for Release channel (stable only - no pre-release in semver AND not marked as prerelease by GitHub)
-                    if release_version.is_none() && !release.prerelease && ReleaseChannel::Release.matches_version(&version) {
-                        println!("  -> Matches Release channel");
-                        release_version = Some(version.clone());
-                    }
+    /// Set whether the shortcuts also include one on the Desktop (Windows only)
+    pub fn desktop_shortcut(mut self, enabled: bool) -> Self {
+        self.desktop_shortcut = enabled;
+        self
+    }
 
-                    // Check for Beta channel (beta/rc releases OR stable releases)
-                    // If GitHub marks it as prerelease, check if it's beta/rc, otherwise only stable
-                    if beta_version.is_none() {
-                        let matches = if release.prerelease {
-                            // For GitHub prereleases, only match if it's actually beta/rc in semver
-                            let pre_str = version.pre.to_string().to_lowercase();
-                            pre_str.contains("beta") || pre_str.contains("rc")
-                        } else {
-                            // Stable releases always match beta channel
-                            ReleaseChannel::Beta.matches_version(&version)
-                        };
+    /// Set the command Apps & features runs to uninstall, which also lists the installation there (Windows only)
+    pub fn uninstall_command(mut self, command: String) -> Self {
+        self.uninstall_command = Some(command);
+        self
+    }
 
-                        if matches {
-                            println!("  -> Matches Beta channel");
-                            beta_version = Some(version.clone());
-                        }
-                    }
+    /// Set the publisher shown in Apps & features
+    pub fn publisher(mut self, publisher: String) -> Self {
+        self.publisher = Some(publisher);
+        self
+    }
 
-                    // Check for Alpha channel (all versions)
-                    if alpha_version.is_none() && ReleaseChannel::Alpha.matches_version(&version) {
-                        println!("  -> Matches Alpha channel");
-                        alpha_version = Some(version.clone());
-                    }
+    /// Require every downloaded asset to carry a valid signature from this key before it is extracted
+    pub fn signature(mut self, signature: SignatureConfig) -> Self {
+        self.signature = Some(signature);
+        self
+    }
 
-                    // Early exit if we found all three
-                    if release_version.is_some() && beta_version.is_some() && alpha_version.is_some() {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    println!("  Failed to parse as semver: {}", e);
-                }
-            }
-        }
+    /// Download large assets over several connections at once, for high-latency links
+    pub fn parallel_download(mut self, enabled: bool) -> Self {
+        self.parallel_download = enabled;
+        self
+    }
 
-        println!("Final versions - Release: {:?}, Beta: {:?}, Alpha: {:?}",
-                 release_version, beta_version, alpha_version);
+    /// Set how many connections a parallel download uses
+    pub fn download_connections(mut self, connections: usize) -> Self {
+        self.download_connections = connections;
+        self
+    }
 
-        Ok((release_version, beta_version, alpha_version))
+    /// Set how many progress updates a `subscribe` receiver can fall behind before it lags
+    pub fn progress_channel_capacity(mut self, capacity: usize) -> Self {
+        self.progress_channel_capacity = capacity;
+        self
     }
 
-    /// Get the latest release for the specified channel
-    pub async fn get_latest_release(&mut self, channel: ReleaseChannel) -> Result<GitHubRelease> {
-        let releases = self.fetch_releases().await?;
+    /// Cap the average speed of each download, e.g. so it doesn't starve a server sharing the connection
+    pub fn max_download_bytes_per_sec(mut self, bytes_per_sec: u64) -> Self {
+        self.max_download_bytes_per_sec = Some(bytes_per_sec);
+        self
+    }
 
-        if releases.is_empty() {
-            anyhow::bail!(
-                "No releases found for repository '{}'. Please ensure the repository has published releases.",
-                self.config.github_repo
-            );
-        }
+    /// Send GitHub requests and downloads through this proxy instead of the one from the environment
+    pub fn proxy(mut self, proxy: String) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
 
-        let total_releases = releases.len();
+    /// Cache the releases list on disk and reuse it for `ttl`, e.g. when a GUI polls for updates
+    pub fn release_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.release_cache_ttl = Some(ttl);
+        self
+    }
 
-        // Find the first release that matches the channel
-        let mut matching_release = None;
-        for release in releases {
-            let version_str = release.tag_name.trim_start_matches('v');
+    /// Talk to the GitHub API at `url` instead of api.github.com, e.g. a GitHub Enterprise Server's "https://github.example.com/api/v3"
+    pub fn api_base_url(mut self, url: String) -> Self {
+        self.api_base_url = Some(url);
+        self
+    }
 
-            // Try to parse the version
-            if let Ok(version) = Version::parse(version_str) {
-                // Check if this version matches the requested channel
-                let matches = match channel {
-                    ReleaseChannel::Release => {
-                        // Must not be marked as prerelease by GitHub AND have no semver pre-release
-                        !release.prerelease && version.pre.is_empty()
-                    }
-                    ReleaseChannel::Beta => {
-                        if release.prerelease {
-                            // For GitHub prereleases, must be beta or rc
-                            let pre_str = version.pre.to_string().to_lowercase();
-                            pre_str.contains("beta") || pre_str.contains("rc")
-                        } else {
-                            // Stable releases match beta channel
-                            true
-                        }
-                    }
-                    ReleaseChannel::Alpha => {
-                        // All versions match alpha channel
-                        true
-                    }
-                };
+    /// Download assets from `url` instead of GitHub, keeping the path of each asset's download URL
+    ///
+    /// With "https://mirror.example.com/gh", `https://github.com/owner/repo/releases/download/v1.0.0/app.zip`
+    /// is fetched from `https://mirror.example.com/gh/owner/repo/releases/download/v1.0.0/app.zip`.
+    /// The GitHub token isn't sent to the mirror.
+    pub fn download_base_url(mut self, url: String) -> Self {
+        self.download_base_url = Some(url);
+        self
+    }
 
-                if matches {
-                    matching_release = Some((release, version));
-                    break;
-                }
-            }
-        }
+    /// Set the permission policy applied to the install tree (Unix only)
+    pub fn file_permission_policy(mut self, policy: FilePermissionPolicy) -> Self {
+        self.file_permission_policy = Some(policy);
+        self
+    }
 
-        match matching_release {
-            Some((release, version)) => {
-                self.latest_version = Some(version);
-                Ok(release)
-            }
-            None => {
-                let channel_name = channel.display_name();
-                anyhow::bail!(
-                    "No releases found in the '{}' channel for repository '{}'. Total releases available: {}. Try selecting a different channel.",
-                    channel_name,
-                    self.config.github_repo,
-                    total_releases
-                )
-            }
-        }
+    /// Render the asset template for a release and architecture (None if no template is set)
+    pub fn render_asset_template(&self, release: &GitHubRelease, arch: &Architecture) -> Option<String> {
+        self.asset_template.as_ref().map(|template| {
+            template
+                .replace("{version}", release.tag_name.trim_start_matches('v'))
+                .replace("{os}", arch.os_name())
+                .replace("{arch}", arch.arch_name())
+                .replace("{ext}", arch.archive_extension())
+        })
     }
 
-    /// Check for updates on the specified channel
-    pub async fn check_for_updates(&mut self, channel: ReleaseChannel) -> Result<bool> {
-        let _latest = self.get_latest_release(channel).await?;
+    /// Get the service display name (returns service_name if not set)
+    pub fn get_display_name(&self) -> &str {
+        self.service_display_name.as_deref().unwrap_or(&self.service_name)
+    }
 
-        #[cfg(target_os = "windows")]
-        {
-            self.current_version = win::get_installed_version(&self.config)?;
-        }
+    /// Get the name of the package in the package database (returns service_name if not set)
+    pub fn get_package_name(&self) -> &str {
+        self.package_name.as_deref().unwrap_or(&self.service_name)
+    }
 
-        #[cfg(target_os = "linux")]
-        {
-            self.current_version = nix::get_installed_version(&self.config)?;
+    /// Get the extraction policy for an install, or an update when `updating` (returns
+    /// Overwrite for installs and CleanThenExtract for updates if not set)
+    pub fn get_extract_policy(&self, updating: bool) -> ExtractPolicy {
+        match self.extract_policy {
+            Some(policy) => policy,
+            None if updating => ExtractPolicy::CleanThenExtract,
+            None => ExtractPolicy::Overwrite,
         }
+    }
 
-        self.is_installed = self.current_version.is_some();
+    /// Get the publisher (returns the owner of github_repo if not set)
+    pub fn get_publisher(&self) -> &str {
+        self.publisher
+            .as_deref()
+            .unwrap_or_else(|| self.github_repo.split('/').next().unwrap_or_default())
+    }
 
-        Ok(match &self.current_version {
-            Some(current) => self.latest_version.as_ref().map_or(false, |latest| latest > current),
-            None => true, // No version installed, update available
+    /// Get the service description (returns a default if not set)
+    pub fn get_description(&self) -> String {
+        self.service_description.clone().unwrap_or_else(|| {
+            format!("{} Service", self.get_display_name())
         })
     }
 
-    /// Select the appropriate asset for the current architecture
-    pub fn select_asset(&self, release: &GitHubRelease) -> Result<GitHubAsset> {
-        let arch = Architecture::detect()?;
-        let patterns = arch.asset_patterns();
-
-        if release.assets.is_empty() {
-            anyhow::bail!(
-                "Release '{}' has no downloadable assets. The release may not be properly configured.",
-                release.tag_name
-            );
-        }
-
-        // Try to find an asset that matches the architecture patterns
-        for asset in &release.assets {
-            let name_lower = asset.name.to_lowercase();
+    /// Get the GitHub token (returns the GITHUB_TOKEN environment variable if not set)
+    pub fn get_github_token(&self) -> Option<String> {
+        self.github_token
+            .clone()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .filter(|token| !token.trim().is_empty())
+    }
 
-            // Count how many patterns match
-            let match_count = patterns.iter()
-                .filter(|&&p| name_lower.contains(p))
-                .count();
+    /// Get the GitHub API base URL (returns https://api.github.com if not set)
+    pub fn get_api_base_url(&self) -> &str {
+        self.api_base_url.as_deref().unwrap_or(DEFAULT_API_BASE_URL)
+    }
 
-            // If we match multiple patterns, it's likely the right asset
-            if match_count >= 2 {
-                return Ok(asset.clone());
+    /// Check that `api_base_url` and `download_base_url` are absolute http(s) URLs
+    pub fn validate_urls(&self) -> Result<(), InstallError> {
+        let urls = [("api_base_url", &self.api_base_url), ("download_base_url", &self.download_base_url)];
+        for (field, url) in urls {
+            if let Some(url) = url {
+                parse_base_url(field, url)?;
             }
         }
+        Ok(())
+    }
+
+    /// Get the file name a bare binary asset is installed as (the primary binary, or service_name
+    /// if not set, with `.exe` added on Windows)
+    pub fn get_binary_file_name(&self) -> String {
+        let name = self.get_binary_name().unwrap_or(&self.service_name);
+        if cfg!(target_os = "windows") && !name.to_lowercase().ends_with(".exe") {
+            format!("{}.exe", name)
+        } else {
+            name.to_string()
+        }
+    }
 
-        // Fallback: try to match at least one pattern
-        for asset in &release.assets {
-            let name_lower = asset.name.to_lowercase();
-            if patterns.iter().any(|&p| name_lower.contains(p)) {
-                return Ok(asset.clone());
+    /// Get the directory an install downloads into: `oim-<service_name>` inside temp_dir, or
+    /// `<install_path>.oim-tmp` next to install_path so extracting stays on one volume
+    pub fn get_temp_dir(&self) -> PathBuf {
+        match &self.temp_dir {
+            Some(dir) => dir.join(format!("oim-{}", self.service_name)),
+            None => {
+                let mut name = self.install_path.file_name().unwrap_or_default().to_os_string();
+                name.push(".oim-tmp");
+                self.install_path.with_file_name(name)
             }
         }
+    }
 
-        let available_assets: Vec<String> = release.assets.iter()
-            .map(|a| a.name.clone())
-            .collect();
+    /// Get the working directory (returns install_path if not set)
+    pub fn get_working_directory(&self) -> &PathBuf {
+        self.working_directory.as_ref().unwrap_or(&self.install_path)
+    }
 
-        anyhow::bail!(
-            "No compatible asset found for your platform ({:?}). Expected patterns: {:?}. Available assets: {}",
-            arch,
-            patterns,
-            available_assets.join(", ")
-        )
+    /// Build the environment for hook and probe commands
+    ///
+    /// Contains `service_env` plus `OIM_INSTALL_PATH`, `OIM_VERSION`, `OIM_SERVICE_NAME` and
+    /// `OIM_WORKING_DIR`; the `OIM_*` variables take precedence over user-supplied values.
+    pub fn hook_environment(&self, version: &str) -> HashMap<String, String> {
+        let mut env = self.service_env.clone();
+        env.insert("OIM_INSTALL_PATH".to_string(), self.install_path.to_string_lossy().into_owned());
+        env.insert("OIM_VERSION".to_string(), version.trim_start_matches('v').to_string());
+        env.insert("OIM_SERVICE_NAME".to_string(), self.service_name.clone());
+        env.insert("OIM_WORKING_DIR".to_string(), self.get_working_directory().to_string_lossy().into_owned());
+        env
     }
 
-    /// Download a release asset
-    pub async fn download_asset(&self, asset: &GitHubAsset, dest_path: &PathBuf) -> Result<()> {
-        use futures::StreamExt;
-        use tokio::io::AsyncWriteExt;
+    /// Get the registry path (Windows)
+    pub fn get_registry_path(&self) -> &str {
+        self.registry_path.as_deref().unwrap_or(r"SOFTWARE\ObsidianInstallationManager")
+    }
 
-        let client = reqwest::Client::builder()
-            .user_agent("obsidian-installation-manager")
-            .build()
-            .context("Failed to create HTTP client for download")?;
+    /// Get the delay before the service is restarted after exiting
+    pub fn get_restart_sec(&self) -> u64 {
+        self.restart_sec.unwrap_or(10)
+    }
 
-        let response = client
-            .get(&asset.browser_download_url)
-            .send()
-            .await
-            .context(format!(
-                "Failed to connect to download URL. Please check your internet connection. File: {}",
-                asset.name
-            ))?;
+    /// Get the service's environment variables, sorted by name
+    pub fn get_service_env(&self) -> Vec<(&str, &str)> {
+        let mut env: Vec<(&str, &str)> = self.service_env.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        env.sort_unstable();
+        env
+    }
 
-        if !response.status().is_success() {
-            anyhow::bail!(
-                "Download failed for '{}' with status: {}. The file may no longer be available.",
-                asset.name,
-                response.status()
-            );
+    /// Get the version file directory (Linux and macOS)
+    pub fn get_version_file_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.version_file_dir {
+            return PathBuf::from(dir);
         }
 
-        let total_size = asset.size;
-        let mut file = tokio::fs::File::create(dest_path)
-            .await
-            .context(format!(
-                "Failed to create file at '{}'. Check disk space and write permissions.",
-                dest_path.display()
-            ))?;
-
-        let mut downloaded: u64 = 0;
-        let mut stream = response.bytes_stream();
+        #[cfg(target_os = "linux")]
+        if self.install_scope == InstallScope::User {
+            return nix::user_data_dir().join("oim");
+        }
 
-        self.broadcast_progress(State::Downloading, 0.0);
+        #[cfg(target_os = "macos")]
+        let default = "/Library/Application Support/oim";
 
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.context(format!(
-                "Network error while downloading '{}'. The connection may have been interrupted.",
-                asset.name
-            ))?;
+        #[cfg(not(target_os = "macos"))]
+        let default = "/var/lib/oim";
 
-            file.write_all(&chunk)
-                .await
-                .context(format!(
-                    "Failed to write to '{}'. Check available disk space.",
-                    dest_path.display()
-                ))?;
+        PathBuf::from(default)
+    }
 
-            downloaded += chunk.len() as u64;
+    /// Get the path of the install log (version file directory on Linux and macOS, working directory elsewhere)
+    pub fn get_install_log_path(&self) -> PathBuf {
+        let file_name = format!("{}.install.log", self.service_name);
 
-            if total_size > 0 {
-                let progress = downloaded as f32 / total_size as f32;
-                self.broadcast_progress(State::Downloading, progress);
-            }
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            self.get_version_file_dir().join(file_name)
         }
 
-        self.broadcast_progress(State::Downloading, 1.0);
-        Ok(())
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            self.get_working_directory().join(file_name)
+        }
     }
 
-    /// Extract downloaded archive
-    pub fn extract_archive(&self, archive_path: &PathBuf, extract_to: &PathBuf) -> Result<()> {
-        self.broadcast_progress(State::Extracting, 0.0);
-        std::fs::create_dir_all(extract_to)
-            .context(format!(
-                "Failed to create extraction directory '{}'. Check write permissions.",
-                extract_to.display()
-            ))?;
-
-        let file_name = archive_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .context(format!("Invalid archive path: {}", archive_path.display()))?;
+    /// Get the path of the record of directories the first install created (version file directory on Linux and
+    /// macOS, working directory elsewhere)
+    pub fn get_created_dirs_path(&self) -> PathBuf {
+        let file_name = format!("{}.dirs.json", self.service_name);
 
-        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
-            self.extract_tar_gz(archive_path, extract_to)
-                .context(format!("Failed to extract TAR.GZ archive '{}'", file_name))?;
-        } else if file_name.ends_with(".zip") {
-            self.extract_zip(archive_path, extract_to)
-                .context(format!("Failed to extract ZIP archive '{}'", file_name))?;
-        } else {
-            anyhow::bail!(
-                "Unsupported archive format: '{}'. Supported formats: .zip, .tar.gz, .tgz",
-                file_name
-            );
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            self.get_version_file_dir().join(file_name)
         }
 
-        // Progress is now reported from within the extraction functions
-        Ok(())
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            self.get_working_directory().join(file_name)
+        }
     }
 
-    fn extract_tar_gz(&self, archive_path: &PathBuf, extract_to: &PathBuf) -> Result<()> {
-        let file = std::fs::File::open(archive_path)?;
-        let decoder = flate2::read::GzDecoder::new(file);
-        let mut archive = tar::Archive::new(decoder);
+    /// Get the path of the cached releases list (version file directory on Linux and macOS, working directory elsewhere)
+    pub fn get_release_cache_path(&self) -> PathBuf {
+        let file_name = format!("{}.releases.json", self.service_name);
 
-        // First pass: calculate total bytes to extract
-        let file_for_count = std::fs::File::open(archive_path)?;
-        let decoder_for_count = flate2::read::GzDecoder::new(file_for_count);
-        let mut archive_for_count = tar::Archive::new(decoder_for_count);
-        let total_bytes: u64 = archive_for_count
-            .entries()?
-            .filter_map(|e| e.ok())
-            .map(|e| e.header().size().unwrap_or(0))
-            .sum();
-
-        // Second pass: extract with progress based on bytes
-        let mut extracted_bytes: u64 = 0;
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let entry_size = entry.header().size().unwrap_or(0);
-            entry.unpack_in(extract_to)?;
-
-            extracted_bytes += entry_size;
-            let progress = if total_bytes > 0 {
-                extracted_bytes as f32 / total_bytes as f32
-            } else {
-                1.0
-            };
-            self.broadcast_progress(State::Extracting, progress);
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            self.get_version_file_dir().join(file_name)
         }
 
-        Ok(())
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            self.get_working_directory().join(file_name)
+        }
     }
 
-    fn extract_zip(&self, archive_path: &PathBuf, extract_to: &std::path::Path) -> Result<()> {
+    /// Get the path of the channel lock record (version file directory on Linux and macOS, working directory elsewhere)
+    pub fn get_channel_lock_path(&self) -> PathBuf {
+        let file_name = format!("{}.channel.json", self.service_name);
+
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            self.get_version_file_dir().join(file_name)
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            self.get_working_directory().join(file_name)
+        }
+    }
+
+    /// The records this library keeps about the installation, which sit inside install_path
+    /// on platforms where the working directory is install_path
+    pub(crate) fn metadata_paths(&self) -> [PathBuf; 4] {
+        [
+            self.get_install_log_path(),
+            self.get_channel_lock_path(),
+            self.get_created_dirs_path(),
+            self.get_release_cache_path(),
+        ]
+    }
+}
+
+/// Check if an installation exists by querying the system (Windows registry or Linux version file)
+#[cfg(target_os = "windows")]
+pub fn check_installation_exists(config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>, InstallError> {
+    let version = win::get_installed_version(config)?;
+    let path = win::get_install_path(config)?;
+
+    match (version, path) {
+        (Some(v), Some(p)) => Ok(Some((v, p))),
+        _ => Ok(None),
+    }
+}
+
+/// Check if an installation exists by querying the system (Windows registry or Linux/macOS version file)
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn check_installation_exists(config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>, InstallError> {
+    #[cfg(target_os = "linux")]
+    let version = nix::get_installed_version(config)?;
+
+    #[cfg(target_os = "macos")]
+    let version = mac::get_installed_version(config)?;
+
+    match version {
+        Some(v) => Ok(Some((v, config.install_path.clone()))),
+        None => Ok(None),
+    }
+}
+
+/// Check if an installation exists (stub for unsupported platforms)
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn check_installation_exists(_config: &InstallationConfig) -> Result<Option<(Version, PathBuf)>, InstallError> {
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Installation manager for handling application installations
+pub struct InstallationManager {
+    is_installed: bool,
+    current_version: Option<Version>,
+    latest_version: Option<Version>,
+    config: InstallationConfig,
+    #[serde(skip)]
+    progress_tx: progress::ProgressSender,
+    #[serde(skip)]
+    install_log: install_log::InstallLog,
+    /// Set once any operation scheduled work that only completes on reboot
+    #[serde(skip)]
+    reboot_pending: Arc<AtomicBool>,
+    /// Set while an install/update/repair/uninstall runs on this manager or any clone of it
+    #[serde(skip)]
+    busy: Arc<AtomicBool>,
+    /// When the last update check ran and whether it found an update, shared with clones
+    #[serde(skip)]
+    last_update_check: Arc<Mutex<Option<(Instant, bool)>>>,
+    /// Print what install/update would do instead of doing it
+    dry_run: bool,
+    /// Receives status messages and warnings, shared with clones
+    #[serde(skip)]
+    observer: Observer,
+    /// Platform to select assets for instead of the detected one
+    #[serde(skip)]
+    target_architecture: Option<Architecture>,
+}
+
+/// Outcome of the debounced `check_for_updates` and `update`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// A check ran and found a newer release (or nothing is installed)
+    UpdateAvailable,
+    /// A check ran and the installed version is current
+    UpToDate,
+    /// An update was applied
+    Updated,
+    /// Another operation is in progress, so nothing was checked
+    Busy,
+    /// A check already ran within `update_check_interval`; its result is reused without a network call
+    CheckedRecently { update_available: bool },
+}
+
+impl UpdateStatus {
+    /// Whether a newer release is available, as far as this status tells
+    pub fn update_available(&self) -> bool {
+        matches!(self, UpdateStatus::UpdateAvailable | UpdateStatus::CheckedRecently { update_available: true })
+    }
+}
+
+/// What `install_with_options` installs and whether it may reinstall the installed version
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallOptions {
+    /// Consider prereleases of every kind (the Alpha channel) instead of stable releases only (defaults to false)
+    #[serde(default)]
+    pub include_prerelease: bool,
+    /// Download and install again even when the chosen version is already installed (defaults to false)
+    #[serde(default)]
+    pub force: bool,
+    /// Install this version instead of the channel's newest release (optional)
+    #[serde(default)]
+    pub version: Option<Version>,
+}
+
+impl InstallOptions {
+    /// Install the newest stable release, skipping it if it's already installed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether prereleases are considered
+    pub fn include_prerelease(mut self, include: bool) -> Self {
+        self.include_prerelease = include;
+        self
+    }
+
+    /// Set whether the installed version is reinstalled when it's the one chosen
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Install this exact version
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// The channel the newest release is picked from
+    fn channel(&self) -> ReleaseChannel {
+        if self.include_prerelease { ReleaseChannel::Alpha } else { ReleaseChannel::Release }
+    }
+}
+
+/// A successful GitHub API response, or word that the copy with the ETag sent is still current
+enum ApiResponse<T> {
+    Fresh { body: T, etag: Option<String> },
+    NotModified,
+}
+
+impl InstallationManager {
+    /// Create a new installation manager with configuration
+    pub fn new(config: InstallationConfig) -> Self {
+        // A broadcast channel needs room for at least one update
+        let capacity = config.progress_channel_capacity.max(1);
+        Self {
+            is_installed: false,
+            current_version: None,
+            latest_version: None,
+            config,
+            progress_tx: progress::ProgressSender::new(capacity),
+            install_log: install_log::InstallLog::default(),
+            reboot_pending: Arc::new(AtomicBool::new(false)),
+            busy: Arc::new(AtomicBool::new(false)),
+            last_update_check: Arc::new(Mutex::new(None)),
+            dry_run: false,
+            observer: Observer::default(),
+            target_architecture: None,
+        }
+    }
+
+    /// Create a new installation manager with basic parameters
+    pub fn with_defaults(
+        install_path: PathBuf,
+        github_repo: String,
+        service_name: String,
+    ) -> Self {
+        Self::new(InstallationConfig::new(install_path, github_repo, service_name))
+    }
+
+    /// Get a reference to the configuration
+    pub fn config(&self) -> &InstallationConfig {
+        &self.config
+    }
+
+    /// Subscribe to progress updates
+    ///
+    /// The channel holds the last 100 updates. A receiver that falls further behind (e.g. a paused
+    /// GUI) gets `RecvError::Lagged` once, skipping the oldest updates, and should keep calling
+    /// `recv`; only `RecvError::Closed` means no more updates will come. Download progress is sent
+    /// once per whole percent, and every phase ends with an update at 1.0, so a lagging receiver
+    /// still sees each phase finish.
+    pub fn subscribe(&self) -> broadcast::Receiver<StateProgress> {
+        self.progress_tx.subscribe()
+    }
+
+    /// Stream every progress update from now on, without the lag and loss of `subscribe`
+    ///
+    /// Updates queue up until the stream is polled, so a consumer that falls behind still sees
+    /// each one, in order. The stream ends once the manager and all its clones are dropped.
+    pub fn progress_stream(&self) -> impl Stream<Item = StateProgress> + Send + Unpin + 'static {
+        self.progress_tx.stream()
+    }
+
+    /// Watch the most recent progress update (`State::Idle` until the first one)
+    ///
+    /// Only the latest value is kept, so this suits a UI that redraws on `changed().await` and
+    /// doesn't need every intermediate step.
+    pub fn watch_progress(&self) -> watch::Receiver<StateProgress> {
+        self.progress_tx.watch()
+    }
+
+    /// Number of receivers returned by `subscribe` (on this manager or its clones) that are still alive
+    pub fn subscriber_count(&self) -> usize {
+        self.progress_tx.receiver_count()
+    }
+
+    /// Send status messages, warnings and phase changes to `observer`
+    ///
+    /// Without an observer the manager prints nothing; use `ConsoleObserver` for command-line output.
+    pub fn set_observer(&mut self, observer: Arc<dyn InstallObserver>) {
+        self.observer = Observer::new(observer);
+    }
+
+    /// Broadcast progress update (internal helper)
+    fn broadcast_progress(&self, state: State, progress: f32) {
+        self.observer.phase(&state);
+        self.install_log.record_progress(&state, progress);
+        self.progress_tx.send(StateProgress::new(state, progress));
+    }
+
+    /// Broadcast download progress with byte counts and speed (`total` of 0 means unknown size)
+    ///
+    /// Chunks that don't move progress on by a whole percent are skipped, so fast downloads
+    /// don't flood the channel.
+    fn broadcast_download(&self, done: u64, total: u64, rate: &mut transfer::TransferRate) {
+        if !rate.should_report(done, total) {
+            return;
+        }
+        let progress = if total > 0 { (done as f32 / total as f32).min(1.0) } else { 0.0 };
+        self.observer.phase(&State::Downloading);
+        self.install_log.record_progress(&State::Downloading, progress);
+        self.progress_tx.send(StateProgress {
+            bytes_done: Some(done),
+            bytes_total: (total > 0).then_some(total),
+            bytes_per_sec: rate.bytes_per_sec(),
+            eta: (total > 0).then(|| rate.eta(total.saturating_sub(done))).flatten(),
+            ..StateProgress::new(State::Downloading, progress)
+        });
+    }
+
+    /// Read back the entries of the most recent install or repair from the persisted install log
+    pub fn last_install_log(&self) -> Result<Vec<LogEntry>, InstallError> {
+        Ok(install_log::read_last_session(&self.config.get_install_log_path())?)
+    }
+
+    /// Re-apply permissions across the install tree after restores or manual copies
+    ///
+    /// On Unix this applies the configured `file_permission_policy` (or the default policy),
+    /// keeping the configured binary executable. On Windows it re-grants the ACLs the service needs.
+    pub fn normalize_permissions(&self) -> Result<(), InstallError> {
+        let _guard = self.begin_operation()?;
+
+        if !self.config.install_path.exists() {
+            return Err(anyhow::anyhow!("Install path '{}' does not exist", self.config.install_path.display()).into());
+        }
+
+        Ok(self.apply_permission_policy(&self.config.file_permission_policy.clone().unwrap_or_default())?)
+    }
+
+    #[cfg(unix)]
+    fn apply_permission_policy(&self, policy: &FilePermissionPolicy) -> Result<()> {
+        let install_path = &self.config.install_path;
+        let executables: Vec<PathBuf> = self.config.binary_names().into_iter()
+            .flat_map(|name| [install_path.join(name), install_path.join("bin").join(name)])
+            .collect();
+
+        permissions::normalize_tree(install_path, policy, &executables)
+    }
+
+    #[cfg(not(unix))]
+    fn apply_permission_policy(&self, _policy: &FilePermissionPolicy) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            win::set_directory_permissions(&self.config)
+                .context("Failed to set directory permissions")?;
+        }
+
+        Ok(())
+    }
+
+    fn requested_channel_lock(&self, channel: ReleaseChannel) -> ChannelLock {
+        ChannelLock {
+            github_repo: self.config.github_repo.clone(),
+            channel,
+        }
+    }
+
+    /// Read the repo and channel this installation is locked to, if it has been installed
+    pub fn channel_lock(&self) -> Result<Option<ChannelLock>, InstallError> {
+        Ok(channel_lock::read(&self.config.get_channel_lock_path())?)
+    }
+
+    /// Deliberately re-point the installation at the configured repo and the given channel
+    ///
+    /// Subsequent installs and updates must then match the new values.
+    pub fn override_lock(&self, channel: ReleaseChannel) -> Result<(), InstallError> {
+        Ok(channel_lock::write(&self.config.get_channel_lock_path(), &self.requested_channel_lock(channel))?)
+    }
+
+    /// Remove every asset from the configured download cache (no-op if no cache_dir is set)
+    pub fn clear_download_cache(&self) -> Result<(), InstallError> {
+        match &self.config.cache_dir {
+            Some(dir) => Ok(cache::clear(dir)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if the application is currently installed
+    ///
+    /// This reflects what this manager last saw; a manager created in a fresh process knows
+    /// nothing until `detect_installation` (or an operation that calls it) has run.
+    pub fn is_installed(&self) -> bool {
+        self.is_installed
+    }
+
+    /// Read the installed version from the platform (registry on Windows, version file on Linux
+    /// and macOS) and update `is_installed` and `current_version` to match
+    ///
+    /// Returns whether an installation was found. `update` and `uninstall` call this
+    /// themselves, so a standalone updater or uninstaller works without a prior install or
+    /// update check in the same process.
+    pub fn detect_installation(&mut self) -> Result<bool, InstallError> {
+        self.current_version = self.installed_version()?;
+        self.is_installed = self.current_version.is_some();
+        Ok(self.is_installed)
+    }
+
+    /// The version recorded by the platform for this service, if it is installed
+    fn installed_version(&self) -> Result<Option<Version>> {
+        #[cfg(target_os = "windows")]
+        return win::get_installed_version(&self.config);
+
+        #[cfg(target_os = "linux")]
+        return nix::get_installed_version(&self.config);
+
+        #[cfg(target_os = "macos")]
+        return mac::get_installed_version(&self.config);
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        Ok(None)
+    }
+
+    /// Make install and update resolve the release and print their plan without downloading,
+    /// writing files or touching the service
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Check whether dry-run mode is enabled
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Check whether an install/uninstall scheduled a delayed action that needs a reboot to finish
+    /// (Windows: locked files replaced or deleted on reboot, or a service marked for deletion)
+    pub fn reboot_required(&self) -> bool {
+        self.reboot_pending.load(Ordering::Relaxed)
+    }
+
+    /// Get the current installed version
+    pub fn current_version(&self) -> Option<&Version> {
+        self.current_version.as_ref()
+    }
+
+    /// Get the latest available version
+    pub fn latest_version(&self) -> Option<&Version> {
+        self.latest_version.as_ref()
+    }
+
+    /// Get the install path from registry (Windows) or config file (Linux and macOS)
+    pub fn get_install_path(&self) -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            win::get_install_path(&self.config).ok().flatten()
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            // For Linux and macOS, return the configured install path if installed
+            if self.is_installed {
+                Some(self.config.install_path.clone())
+            } else {
+                None
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        {
+            None
+        }
+    }
+
+    /// Find the main executable in the installation directory
+    fn find_installed_executable(&self) -> Result<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            win::find_executable(&self.config)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            nix::find_executable(&self.config)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac::find_executable(&self.config)
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        {
+            anyhow::bail!("Locating the installed executable is not supported on this platform")
+        }
+    }
+
+    /// Start the installed executable as a regular process, outside the service manager
+    ///
+    /// Runs the primary binary when set, otherwise the first executable found in install_path or its
+    /// `bin/` (files with an execute bit on Unix, `.exe` files on Windows), in the working
+    /// directory with `service_env` added. Wait on the returned handle to monitor the process, or
+    /// drop it to leave the process running.
+    pub fn launch(&self) -> Result<std::process::Child, InstallError> {
+        let exe_path = self.find_installed_executable()?;
+
+        self.observer.message(format_args!("Launching {}...", exe_path.display()));
+        let child = std::process::Command::new(&exe_path)
+            .current_dir(self.config.get_working_directory())
+            .envs(&self.config.service_env)
+            .spawn()
+            .with_context(|| format!("Failed to launch '{}'", exe_path.display()))?;
+
+        Ok(child)
+    }
+
+    /// Record the resolved release and asset in the install log
+    fn record_resolved(&self, release: &GitHubRelease, asset: &GitHubAsset) {
+        self.install_log.record(LogEvent::ReleaseResolved { tag_name: release.tag_name.clone() });
+        self.install_log.record(LogEvent::AssetSelected { name: asset.name.clone(), size: asset.size });
+    }
+
+    /// Run the configured self-test command against the installed binary, if any
+    fn run_post_install_verify(&self, version: &str) -> Result<()> {
+        let Some(command) = &self.config.post_install_verify_command else {
+            return Ok(());
+        };
+
+        let exe_path = self.find_installed_executable()
+            .context("Failed to locate the installed binary for post-install verification")?;
+
+        self.observer.message(format_args!("Verifying installation with '{} {}'...", exe_path.display(), command));
+        run_verify_command(
+            &exe_path,
+            command,
+            self.config.get_working_directory(),
+            &self.config.hook_environment(version),
+        )
+    }
+
+    /// Run the configured pre- or post-install hook, if any, forwarding its output to the observer
+    /// and the install log
+    ///
+    /// Hooks get the same environment as the verify command and run in the working directory
+    /// once it exists. A non-zero exit or timeout fails with `CommandError`.
+    fn run_hook(&self, name: &str, hook: Option<&[String]>, version: &str) -> Result<()> {
+        let Some((program, args)) = hook.and_then(|hook| hook.split_first()) else {
+            return Ok(());
+        };
+
+        let command_line = hook.unwrap_or_default().join(" ");
+        self.observer.message(format_args!("Running {} hook '{}'...", name, command_line));
+
+        let mut command = std::process::Command::new(program);
+        command.args(args).envs(self.config.hook_environment(version));
+        if self.config.get_working_directory().is_dir() {
+            command.current_dir(self.config.get_working_directory());
+        }
+        let result = command::run_command_with(&mut command, command::COMMAND_TIMEOUT, 0);
+
+        let (stdout, stderr) = match &result {
+            Ok(output) => (output.stdout.as_str(), output.stderr.as_str()),
+            Err(e) => e
+                .downcast_ref::<CommandError>()
+                .map(|e| (e.stdout.as_str(), e.stderr.as_str()))
+                .unwrap_or_default(),
+        };
+        for line in stdout.lines().chain(stderr.lines()).filter(|line| !line.trim().is_empty()) {
+            self.observer.message(format_args!("[{}] {}", name, line));
+        }
+        self.install_log.record(LogEvent::HookRan {
+            hook: name.to_string(),
+            command: command_line,
+            success: result.is_ok(),
+            output: format!("{}{}", stdout, stderr),
+        });
+
+        result.map(|_| ()).context(format!("The {} hook failed", name))
+    }
+
+    /// Fetch releases from GitHub
+    ///
+    /// With `release_cache_ttl` set, a list fetched less than that long ago is served from disk
+    /// unless `force_refresh` is true. A stale list is revalidated with its ETag, so an unchanged
+    /// list costs a 304 response instead of the full download.
+    #[tracing::instrument(name = "fetch", skip_all, fields(repo = %self.config.github_repo))]
+    pub async fn fetch_releases(&self, force_refresh: bool) -> Result<Vec<GitHubRelease>, InstallError> {
+        let url = self.api_url(&format!("repos/{}/releases", self.config.github_repo))?;
+        let not_found_message = format!(
+            "Repository '{}' not found. Please verify the repository name is correct.",
+            self.config.github_repo
+        );
+
+        let Some(ttl) = self.config.release_cache_ttl else {
+            return Ok(self.get_github_json(&url, not_found_message).await?);
+        };
+
+        let cache_path = self.config.get_release_cache_path();
+        let cached = release_cache::read(&cache_path, &self.config.github_repo);
+        if let Some(cached) = &cached && !force_refresh && cached.is_fresh(ttl) {
+            return Ok(cached.releases.clone());
+        }
+
+        let etag = cached.as_ref().and_then(|cached| cached.etag.clone());
+        let (releases, etag) = match self.get_github_json_conditional(&url, not_found_message, etag.as_deref()).await? {
+            ApiResponse::Fresh { body, etag } => (body, etag),
+            ApiResponse::NotModified => match cached {
+                Some(cached) => (cached.releases, cached.etag),
+                None => return Err(anyhow::anyhow!("GitHub answered 304 Not Modified without a cached releases list").into()),
+            },
+        };
+
+        let updated = release_cache::CachedReleases {
+            github_repo: self.config.github_repo.clone(),
+            fetched_at: chrono::Utc::now(),
+            etag,
+            releases,
+        };
+        // The cache only saves requests, so e.g. a read-only version file directory isn't fatal
+        if let Err(e) = release_cache::write(&cache_path, &updated) {
+            self.observer.warning(format_args!("{:#}", e));
+        }
+        Ok(updated.releases)
+    }
+
+    /// Fetch the release the repository marks as "latest" via GitHub's `/releases/latest` endpoint
+    ///
+    /// GitHub never marks drafts or prereleases as latest, and maintainers may pin an older
+    /// release as latest, so this can differ from the newest release by date.
+    pub async fn fetch_latest_release(&self) -> Result<GitHubRelease, InstallError> {
+        let url = self.api_url(&format!("repos/{}/releases/latest", self.config.github_repo))?;
+
+        self.get_github_json(
+            &url,
+            format!(
+                "Repository '{}' not found or has no release marked as latest.",
+                self.config.github_repo
+            ),
+        )
+        .await
+        .map_err(InstallError::from)
+    }
+
+    /// GET a GitHub API URL and parse the JSON body, mapping error statuses to readable messages
+    ///
+    /// Timeouts, dropped connections and 5xx responses are retried up to `retry_count` times.
+    async fn get_github_json<T: serde::de::DeserializeOwned>(&self, url: &str, not_found_message: String) -> Result<T> {
+        match self.get_github_json_conditional(url, not_found_message, None).await? {
+            ApiResponse::Fresh { body, .. } => Ok(body),
+            ApiResponse::NotModified => anyhow::bail!("GitHub answered 304 Not Modified to an unconditional request"),
+        }
+    }
+
+    /// GET a GitHub API URL as `get_github_json` does, sending `etag` as `If-None-Match`
+    async fn get_github_json_conditional<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        not_found_message: String,
+        etag: Option<&str>,
+    ) -> Result<ApiResponse<T>> {
+        retry::with_backoff(self.config.retry_count, &CancellationToken::new(), &self.observer, || {
+            self.get_github_json_once(url, &not_found_message, etag)
+        })
+        .await
+    }
+
+    async fn get_github_json_once<T: serde::de::DeserializeOwned>(&self, url: &str, not_found_message: &str, etag: Option<&str>) -> Result<ApiResponse<T>> {
+        let client = self.http_client().context("Failed to create HTTP client")?;
+
+        let mut request = client.get(url).timeout(self.config.request_timeout);
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| self.connect_error(e, url, format!(
+                "Failed to connect to GitHub API. Please check your internet connection and try again. URL: {}",
+                url
+            )))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED && etag.is_some() {
+            return Ok(ApiResponse::NotModified);
+        }
+        if !status.is_success() {
+            if matches!(status.as_u16(), 403 | 429)
+                && let Some(limited) = error::RateLimitExceeded::from_headers(response.headers(), self.config.get_github_token().is_some())
+            {
+                return Err(limited.into());
+            }
+
+            let error_body = response.text().await.unwrap_or_default();
+
+            let error_message = match status.as_u16() {
+                404 => return Err(error::AssetNotFound(not_found_message.to_string()).into()),
+                403 => format!(
+                    "GitHub API access denied. Please try again later. Details: {}",
+                    error_body
+                ),
+                401 => "GitHub API authentication failed. The repository may be private, or the GitHub token is invalid or expired.".to_string(),
+                _ => format!(
+                    "GitHub API error (status {}): {}",
+                    status,
+                    if error_body.is_empty() { "No additional details" } else { &error_body }
+                ),
+            };
+
+            if retry::is_transient_status(status) {
+                return Err(retry::TransientStatus(error_message).into());
+            }
+            anyhow::bail!(error_message);
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response
+            .json()
+            .await
+            .context("Failed to parse GitHub API response. The API response format may have changed.")?;
+        Ok(ApiResponse::Fresh { body, etag })
+    }
+
+    /// Get the latest version for each channel without fetching releases multiple times
+    pub async fn get_channel_versions(&mut self) -> Result<(Option<Version>, Option<Version>, Option<Version>), InstallError> {
+        let releases = self.fetch_releases(false).await?;
+
+        self.observer.message(format_args!("Found {} releases from GitHub", releases.len()));
+
+        if releases.is_empty() {
+            return Ok((None, None, None));
+        }
+
+        let mut release_version: Option<Version> = None;
+        let mut beta_version: Option<Version> = None;
+        let mut alpha_version: Option<Version> = None;
+
+        // Parse all releases and categorize them
+        for release in &releases {
+            let version_str = release.tag_name.trim_start_matches('v');
+            self.observer.message(format_args!("Parsing release: {} (prerelease: {})", release.tag_name, release.prerelease));
+
+            match Version::parse(version_str) {
+                Ok(version) => {
+                    self.observer.message(format_args!("  Parsed as semver: {} (pre: {:?})", version, version.pre));
+
+                    // If GitHub marks this as a prerelease, it should NOT match Release channel
+                    // Check for Release channel (stable only - no pre-release in semver AND not marked as prerelease by GitHub)
+                    if release_version.is_none() && !release.prerelease && ReleaseChannel::Release.matches_version(&version) {
+                        self.observer.message(format_args!("  -> Matches Release channel"));
+                        release_version = Some(version.clone());
+                    }
+
+                    // Check for Beta channel (beta/rc releases OR stable releases)
+                    // If GitHub marks it as prerelease, check if it's beta/rc, otherwise only stable
+                    if beta_version.is_none() {
+                        let matches = if release.prerelease {
+                            // For GitHub prereleases, only match if it's actually beta/rc in semver
+                            let pre_str = version.pre.to_string().to_lowercase();
+                            pre_str.contains("beta") || pre_str.contains("rc")
+                        } else {
+                            // Stable releases always match beta channel
+                            ReleaseChannel::Beta.matches_version(&version)
+                        };
+
+                        if matches {
+                            self.observer.message(format_args!("  -> Matches Beta channel"));
+                            beta_version = Some(version.clone());
+                        }
+                    }
+
+                    // Check for Alpha channel (all versions)
+                    if alpha_version.is_none() && ReleaseChannel::Alpha.matches_version(&version) {
+                        self.observer.message(format_args!("  -> Matches Alpha channel"));
+                        alpha_version = Some(version.clone());
+                    }
+
+                    // Early exit if we found all three
+                    if release_version.is_some() && beta_version.is_some() && alpha_version.is_some() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.observer.message(format_args!("  Failed to parse as semver: {}", e));
+                }
+            }
+        }
+
+        self.observer.message(format_args!("Final versions - Release: {:?}, Beta: {:?}, Alpha: {:?}",
+                 release_version, beta_version, alpha_version));
+
+        Ok((release_version, beta_version, alpha_version))
+    }
+
+    /// Get the latest release for the specified channel
+    pub async fn get_latest_release(&mut self, channel: ReleaseChannel) -> Result<GitHubRelease, InstallError> {
+        // Honor the maintainer's explicit "latest" designation for stable installs
+        if channel == ReleaseChannel::Release && self.config.use_latest_endpoint {
+            let release = self.fetch_latest_release().await?;
+            let version = channel_version(&release, channel).ok_or_else(|| anyhow::anyhow!(
+                "The release marked as latest ('{}') is not a stable semantic version",
+                release.tag_name
+            ))?;
+
+            // A freshly published latest falls back to the newest release that's old enough
+            if old_enough(&release, self.config.min_release_age, chrono::Utc::now()) {
+                self.latest_version = Some(version);
+                return Ok(release);
+            }
+        }
+
+        self.latest_release_matching(channel.display_name(), |release| channel_version(release, channel)).await
+    }
+
+    /// Get the latest release whose tag belongs to the specified channel
+    ///
+    /// Unlike `get_latest_release`, the beta and nightly channels only follow tags with their own
+    /// prerelease suffix (`-beta`, `-rc`, `-nightly`), so subscribing to nightly builds doesn't also
+    /// pull in betas. The `min_release_age` setting applies as it does there.
+    pub async fn get_latest_release_for_channel(&mut self, channel: &Channel) -> Result<GitHubRelease, InstallError> {
+        self.latest_release_matching(&channel.to_string(), |release| channel.matches(release)).await
+    }
+
+    /// Get the newest release old enough to install for which `version_of` returns a version
+    async fn latest_release_matching(
+        &mut self,
+        channel_name: &str,
+        version_of: impl Fn(&GitHubRelease) -> Option<Version>,
+    ) -> Result<GitHubRelease, InstallError> {
+        let releases = self.fetch_releases(false).await?;
+
+        if releases.is_empty() {
+            return Err(error::AssetNotFound(format!(
+                "No releases found for repository '{}'. Please ensure the repository has published releases.",
+                self.config.github_repo
+            )).into());
+        }
+
+        let total_releases = releases.len();
+        let now = chrono::Utc::now();
+
+        // Find the first release that matches the channel and has been out long enough
+        let matching_release = releases
+            .into_iter()
+            .filter(|release| old_enough(release, self.config.min_release_age, now))
+            .find_map(|release| version_of(&release).map(|version| (release, version)));
+
+        match matching_release {
+            Some((release, version)) => {
+                self.latest_version = Some(version);
+                Ok(release)
+            }
+            None => {
+                if let Some(age) = self.config.min_release_age {
+                    return Err(error::AssetNotFound(format!(
+                        "No releases in the '{}' channel for repository '{}' were published more than {} hours ago. Total releases available: {}.",
+                        channel_name,
+                        self.config.github_repo,
+                        age.as_secs() / 3600,
+                        total_releases
+                    )).into());
+                }
+                Err(error::AssetNotFound(format!(
+                    "No releases found in the '{}' channel for repository '{}'. Total releases available: {}. Try selecting a different channel.",
+                    channel_name,
+                    self.config.github_repo,
+                    total_releases
+                )).into())
+            }
+        }
+    }
+
+    /// List every published release whose tag is a semantic version, newest version first
+    ///
+    /// Drafts and tags that aren't versions (e.g. "nightly") are left out.
+    pub async fn list_versions(&self) -> Result<Vec<ReleaseInfo>, InstallError> {
+        Ok(release_infos(self.fetch_releases(false).await?))
+    }
+
+    /// Get the release whose tag is the given version, with or without a `v` prefix
+    ///
+    /// Fails with `AssetNotFound`, listing the available versions, when no release matches.
+    pub async fn get_release_by_tag(&self, tag: &str) -> Result<GitHubRelease, InstallError> {
+        let version = Version::parse(tag.trim_start_matches('v'))?;
+        let releases = self.fetch_releases(false).await?;
+        Ok(find_release_by_version(releases, &version, &self.config.github_repo)?)
+    }
+
+    /// Get the release notes of the latest release, for showing "what's new"
+    ///
+    /// Only stable releases are considered unless `include_prerelease` is set. The Markdown body
+    /// is returned as written, and is empty if the release has no notes.
+    pub async fn latest_changelog(&mut self, include_prerelease: bool) -> Result<String, InstallError> {
+        let channel = if include_prerelease { ReleaseChannel::Alpha } else { ReleaseChannel::Release };
+        Ok(self.get_latest_release(channel).await?.body)
+    }
+
+    /// Resolve the release and asset to install on the specified channel
+    ///
+    /// When `require_latest` is disabled and the newest release has no asset for this platform yet,
+    /// this walks back to the most recent release in the channel that does.
+    pub async fn resolve_release(&mut self, channel: ReleaseChannel) -> Result<(GitHubRelease, GitHubAsset), InstallError> {
+        if self.config.require_latest {
+            let release = self.get_latest_release(channel).await?;
+            let asset = self.select_asset(&release)?;
+            return Ok((release, asset));
+        }
+
+        let releases = self.fetch_releases(false).await?;
+        let (release, asset, version) = self.newest_release_with_asset(releases, channel)?;
+        self.latest_version = Some(version);
+        Ok((release, asset))
+    }
+
+    /// Pick the newest release in the channel that has an asset for this platform
+    fn newest_release_with_asset(
+        &self,
+        releases: Vec<GitHubRelease>,
+        channel: ReleaseChannel,
+    ) -> Result<(GitHubRelease, GitHubAsset, Version)> {
+        let mut newest_tag: Option<String> = None;
+        let mut first_error = None;
+        let now = chrono::Utc::now();
+
+        for release in releases {
+            let Some(version) = channel_version(&release, channel) else {
+                continue;
+            };
+            if !old_enough(&release, self.config.min_release_age, now) {
+                continue;
+            }
+            newest_tag.get_or_insert_with(|| release.tag_name.clone());
+
+            match self.select_asset(&release) {
+                Ok(asset) => {
+                    if newest_tag.as_deref() != Some(release.tag_name.as_str()) {
+                        self.observer.message(format_args!(
+                            "Latest release {} has no asset for this platform yet, using {} instead",
+                            newest_tag.as_deref().unwrap_or_default(),
+                            release.tag_name
+                        ));
+                    }
+                    return Ok((release, asset, version));
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(anyhow::Error::from(e).context(format!(
+                "No release in the '{}' channel has an asset for this platform",
+                channel.display_name()
+            ))),
+            None => Err(error::AssetNotFound(format!(
+                "No releases found in the '{}' channel for repository '{}'. Try selecting a different channel.",
+                channel.display_name(),
+                self.config.github_repo
+            ))
+            .into()),
+        }
+    }
+
+    /// Check for updates on the specified channel, safe to call from a polling timer
+    ///
+    /// Returns `Busy` without checking while another operation runs on this manager or a clone,
+    /// and `CheckedRecently` with the previous result if a check ran within `update_check_interval`.
+    pub async fn check_for_updates(&mut self, channel: ReleaseChannel) -> Result<UpdateStatus, InstallError> {
+        if self.busy.load(Ordering::Acquire) {
+            return Ok(UpdateStatus::Busy);
+        }
+
+        if let Some(update_available) = self.recent_update_check() {
+            return Ok(UpdateStatus::CheckedRecently { update_available });
+        }
+
+        Ok(if self.fetch_update_available(channel).await? {
+            UpdateStatus::UpdateAvailable
+        } else {
+            UpdateStatus::UpToDate
+        })
+    }
+
+    /// Fetch the latest release on the channel and compare it with the installed version
+    pub(crate) async fn fetch_update_available(&mut self, channel: ReleaseChannel) -> Result<bool> {
+        self.get_latest_release(channel).await?;
+        self.update_available()
+    }
+
+    /// Resolve the release and asset an update would install, or None if the installed version is current
+    ///
+    /// The release is fetched once and the same one is compared and installed, so a release
+    /// published in between can't slip in.
+    async fn resolve_update(&mut self, channel: ReleaseChannel) -> Result<Option<(GitHubRelease, GitHubAsset)>> {
+        if self.config.require_latest {
+            // Compare first so an up-to-date install doesn't fail on a missing asset
+            let release = self.get_latest_release(channel).await?;
+            if !self.update_available()? {
+                return Ok(None);
+            }
+            let asset = self.select_asset(&release)?;
+            return Ok(Some((release, asset)));
+        }
+
+        let (release, asset) = self.resolve_release(channel).await?;
+        Ok(self.update_available()?.then_some((release, asset)))
+    }
+
+    /// Compare the installed version with `latest_version`, recording the result for debouncing
+    fn update_available(&mut self) -> Result<bool> {
+        self.detect_installation()?;
+
+        let update_available = match &self.current_version {
+            Some(current) => self.latest_version.as_ref().is_some_and(|latest| latest > current),
+            None => true, // No version installed, update available
+        };
+
+        *self.last_update_check.lock().unwrap() = Some((Instant::now(), update_available));
+        Ok(update_available)
+    }
+
+    /// Result of the last update check if it ran within `update_check_interval`
+    fn recent_update_check(&self) -> Option<bool> {
+        let interval = self.config.update_check_interval?;
+        self.last_update_check
+            .lock()
+            .unwrap()
+            .filter(|(checked_at, _)| checked_at.elapsed() < interval)
+            .map(|(_, update_available)| update_available)
+    }
+
+    /// Check that the running service was started from the recorded version
+    ///
+    /// Runs `--version` against the running process's executable image (on Linux the loaded
+    /// binary, even if the file on disk has since been replaced). Returns false when they differ,
+    /// meaning the files were updated but the service needs a restart. A stopped service has
+    /// nothing stale loaded and counts as matching.
+    pub fn running_version_matches(&self) -> Result<bool, InstallError> {
+        #[cfg(target_os = "windows")]
+        let (recorded, running_exe) = (
+            win::get_installed_version(&self.config)?,
+            win::running_executable(&self.config)?,
+        );
+
+        #[cfg(target_os = "linux")]
+        let (recorded, running_exe) = (
+            nix::get_installed_version(&self.config)?,
+            nix::running_executable(&self.config)?,
+        );
+
+        #[cfg(target_os = "macos")]
+        let (recorded, running_exe) = (
+            mac::get_installed_version(&self.config)?,
+            mac::running_executable(&self.config)?,
+        );
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        let (recorded, running_exe): (Option<Version>, Option<PathBuf>) = (None, None);
+
+        let Some(recorded) = recorded else {
+            return Err(anyhow::anyhow!("'{}' is not installed", self.config.service_name).into());
+        };
+        let Some(running_exe) = running_exe else {
+            return Ok(true);
+        };
+
+        let output = command::run_command_with(
+            std::process::Command::new(&running_exe)
+                .arg("--version")
+                .current_dir(self.config.get_working_directory())
+                .envs(self.config.hook_environment(&recorded.to_string())),
+            command::COMMAND_TIMEOUT,
+            0,
+        )?;
+
+        let running = parse_reported_version(&output.stdout)
+            .or_else(|| parse_reported_version(&output.stderr))
+            .ok_or_else(|| anyhow::anyhow!(
+                "'{} --version' did not report a semantic version: {}",
+                running_exe.display(),
+                output.stdout
+            ))?;
+
+        if running != recorded {
+            self.observer.warning(format_args!(
+                "Service '{}' is running {} but {} is installed; restart the service to load it",
+                self.config.service_name, running, recorded
+            ));
+        }
+
+        Ok(running == recorded)
+    }
+
+    /// Archive formats this build can extract; `select_asset` skips assets in any other format
+    pub fn supported_formats(&self) -> Vec<ArchiveFormat> {
+        ArchiveFormat::ALL.to_vec()
+    }
+
+    /// Select assets for `arch` instead of the platform this runs on, e.g. to prepare an
+    /// install for another machine
+    pub fn set_target_architecture(&mut self, arch: Architecture) {
+        self.target_architecture = Some(arch);
+    }
+
+    /// The platform assets are selected for: the override if set, else the detected one
+    fn target_architecture(&self) -> Result<Architecture, InstallError> {
+        match &self.target_architecture {
+            Some(arch) => Ok(arch.clone()),
+            None => Architecture::detect(),
+        }
+    }
+
+    /// Select the appropriate asset for the current architecture (or the target architecture, if set)
+    pub fn select_asset(&self, release: &GitHubRelease) -> Result<GitHubAsset, InstallError> {
+        self.select_asset_for(release, &self.target_architecture()?)
+    }
+
+    /// Select the asset `select_asset` would pick on `arch`
+    pub fn select_asset_for(&self, release: &GitHubRelease, arch: &Architecture) -> Result<GitHubAsset, InstallError> {
+        if release.assets.is_empty() {
+            return Err(error::AssetNotFound(format!(
+                "Release '{}' has no downloadable assets. The release may not be properly configured.",
+                release.tag_name
+            )).into());
+        }
+
+        // A package for the host's package manager is preferred over any archive
+        #[cfg(target_os = "linux")]
+        if let Some(asset) = self.select_package_asset(release, arch) {
+            return Ok(asset);
+        }
+
+        let supported = self.supported_formats();
+        let is_supported = |asset: &GitHubAsset| {
+            asset.format().is_some_and(|f| supported.contains(&f))
+        };
+
+        // An explicit template bypasses the heuristics entirely
+        if let Some(expected) = self.config.render_asset_template(release, arch) {
+            let asset = release.assets.iter()
+                .find(|a| a.name == expected)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!(
+                    "Asset '{}' (from template '{}') not found in release '{}'. Available assets: {}",
+                    expected,
+                    self.config.asset_template.as_deref().unwrap_or_default(),
+                    release.tag_name,
+                    release.assets.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", ")
+                ))?;
+
+            if !asset.is_uploaded() {
+                return Err(error::AssetNotFound(format!(
+                    "Asset '{}' in release '{}' is still uploading. Try again shortly.",
+                    asset.name,
+                    release.tag_name
+                )).into());
+            }
+
+            if !is_supported(&asset) {
+                return Err(error::UnsupportedArchive(format!(
+                    "Asset '{}' is not in a supported archive format ({})",
+                    asset.name,
+                    archive::describe_extensions(&supported)
+                )).into());
+            }
+
+            return ensure_native(asset, arch);
+        }
+
+        match &self.config.asset_filter {
+            Some(filter) => self.select_matching_asset(release, arch, |asset| filter.is_match(&asset.name)),
+            None => self.select_matching_asset(release, arch, |_| true),
+        }
+    }
+
+    /// Select the asset for this platform among those accepted by `predicate`
+    ///
+    /// Assets that are still uploading or in an unsupported format are skipped as in
+    /// `select_asset`, and the platform heuristic picks among the remaining matches.
+    pub fn select_asset_with<F: Fn(&GitHubAsset) -> bool>(&self, release: &GitHubRelease, predicate: F) -> Result<GitHubAsset, InstallError> {
+        self.select_matching_asset(release, &self.target_architecture()?, predicate)
+    }
+
+    fn select_matching_asset<F: Fn(&GitHubAsset) -> bool>(
+        &self,
+        release: &GitHubRelease,
+        arch: &Architecture,
+        predicate: F,
+    ) -> Result<GitHubAsset, InstallError> {
+        let patterns = arch.asset_patterns();
+
+        if release.assets.is_empty() {
+            return Err(error::AssetNotFound(format!(
+                "Release '{}' has no downloadable assets. The release may not be properly configured.",
+                release.tag_name
+            )).into());
+        }
+
+        let supported = self.supported_formats();
+        let is_supported = |asset: &GitHubAsset| {
+            asset.format().is_some_and(|f| supported.contains(&f))
+        };
+
+        // Only consider fully uploaded assets this build can extract
+        let candidates: Vec<&GitHubAsset> = release.assets.iter()
+            .filter(|a| a.is_uploaded() && is_supported(a) && predicate(a))
+            .collect();
+
+        // Prefer an asset naming both this OS and CPU, then the OS alone, then the CPU alone;
+        // the first of equally good matches wins
+        let best = candidates.iter().copied()
+            .filter(|a| arch.match_score(&a.name) > 0)
+            .min_by_key(|a| std::cmp::Reverse(arch.match_score(&a.name)));
+        if let Some(asset) = best {
+            return ensure_native(asset.clone(), arch);
+        }
+
+        let available_assets: Vec<String> = release.assets.iter()
+            .map(|a| a.name.clone())
+            .collect();
+        let skipped_assets: Vec<&str> = release.assets.iter()
+            .filter(|a| !is_supported(a))
+            .map(|a| a.name.as_str())
+            .collect();
+        let uploading_assets: Vec<&str> = release.assets.iter()
+            .filter(|a| !a.is_uploaded())
+            .map(|a| a.name.as_str())
+            .collect();
+        let filtered_assets: Vec<&str> = release.assets.iter()
+            .filter(|a| !predicate(a))
+            .map(|a| a.name.as_str())
+            .collect();
+
+        Err(error::AssetNotFound(format!(
+            "No compatible asset found for your platform ({:?}). Expected patterns: {:?}. Available assets: {}. Skipped (unsupported format, expected {}): {}. Still uploading: {}. Excluded by the asset filter: {}",
+            arch,
+            patterns,
+            available_assets.join(", "),
+            archive::describe_extensions(&supported),
+            if skipped_assets.is_empty() { "none".to_string() } else { skipped_assets.join(", ") },
+            if uploading_assets.is_empty() { "none".to_string() } else { uploading_assets.join(", ") },
+            if filtered_assets.is_empty() { "none".to_string() } else { filtered_assets.join(", ") }
+        )).into())
+    }
+
+    /// Fetch a release asset, reusing a verified copy from the download cache when possible
+    ///
+    /// The download is checked against the asset's digest whenever GitHub reports one, whether
+    /// or not the cache is enabled.
+    #[tracing::instrument(name = "download", skip_all, fields(repo = %self.config.github_repo, asset = %asset.name, bytes = asset.size))]
+    async fn fetch_asset(&self, asset: &GitHubAsset, dest_path: &PathBuf, cancel: &CancellationToken) -> Result<()> {
+        let Some(cache_dir) = &self.config.cache_dir else {
+            self.download_asset(asset, dest_path, cancel).await?;
+            if let Some(expected) = asset.checksum() {
+                self.verify_checksum(dest_path, &asset.name, &expected)?;
+            }
+            return Ok(());
+        };
+        let cache = cache::DownloadCache::new(cache_dir, self.config.cache_max_bytes);
+
+        if cache.restore(asset, dest_path)? {
+            self.observer.message(format_args!("Using cached {}", asset.name));
+            self.broadcast_progress(State::Downloading, 1.0);
+            return Ok(());
+        }
+
+        self.download_asset(asset, dest_path, cancel).await?;
+
+        if let Some(expected) = asset.checksum() {
+            self.verify_checksum(dest_path, &asset.name, &expected)?;
+
+            // A cache that can't be written shouldn't fail the install
+            if let Err(e) = cache.store(asset, dest_path) {
+                self.observer.warning(format_args!("Failed to store {} in download cache: {:#}", asset.name, e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Download a release asset and check it against `expected`, or the GitHub-reported digest
+    /// when no checksum is given
+    ///
+    /// On a mismatch the downloaded file is deleted and `ChecksumMismatch` is returned.
+    pub async fn download_asset_verified(
+        &self,
+        asset: &GitHubAsset,
+        dest_path: &PathBuf,
+        expected: Option<Checksum>,
+    ) -> Result<()> {
+        self.download_asset(asset, dest_path, &CancellationToken::new()).await?;
+
+        match expected.or_else(|| asset.checksum()) {
+            Some(expected) => self.verify_checksum(dest_path, &asset.name, &expected),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a downloaded file against `expected`, reporting the Verifying state while it is hashed
+    fn verify_checksum(&self, path: &std::path::Path, asset_name: &str, expected: &Checksum) -> Result<()> {
+        self.broadcast_progress(State::Verifying, 0.0);
+        checksum::verify_file(path, asset_name, expected)?;
+        self.broadcast_progress(State::Verifying, 1.0);
+        Ok(())
+    }
+
+    /// Check a downloaded asset against its signature asset in `release`, if a signing key is configured
+    ///
+    /// Fails with `SignatureInvalid` when the release has no signature for the asset or it
+    /// doesn't verify.
+    async fn verify_signature(&self, release: &GitHubRelease, asset: &GitHubAsset, path: &std::path::Path) -> Result<()> {
+        let Some(config) = &self.config.signature else {
+            return Ok(());
+        };
+
+        let signature_name = config.signature_asset_name(&asset.name);
+        let Some(signature_asset) = release.assets.iter().find(|a| a.name == signature_name) else {
+            return Err(SignatureInvalid {
+                asset: asset.name.clone(),
+                reason: format!("release {} has no signature asset '{}'", release.tag_name, signature_name),
+            }
+            .into());
+        };
+
+        self.broadcast_progress(State::Verifying, 0.0);
+        let signature = self.request_asset(signature_asset).await?
+            .bytes()
+            .await
+            .context(format!("Failed to download signature '{}'", signature_name))?;
+        signature::verify_file(config, path, &asset.name, &signature)?;
+        self.broadcast_progress(State::Verifying, 1.0);
+        Ok(())
+    }
+
+    /// Look up the published checksum of `asset_name` in a release's `<asset>.sha256`/`.sha512`
+    /// sidecar or `checksums.txt`-style file
+    ///
+    /// Returns None if the release has no checksum file covering the asset.
+    pub async fn fetch_checksum_for(&self, release: &GitHubRelease, asset_name: &str) -> Result<Option<Checksum>, InstallError> {
+        let mut candidates: Vec<&GitHubAsset> = release.assets.iter()
+            .filter(|a| checksum::is_checksum_file_for(&a.name, asset_name))
+            .collect();
+
+        // Per-asset sidecars are more specific than combined checksum lists
+        candidates.sort_by_key(|a| !a.name.to_lowercase().starts_with(&asset_name.to_lowercase()));
+
+        for candidate in candidates {
+            let content = self.request_asset(candidate).await?
+                .text()
+                .await
+                .context(format!("Failed to read checksum file '{}'", candidate.name))?;
+
+            if let Some(checksum) = checksum::parse_checksum_file(&content, asset_name) {
+                return Ok(Some(checksum));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Send the download request for an asset, failing on a non-success status
+    async fn request_asset(&self, asset: &GitHubAsset) -> Result<reqwest::Response> {
+        self.request_asset_from(asset, 0).await
+    }
+
+    /// URL of the GitHub API endpoint `path` (relative, e.g. "repos/owner/repo/releases")
+    fn api_url(&self, path: &str) -> Result<String> {
+        let base = parse_base_url("api_base_url", self.config.get_api_base_url())?;
+        Ok(format!("{}/{}", base.as_str().trim_end_matches('/'), path))
+    }
+
+    /// Where to download `asset` from: its download URL, moved onto `download_base_url` if set
+    fn download_url(&self, asset: &GitHubAsset) -> Result<String> {
+        let Some(base) = &self.config.download_base_url else {
+            return Ok(asset.browser_download_url.clone());
+        };
+        let base = parse_base_url("download_base_url", base)?;
+        let original = reqwest::Url::parse(&asset.browser_download_url)
+            .context(format!("Asset '{}' has an invalid download URL '{}'", asset.name, asset.browser_download_url))?;
+        let mut url = format!("{}{}", base.as_str().trim_end_matches('/'), original.path());
+        if let Some(query) = original.query() {
+            url.push('?');
+            url.push_str(query);
+        }
+        Ok(url)
+    }
+
+    /// HTTP client for GitHub requests, authenticated when a token is configured
+    ///
+    /// reqwest drops the `Authorization` header when a download redirects to another host, so
+    /// the token isn't sent on to GitHub's asset storage.
+    fn http_client(&self) -> Result<reqwest::Client> {
+        self.build_http_client(true)
+    }
+
+    /// HTTP client for GitHub requests, or for a download mirror that mustn't see the token
+    fn build_http_client(&self, authenticated: bool) -> Result<reqwest::Client> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(token) = self.config.get_github_token().filter(|_| authenticated) {
+            let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+                .context("The GitHub token contains characters that aren't allowed in an HTTP header")?;
+            value.set_sensitive(true);
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+
+        let builder = reqwest::Client::builder()
+            .user_agent("obsidian-installation-manager")
+            .default_headers(headers)
+            .connect_timeout(self.config.connect_timeout)
+            .read_timeout(self.config.request_timeout);
+        Ok(proxy::apply(builder, self.config.proxy.as_deref(), false)?.build()?)
+    }
+
+    /// Explain a request to `url` that got no response, blaming the proxy when it was sent through one
+    fn connect_error(&self, error: reqwest::Error, url: &str, message: String) -> anyhow::Error {
+        match proxy::unreachable(&error, self.config.proxy.as_deref(), url) {
+            Some(unreachable) => anyhow::Error::new(error).context(unreachable),
+            None => anyhow::Error::new(error).context(message),
+        }
+    }
+
+    /// Send the download request for an asset starting at byte `offset`
+    ///
+    /// With a non-zero offset the server may answer 206 (partial content), 200 (range ignored,
+    /// full body) or 416 (nothing left to send); other non-success statuses fail.
+    async fn request_asset_from(&self, asset: &GitHubAsset, offset: u64) -> Result<reqwest::Response> {
+        self.request_asset_range(asset, offset, None).await
+    }
+
+    /// Send the download request for bytes `offset..=end` of an asset (to the end without `end`)
+    pub(crate) async fn request_asset_range(&self, asset: &GitHubAsset, offset: u64, end: Option<u64>) -> Result<reqwest::Response> {
+        let url = self.download_url(asset)?;
+        let client = self
+            .build_http_client(self.config.download_base_url.is_none())
+            .context("Failed to create HTTP client for download")?;
+
+        let mut request = client.get(&url);
+        match end {
+            Some(end) => request = request.header(reqwest::header::RANGE, format!("bytes={}-{}", offset, end)),
+            None if offset > 0 => request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset)),
+            None => {}
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| self.connect_error(e, &url, format!(
+                "Failed to connect to download URL. Please check your internet connection. File: {}",
+                asset.name
+            )))?;
+
+        let status = response.status();
+        let range_not_satisfiable = offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE;
+        if !status.is_success() && !range_not_satisfiable {
+            let message = format!(
+                "Download failed for '{}' with status: {}. The file may no longer be available.",
+                asset.name, status
+            );
+            if retry::is_transient_status(status) {
+                return Err(retry::TransientStatus(message).into());
+            }
+            anyhow::bail!(message);
+        }
+
+        Ok(response)
+    }
+
+    /// Download a release asset
+    ///
+    /// If `dest_path` already holds part of the asset from an interrupted download, only the
+    /// remaining bytes are requested and appended. Servers that ignore the range restart the
+    /// download from scratch.
+    ///
+    /// `cancel` is checked between chunks; a cancelled download deletes the partial file and
+    /// fails with `Cancelled`.
+    ///
+    /// A download that times out, loses its connection or gets a 5xx is retried up to
+    /// `retry_count` times, resuming from the bytes already written.
+    ///
+    /// With `parallel_download` set, large assets are fetched over `download_connections` ranged
+    /// requests at once; a failed parallel download is retried from the start.
+    pub async fn download_asset(&self, asset: &GitHubAsset, dest_path: &PathBuf, cancel: &CancellationToken) -> Result<(), InstallError> {
+        Ok(retry::with_backoff(self.config.retry_count, cancel, &self.observer, || self.download_asset_attempt(asset, dest_path, cancel)).await?)
+    }
+
+    async fn download_asset_attempt(&self, asset: &GitHubAsset, dest_path: &PathBuf, cancel: &CancellationToken) -> Result<()> {
+        // A partial single-stream download is quicker to resume than to start over in parallel
+        let resumable = resume_marker_path(dest_path).exists();
+        if self.use_parallel_download(asset) && !resumable && self.download_in_chunks(asset, dest_path, cancel).await? {
+            return Ok(());
+        }
+        self.download_asset_once(asset, dest_path, cancel).await
+    }
+
+    async fn download_asset_once(&self, asset: &GitHubAsset, dest_path: &PathBuf, cancel: &CancellationToken) -> Result<()> {
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        cancel.check()?;
+
+        // Only resume a partial file left by a download of this same asset
+        let marker_path = resume_marker_path(dest_path);
+        let existing_len = match std::fs::read_to_string(&marker_path) {
+            Ok(url) if url == asset.browser_download_url => std::fs::metadata(dest_path).map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        };
+        let response = self.request_asset_from(asset, existing_len).await?;
+
+        let total_size = asset.size;
+        let resuming = existing_len > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+
+        // The server has nothing past what we already have
+        if existing_len > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            self.observer.message(format_args!("{} is already fully downloaded", asset.name));
+            let _ = std::fs::remove_file(&marker_path);
+            self.broadcast_progress(State::Downloading, 1.0);
+            return Ok(());
+        }
+
+        let file = if resuming {
+            self.observer.message(format_args!("Resuming download of {} at byte {}", asset.name, existing_len));
+            std::fs::OpenOptions::new().append(true).open(dest_path)
+        } else {
+            std::fs::File::create(dest_path)
+        }
+        .context(format!(
+            "Failed to create file at '{}'. Check disk space and write permissions.",
+            dest_path.display()
+        ))?;
+        preflight::preallocate(&file, total_size)
+            .context(format!(
+                "Not enough disk space to download '{}' ({} bytes) to '{}'.",
+                asset.name,
+                total_size,
+                dest_path.display()
+            ))?;
+        let mut file = tokio::fs::File::from_std(file);
+        std::fs::write(&marker_path, &asset.browser_download_url)
+            .context(format!("Failed to write to '{}'.", marker_path.display()))?;
+
+        let mut downloaded: u64 = if resuming { existing_len } else { 0 };
+        let mut stream = response.bytes_stream();
+
+        let mut rate = transfer::TransferRate::new();
+        let mut throttle = transfer::Throttle::new(self.config.max_download_bytes_per_sec);
+        self.broadcast_download(downloaded, total_size, &mut rate);
+
+        while let Some(chunk) = stream.next().await {
+            if cancel.is_cancelled() {
+                drop(file);
+                let _ = std::fs::remove_file(dest_path);
+                let _ = std::fs::remove_file(&marker_path);
+                return Err(Cancelled.into());
+            }
+
+            let chunk = chunk.context(format!(
+                "Network error while downloading '{}'. The connection may have been interrupted; run the operation again to resume.",
+                asset.name
+            ))?;
+
+            file.write_all(&chunk)
+                .await
+                .context(format!(
+                    "Failed to write to '{}'. Check available disk space.",
+                    dest_path.display()
+                ))?;
+
+            downloaded += chunk.len() as u64;
+            rate.record(chunk.len() as u64);
+            self.broadcast_download(downloaded, total_size, &mut rate);
+            throttle.pace(chunk.len() as u64, cancel).await;
+        }
+
+        file.flush()
+            .await
+            .context(format!("Failed to write to '{}'.", dest_path.display()))?;
+        let _ = std::fs::remove_file(&marker_path);
+
+        self.broadcast_progress(State::Downloading, 1.0);
+        Ok(())
+    }
+
+    /// Download a tar.gz asset and extract it in a single pass, never writing the archive to disk
+    ///
+    /// Progress is reported as Downloading by bytes received. The SHA-256 digest is checked
+    /// inline, so a mismatch is only detected after the files have been extracted.
+    #[tracing::instrument(name = "download", skip_all, fields(repo = %self.config.github_repo, asset = %asset.name, bytes = asset.size, streaming = true))]
+    async fn stream_extract_tar_gz(&self, asset: &GitHubAsset, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        use futures::StreamExt;
+        use sha2::{Digest, Sha256};
+
+        std::fs::create_dir_all(extract_to)
+            .context(format!(
+                "Failed to create extraction directory '{}'. Check write permissions.",
+                extract_to.display()
+            ))?;
+
+        // Only the request can be retried; a stream that fails part way has already been extracted from
+        let response = retry::with_backoff(self.config.retry_count, cancel, &self.observer, || self.request_asset(asset)).await?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+        let dest = extract_to.to_path_buf();
+        let max_uncompressed_size = self.config.max_uncompressed_size;
+        let strip = self.config.strip_components;
+        let skip_existing = self.skip_existing();
+        let extractor_cancel = cancel.clone();
+        let progress_tx = self.progress_tx.clone();
+        let compressed_size = asset.size;
+        let extractor = tokio::task::spawn_blocking(move || {
+            // The uncompressed size isn't known up front, so measure how much of the download
+            // the decoder has consumed; not logged, as these interleave with download updates
+            let report = |consumed: u64| {
+                if compressed_size > 0 {
+                    let progress = (consumed as f32 / compressed_size as f32).min(1.0);
+                    progress_tx.send(StateProgress::new(State::Extracting, progress));
+                }
+            };
+            streaming::extract_tar_gz_stream(streaming::ChannelReader::new(rx), &dest, max_uncompressed_size, strip, skip_existing, &extractor_cancel, report)
+        });
+
+        let mut rate = transfer::TransferRate::new();
+        self.broadcast_download(0, asset.size, &mut rate);
+
+        let download = async {
+            let mut throttle = transfer::Throttle::new(self.config.max_download_bytes_per_sec);
+            let mut hasher = Sha256::new();
+            let mut downloaded: u64 = 0;
+            let mut stream = response.bytes_stream();
+
+            while let Some(chunk) = stream.next().await {
+                cancel.check()?;
+
+                let chunk = chunk.context(format!(
+                    "Network error while downloading '{}'. The connection may have been interrupted.",
+                    asset.name
+                ))?;
+
+                hasher.update(&chunk);
+                downloaded += chunk.len() as u64;
+                rate.record(chunk.len() as u64);
+                self.broadcast_download(downloaded, asset.size, &mut rate);
+
+                // The extractor only hangs up early on failure, which is reported below
+                if tx.send(chunk.to_vec()).await.is_err() {
+                    break;
+                }
+                throttle.pace(chunk.len() as u64, cancel).await;
+            }
+
+            Ok::<_, anyhow::Error>(Checksum::Sha256(hasher.finalize().into()))
+        }
+        .await;
+
+        // Closing the channel ends the stream for the extractor
+        drop(tx);
+        let extracted = extractor.await.context("Extraction task panicked")?;
+
+        let actual = download?;
+        extracted.context(format!("Failed to extract TAR.GZ archive '{}'", asset.name))?;
+
+        // The digest was computed as the stream went by, so checking it is instant
+        if let Some(expected @ Checksum::Sha256(_)) = asset.checksum() {
+            self.broadcast_progress(State::Verifying, 0.0);
+            if actual != expected {
+                return Err(ChecksumMismatch {
+                    asset: asset.name.clone(),
+                    expected,
+                    actual,
+                }
+                .into());
+            }
+            self.broadcast_progress(State::Verifying, 1.0);
+        }
+
+        self.broadcast_progress(State::Downloading, 1.0);
+        self.broadcast_progress(State::Extracting, 1.0);
+        Ok(())
+    }
+
+    /// Extract downloaded archive
+    pub fn extract_archive(&self, archive_path: &PathBuf, extract_to: &PathBuf) -> Result<(), InstallError> {
+        Ok(self.extract_archive_cancellable(archive_path, extract_to, &CancellationToken::new())?)
+    }
+
+    /// Extract a downloaded archive, checking `cancel` before each entry
+    #[tracing::instrument(name = "extract", skip_all, fields(archive = %archive_path.display(), to = %extract_to.display()))]
+    fn extract_archive_cancellable(&self, archive_path: &PathBuf, extract_to: &PathBuf, cancel: &CancellationToken) -> Result<()> {
+        self.broadcast_progress(State::Extracting, 0.0);
+        std::fs::create_dir_all(extract_to)
+            .context(format!(
+                "Failed to create extraction directory '{}'. Check write permissions.",
+                extract_to.display()
+            ))?;
+
+        let file_name = archive_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context(format!("Invalid archive path: {}", archive_path.display()))?;
+
+        let supported = self.supported_formats();
+        match ArchiveFormat::from_file_name(file_name).filter(|f| supported.contains(f)) {
+            Some(ArchiveFormat::TarGz) => {
+                self.extract_tar_gz(archive_path, extract_to, cancel)
+                    .context(format!("Failed to extract TAR.GZ archive '{}'", file_name))?;
+            }
+            Some(ArchiveFormat::TarXz) => {
+                self.extract_tar_xz(archive_path, extract_to, cancel)
+                    .context(format!("Failed to extract TAR.XZ archive '{}'", file_name))?;
+            }
+            Some(ArchiveFormat::TarBz2) => {
+                self.extract_tar_bz2(archive_path, extract_to, cancel)
+                    .context(format!("Failed to extract TAR.BZ2 archive '{}'", file_name))?;
+            }
+            Some(ArchiveFormat::Zip) => {
+                self.extract_zip(archive_path, extract_to, cancel)
+                    .context(format!("Failed to extract ZIP archive '{}'", file_name))?;
+            }
+            Some(ArchiveFormat::Gzip) => {
+                self.extract_gzip(archive_path, extract_to, cancel)
+                    .context(format!("Failed to decompress GZ file '{}'", file_name))?;
+            }
+            Some(ArchiveFormat::Binary) => {
+                self.install_binary(archive_path, extract_to, cancel)
+                    .context(format!("Failed to copy binary '{}'", file_name))?;
+            }
+            None => return Err(error::UnsupportedArchive(format!(
+                "Unsupported archive format: '{}'. Supported formats: {}",
+                file_name,
+                archive::describe_extensions(&supported)
+            )).into()),
+        }
+
+        // Progress is now reported from within the extraction functions
+        Ok(())
+    }
+
+    fn extract_tar_gz(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        self.extract_tar(archive_path, extract_to, cancel, flate2::read::GzDecoder::new)
+    }
+
+    fn extract_tar_xz(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        self.extract_tar(archive_path, extract_to, cancel, xz2::read::XzDecoder::new)
+    }
+
+    fn extract_tar_bz2(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        self.extract_tar(archive_path, extract_to, cancel, bzip2::read::BzDecoder::new)
+    }
+
+    /// Decompress a single gzipped file into `extract_to`, named after the archive minus `.gz`
+    fn extract_gzip(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        cancel.check()?;
+
+        let file_name = archive_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let output_name = &file_name[..file_name.len().saturating_sub(".gz".len())];
+        let output_path = archive::safe_entry_path(extract_to, std::path::Path::new(output_name))?;
+        if self.skip_existing() && output_path.exists() {
+            self.broadcast_progress(State::Extracting, 1.0);
+            return Ok(());
+        }
+
+        let mut decoder = flate2::read::GzDecoder::new(std::fs::File::open(archive_path)?);
+        let mut output = std::fs::File::create(&output_path)?;
+
+        // Read at most one byte past the cap to detect a decompression bomb
+        let mut budget = archive::ExtractionBudget::new(self.config.max_uncompressed_size);
+        let written = match budget.remaining() {
+            Some(remaining) => std::io::copy(&mut std::io::Read::take(&mut decoder, remaining.saturating_add(1)), &mut output)?,
+            None => std::io::copy(&mut decoder, &mut output)?,
+        };
+        if let Err(e) = budget.charge(written) {
+            drop(output);
+            let _ = std::fs::remove_file(&output_path);
+            return Err(e.into());
+        }
+
+        // A release shipped as one compressed file is the program itself
+        set_executable(&output_path)?;
+        self.broadcast_progress(State::Extracting, 1.0);
+        Ok(())
+    }
+
+    /// Copy a bare executable into `extract_to` under the configured binary name
+    fn install_binary(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
+        cancel.check()?;
+
+        let output_path = extract_to.join(self.config.get_binary_file_name());
+        if self.skip_existing() && output_path.exists() {
+            self.broadcast_progress(State::Extracting, 1.0);
+            return Ok(());
+        }
+        std::fs::copy(archive_path, &output_path)?;
+
+        set_executable(&output_path)?;
+        self.broadcast_progress(State::Extracting, 1.0);
+        Ok(())
+    }
+
+    /// Extract a tarball, opening the file twice and wrapping it with `decoder` each time
+    fn extract_tar<R: std::io::Read>(
+        &self,
+        archive_path: &PathBuf,
+        extract_to: &std::path::Path,
+        cancel: &CancellationToken,
+        decoder: fn(std::fs::File) -> R,
+    ) -> Result<()> {
+        let file = std::fs::File::open(archive_path)?;
+        let mut archive = tar::Archive::new(decoder(file));
+
+        // First pass: calculate total bytes to extract
+        let file_for_count = std::fs::File::open(archive_path)?;
+        let mut archive_for_count = tar::Archive::new(decoder(file_for_count));
+        let mut total_bytes: u64 = 0;
+        let mut entry_count: u64 = 0;
+        let mut entry_paths = Vec::new();
+        for entry in archive_for_count.entries()?.filter_map(|e| e.ok()) {
+            total_bytes += entry.header().size().unwrap_or(0);
+            entry_count += 1;
+            if let Ok(path) = entry.path() {
+                entry_paths.push(path.into_owned());
+            }
+        }
+        let strip = self.components_to_strip(entry_paths.iter().map(PathBuf::as_path));
+
+        preflight::check_inodes(extract_to, entry_count)?;
+
+        // Refuse oversized archives before writing anything
+        archive::ExtractionBudget::new(self.config.max_uncompressed_size).charge(total_bytes)?;
+
+        // Second pass: extract, reporting progress after each entry
+        let mut state = extract_state::ExtractState::open(extract_to, archive_path)?;
+        let mut budget = archive::ExtractionBudget::new(self.config.max_uncompressed_size);
+        let mut progress = archive::ExtractionProgress::new(total_bytes, entry_count);
+        let result = (|| -> Result<()> {
+            for entry in archive.entries()? {
+                cancel.check()?;
+                let mut entry = entry?;
+                let entry_size = entry.header().size().unwrap_or(0);
+                archive::unpack_tar_entry(&mut entry, extract_to, strip, self.skip_existing(), &mut state, &mut budget)?;
+
+                self.broadcast_progress(State::Extracting, progress.advance(entry_size));
+            }
+            Ok(())
+        })();
+
+        state.conclude(result)
+    }
+
+    fn extract_zip(&self, archive_path: &PathBuf, extract_to: &std::path::Path, cancel: &CancellationToken) -> Result<()> {
         let file = std::fs::File::open(archive_path)?;
         let mut archive = zip::ZipArchive::new(file)?;
 
-        // Calculate total bytes to extract
-        let mut total_bytes: u64 = 0;
-        for i in 0..archive.len() {
-            if let Ok(file) = archive.by_index(i) {
-                total_bytes += file.size();
+        preflight::check_inodes(extract_to, archive.len() as u64)?;
+
+        // Calculate total bytes to extract
+        let mut total_bytes: u64 = 0;
+        for i in 0..archive.len() {
+            if let Ok(file) = archive.by_index(i) {
+                total_bytes += file.size();
+            }
+        }
+
+        // Refuse archives that declare an oversized total before writing anything; the sizes in
+        // the central directory can lie, so the bytes actually written are counted below as well
+        archive::ExtractionBudget::new(self.config.max_uncompressed_size).charge(total_bytes)?;
+
+        let strip = self.components_to_strip(archive.file_names().map(std::path::Path::new));
+
+        let mut state = extract_state::ExtractState::open(extract_to, archive_path)?;
+        let mut budget = archive::ExtractionBudget::new(self.config.max_uncompressed_size);
+        let mut progress = archive::ExtractionProgress::new(total_bytes, archive.len() as u64);
+
+        let result = (|| -> Result<()> {
+            for i in 0..archive.len() {
+                cancel.check()?;
+                let mut file = archive.by_index(i)?;
+                let file_size = file.size();
+                let Some(entry_path) = archive::strip_components(std::path::Path::new(file.name()), strip) else {
+                    self.broadcast_progress(State::Extracting, progress.advance(file_size));
+                    continue;
+                };
+                if extract_state::is_state_file(&entry_path) {
+                    return Err(UnsafeArchiveEntry { entry: entry_path }.into());
+                }
+                let outpath = archive::safe_entry_path(extract_to, &entry_path)?;
+                #[cfg(target_os = "windows")]
+                let outpath = archive::long_path(&outpath);
+
+                if (self.skip_existing() && !file.is_dir() && outpath.symlink_metadata().is_ok()) || state.is_extracted(&outpath) {
+                    self.broadcast_progress(State::Extracting, progress.advance(file_size));
+                    continue;
+                }
+
+                #[cfg(unix)]
+                if file.unix_mode().is_some_and(archive::is_symlink_mode) {
+                    let mut target = String::new();
+                    std::io::Read::read_to_string(&mut std::io::Read::take(&mut file, 4096), &mut target)?;
+                    budget.charge(target.len() as u64)?;
+                    archive::create_symlink(&entry_path, std::path::Path::new(&target), &outpath)?;
+                    self.broadcast_progress(State::Extracting, progress.advance(file_size));
+                    continue;
+                }
+
+                if file.name().ends_with('/') {
+                    std::fs::create_dir_all(&outpath)?;
+                } else {
+                    if let Some(p) = outpath.parent() && !p.exists() {
+                        std::fs::create_dir_all(p)?;
+                    }
+                    let mut outfile = match std::fs::File::create(&outpath) {
+                        Ok(outfile) => outfile,
+                        #[cfg(target_os = "windows")]
+                        Err(e) if outpath.exists() && win::is_file_in_use(&outpath, &e) => {
+                            // A running binary can't be overwritten, but it can be renamed aside
+                            // and deleted on the next reboot
+                            let mut aside = outpath.clone().into_os_string();
+                            aside.push(".oim-old");
+                            let aside = PathBuf::from(aside);
+                            let _ = std::fs::remove_file(&aside);
+                            std::fs::rename(&outpath, &aside)?;
+                            win::schedule_delete_on_reboot(&aside)?;
+                            self.reboot_pending.store(true, Ordering::Relaxed);
+                            std::fs::File::create(&outpath)?
+                        }
+                        Err(e) => return Err(e.into()),
+                    };
+                    // Read at most one byte past the remaining budget to detect an overrun
+                    let written = match budget.remaining() {
+                        Some(remaining) => std::io::copy(&mut std::io::Read::take(&mut file, remaining.saturating_add(1)), &mut outfile)?,
+                        None => std::io::copy(&mut file, &mut outfile)?,
+                    };
+                    budget.charge(written)?;
+                }
+
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if let Some(mode) = file.unix_mode() {
+                        std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode))?;
+                    }
+                }
+                if !file.is_dir() {
+                    state.record(&outpath)?;
+                }
+
+                self.broadcast_progress(State::Extracting, progress.advance(file_size));
+            }
+            Ok(())
+        })();
+
+        state.conclude(result)
+    }
+
+    /// Whether extraction leaves files that already exist alone
+    fn skip_existing(&self) -> bool {
+        self.config.extract_policy == Some(ExtractPolicy::SkipExisting)
+    }
+
+    /// Delete the previous files from install_path before extracting, if the extraction policy
+    /// asks for it, keeping `data_dirs` and the metadata files stored there
+    fn clean_install_dir(&self, updating: bool) -> Result<()> {
+        let install_path = &self.config.install_path;
+        if self.config.get_extract_policy(updating) != ExtractPolicy::CleanThenExtract || !install_path.exists() {
+            return Ok(());
+        }
+        // Keep the files of an interrupted extraction, so it can resume
+        if extract_state::is_pending(install_path) {
+            return Ok(());
+        }
+
+        let metadata = self.config.metadata_paths();
+        let keep_paths = uninstall::relative_keep_paths(install_path, self.config.data_dirs.iter().chain(&metadata));
+        self.observer.message(format_args!("Removing the previous files from {}...", install_path.display()));
+        uninstall::clear_install_dir(install_path, &keep_paths)
+    }
+
+    /// How many leading directories to remove from the entries of an archive with these paths
+    fn components_to_strip<'a>(&self, entries: impl IntoIterator<Item = &'a std::path::Path>) -> usize {
+        if self.config.strip_components > 0 {
+            self.config.strip_components
+        } else if self.config.strip_single_root && archive::has_single_root(entries) {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Take the cross-process lock for this service, if locking is enabled
+    fn lock_operation(&self) -> Result<Option<lock::OperationLock>> {
+        if !self.config.operation_lock {
+            return Ok(None);
+        }
+        lock::OperationLock::acquire(&self.config.service_name).map(Some)
+    }
+
+    /// Mark this manager and its clones busy and take the cross-process lock if enabled
+    fn begin_operation(&self) -> Result<lock::OperationGuard> {
+        let guard = lock::OperationGuard::begin(&self.busy, &self.config.service_name)?;
+        Ok(guard.with_lock(self.lock_operation()?))
+    }
+
+    /// Install a release from the specified channel
+    ///
+    /// Fails with `InvalidInstallPath` before anything else if `install_path` can't be installed
+    /// to (see `InstallationConfig::validate`), with `OperationInProgress` if another process is
+    /// operating on the same service, and with `Cancelled` if `cancel` is cancelled while
+    /// downloading or extracting. Fails with `ForeignInstallation` rather than overwrite an
+    /// installation this library didn't make (see `installation_origin`).
+    pub async fn install(&mut self, channel: ReleaseChannel, cancel: &CancellationToken) -> Result<(), InstallError> {
+        self.config.validate()?;
+        if self.dry_run {
+            let plan = self.plan_install(channel).await?;
+            self.observer.message(format_args!("{}", plan));
+            return Ok(());
+        }
+
+        let _guard = self.begin_operation()?;
+        self.refuse_foreign()?;
+        self.install_logged(channel, cancel).await?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    /// Work out what `install` would do on the specified channel without changing anything
+    ///
+    /// Fails the same way `install` would if the installation is locked to another channel or
+    /// no release has an asset for this platform.
+    pub async fn plan_install(&mut self, channel: ReleaseChannel) -> Result<InstallPlan, InstallError> {
+        channel_lock::verify(&self.config.get_channel_lock_path(), &self.requested_channel_lock(channel))?;
+        let (release, asset) = self.resolve_release(channel).await?;
+        Ok(self.plan_for(PlannedOperation::Install, &release, &asset))
+    }
+
+    /// Work out what `update` would do on the specified channel without changing anything
+    ///
+    /// Returns None when the installation is already up to date.
+    pub async fn plan_update(&mut self, channel: ReleaseChannel) -> Result<Option<InstallPlan>, InstallError> {
+        channel_lock::verify(&self.config.get_channel_lock_path(), &self.requested_channel_lock(channel))?;
+        let Some((release, asset)) = self.resolve_update(channel).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.plan_for(PlannedOperation::Update, &release, &asset)))
+    }
+
+    fn plan_for(&self, operation: PlannedOperation, release: &GitHubRelease, asset: &GitHubAsset) -> InstallPlan {
+        let updating = operation == PlannedOperation::Update;
+
+        #[cfg(target_os = "windows")]
+        let service_commands = win::planned_service_commands(&self.config, updating);
+
+        #[cfg(target_os = "linux")]
+        let service_commands = nix::planned_service_commands(&self.config, updating);
+
+        #[cfg(target_os = "macos")]
+        let service_commands = mac::planned_service_commands(&self.config, updating);
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        let service_commands = Vec::new();
+
+        InstallPlan {
+            operation,
+            release_tag: release.tag_name.clone(),
+            current_version: if updating { self.current_version.clone() } else { None },
+            asset_name: asset.name.clone(),
+            download_url: self.download_url(asset).unwrap_or_else(|_| asset.browser_download_url.clone()),
+            download_size: asset.size,
+            archive_format: asset.format(),
+            install_path: self.config.install_path.clone(),
+            backup_path: (updating && self.config.rollback_on_failure && self.config.install_path.exists())
+                .then(|| backup::backup_path(&self.config.install_path)),
+            service_commands,
+        }
+    }
+
+    /// Install a release, extracting `.tar.gz` assets while they download instead of saving
+    /// the archive first (other formats, and any archive when `strip_single_root` or
+    /// `signature` is set, use the regular buffered path)
+    ///
+    /// Fails with `OperationInProgress` if another process is operating on the same service, and
+    /// with `ForeignInstallation` if install_path holds an installation this library didn't make.
+    pub async fn install_streaming(&mut self, channel: ReleaseChannel) -> Result<(), InstallError> {
+        self.config.validate()?;
+        if self.dry_run {
+            let plan = self.plan_install(channel).await?;
+            self.observer.message(format_args!("{}", plan));
+            return Ok(());
+        }
+
+        let _guard = self.begin_operation()?;
+        self.refuse_foreign()?;
+        let created_dirs = self.begin_install_log();
+        let result = self.resolve_and_install(channel, &created_dirs, true, &CancellationToken::new()).await;
+        self.install_log.finish(&result, self.reboot_required());
+        result?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    async fn install_logged(&mut self, channel: ReleaseChannel, cancel: &CancellationToken) -> Result<()> {
+        let created_dirs = self.begin_install_log();
+        let result = self.resolve_and_install(channel, &created_dirs, false, cancel).await;
+        self.install_log.finish(&result, self.reboot_required());
+        result
+    }
+
+    /// Start the install log entry of a first install, returning the directories the install
+    /// will create for install_path
+    ///
+    /// They are found first because the log itself may live inside install_path.
+    fn begin_install_log(&self) -> Vec<PathBuf> {
+        let created_dirs = uninstall_manifest::missing_ancestors(&self.config.install_path);
+        self.install_log.begin(&self.config.get_install_log_path(), "install");
+        created_dirs
+    }
+
+    async fn resolve_and_install(
+        &mut self,
+        channel: ReleaseChannel,
+        created_dirs: &[PathBuf],
+        streaming: bool,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        let requested_lock = self.requested_channel_lock(channel);
+        channel_lock::verify(&self.config.get_channel_lock_path(), &requested_lock)?;
+
+        let (release, asset) = self.resolve_release(channel).await?;
+        self.install_release(release, asset, requested_lock, created_dirs, streaming, false, cancel).await
+    }
+
+    /// Install, update or reinstall, whichever brings the installation to the chosen release
+    ///
+    /// The release is `options.version` if set, else the newest one on the Release channel (the
+    /// Alpha channel with `include_prerelease`). Nothing installed means a fresh install; another
+    /// version installed means an update, with its backup and rollback. When the chosen version
+    /// is already installed nothing happens unless `force` is set, in which case it is
+    /// downloaded and installed again the way an update would be. An existing channel lock must
+    /// match the channel, except when a version is given. An installation this library didn't
+    /// make fails with `ForeignInstallation`, or is installed over with `force`.
+    pub async fn install_with_options(&mut self, options: InstallOptions, cancel: &CancellationToken) -> Result<(), InstallError> {
+        self.config.validate()?;
+        let _guard = if self.dry_run { None } else { Some(self.begin_operation()?) };
+
+        let lock_path = self.config.get_channel_lock_path();
+        let (release, asset, channel) = match &options.version {
+            Some(version) => {
+                let release = self.get_release_by_tag(&version.to_string()).await?;
+                let asset = self.select_asset(&release)?;
+                let channel = match channel_lock::read(&lock_path)? {
+                    Some(locked) => locked.channel,
+                    None => narrowest_channel(&release),
+                };
+                (release, asset, channel)
+            }
+            None => {
+                let (release, asset) = self.resolve_release(options.channel()).await?;
+                (release, asset, options.channel())
+            }
+        };
+        let requested_lock = self.requested_channel_lock(channel);
+        channel_lock::verify(&lock_path, &requested_lock)?;
+
+        let version = Version::parse(release.tag_name.trim_start_matches('v'))?;
+        let installed = self.is_installed || self.detect_installation()?;
+        if installed && !options.force && self.current_version.as_ref() == Some(&version) {
+            self.observer.message(format_args!("{} {} is already installed", self.config.service_name, version));
+            return Ok(());
+        }
+        self.latest_version = Some(version);
+
+        if !installed {
+            match self.refuse_foreign() {
+                Err(e) if options.force && e.downcast_ref::<ForeignInstallation>().is_some() => {
+                    self.observer.warning(format_args!("{}; installing over it", e));
+                }
+                result => result?,
+            }
+        }
+
+        let operation = if installed { PlannedOperation::Update } else { PlannedOperation::Install };
+        if self.dry_run {
+            self.observer.message(format_args!("{}", self.plan_for(operation, &release, &asset)));
+            return Ok(());
+        }
+
+        if installed {
+            return self.update_to(release, asset, requested_lock, cancel).await;
+        }
+        let created_dirs = self.begin_install_log();
+        let result = self.install_release(release, asset, requested_lock, &created_dirs, false, false, cancel).await;
+        self.install_log.finish(&result, self.reboot_required());
+        result?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    /// Install a specific version, regardless of which channel's newest release it is
+    ///
+    /// The version may be given with or without a `v` prefix in the release tag. An existing
+    /// channel lock is kept as is; a first install is locked to the most stable channel that
+    /// contains the version. Fails with `AssetNotFound`, listing the available versions, when no
+    /// release has that version.
+    pub async fn install_version(&mut self, version: &Version, cancel: &CancellationToken) -> Result<(), InstallError> {
+        self.config.validate()?;
+        if self.dry_run {
+            let release = self.get_release_by_tag(&version.to_string()).await?;
+            let asset = self.select_asset(&release)?;
+            self.observer.message(format_args!("{}", self.plan_for(PlannedOperation::Install, &release, &asset)));
+            return Ok(());
+        }
+
+        let _guard = self.begin_operation()?;
+        self.refuse_foreign()?;
+        let created_dirs = self.begin_install_log();
+        let result = self.install_version_release(version, &created_dirs, cancel).await;
+        self.install_log.finish(&result, self.reboot_required());
+        result?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    async fn install_version_release(&mut self, version: &Version, created_dirs: &[PathBuf], cancel: &CancellationToken) -> Result<()> {
+        let release = self.get_release_by_tag(&version.to_string()).await?;
+        let asset = self.select_asset(&release)?;
+
+        let lock_path = self.config.get_channel_lock_path();
+        let channel = match channel_lock::read(&lock_path)? {
+            Some(locked) => locked.channel,
+            None => narrowest_channel(&release),
+        };
+        let requested_lock = self.requested_channel_lock(channel);
+        channel_lock::verify(&lock_path, &requested_lock)?;
+
+        self.latest_version = Some(version.clone());
+        self.install_release(release, asset, requested_lock, created_dirs, false, false, cancel).await
+    }
+
+    /// Install from an archive already on disk, for servers without access to GitHub
+    ///
+    /// Extraction, service registration and everything after it run as in `install`, and
+    /// `version` is recorded as the installed version. An existing channel lock is kept as is; a
+    /// first install is locked to the most stable channel that contains the version. Fails with
+    /// `UnsupportedArchive` before touching anything if the file's extension isn't one this
+    /// build can extract.
+    pub async fn install_from_file(&mut self, archive_path: &std::path::Path, version: Version) -> Result<(), InstallError> {
+        let file_name = archive_path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let supported = self.supported_formats();
+        if ArchiveFormat::from_file_name(file_name).is_none_or(|format| !supported.contains(&format)) {
+            return Err(error::UnsupportedArchive(format!(
+                "Unsupported archive format: '{}'. Supported formats: {}",
+                archive_path.display(),
+                archive::describe_extensions(&supported)
+            ))
+            .into());
+        }
+        self.config.validate()?;
+
+        if self.dry_run {
+            self.observer.message(format_args!(
+                "Would install {} version {} from {} to {}",
+                self.config.service_name,
+                version,
+                archive_path.display(),
+                self.config.install_path.display()
+            ));
+            return Ok(());
+        }
+
+        let _guard = self.begin_operation()?;
+        let created_dirs = self.begin_install_log();
+        let result = self.install_local_archive(archive_path, version, &created_dirs, &CancellationToken::new()).await;
+        self.install_log.finish(&result, self.reboot_required());
+        result?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    async fn install_local_archive(
+        &mut self,
+        archive_path: &std::path::Path,
+        version: Version,
+        created_dirs: &[PathBuf],
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        if !archive_path.is_file() {
+            anyhow::bail!("Archive '{}' does not exist", archive_path.display());
+        }
+
+        let lock_path = self.config.get_channel_lock_path();
+        let channel = match channel_lock::read(&lock_path)? {
+            Some(locked) => locked.channel,
+            None => [ReleaseChannel::Release, ReleaseChannel::Beta]
+                .into_iter()
+                .find(|channel| channel.matches_version(&version))
+                .unwrap_or(ReleaseChannel::Alpha),
+        };
+        let requested_lock = self.requested_channel_lock(channel);
+        channel_lock::verify(&lock_path, &requested_lock)?;
+
+        self.observer.message(format_args!("Installing {} version {} from {}...", self.config.service_name, version, archive_path.display()));
+
+        let size = std::fs::metadata(archive_path)?.len();
+        let extracted = self.config.min_free_bytes.unwrap_or_else(|| size.saturating_mul(EXTRACTION_SPACE_MULTIPLIER));
+        preflight::check_space(&[(self.config.install_path.as_path(), extracted)])?;
+        self.run_hook("pre-install", self.config.pre_install_hook.as_deref(), &version.to_string())?;
+
+        // A first install that fails before the service is registered leaves nothing behind
+        let partial_install = cleanup::DirGuard::for_created_dirs(created_dirs);
+        uninstall_manifest::write_created_dirs(&self.config.get_created_dirs_path(), created_dirs)?;
+        self.clean_install_dir(false)?;
+        self.observer.message(format_args!("Extracting to {}...", self.config.install_path.display()));
+        self.extract_archive_cancellable(&archive_path.to_path_buf(), &self.config.install_path, cancel)?;
+        if let Some(guard) = partial_install {
+            guard.disarm();
+        }
+
+        self.latest_version = Some(version.clone());
+        self.register_installation(&version.to_string(), requested_lock, false, cancel).await?;
+
+        self.observer.message(format_args!("Installation complete!"));
+        Ok(())
+    }
+
+    /// Download, extract and register an already resolved release; shared by install and update
+    ///
+    /// `created_dirs` are the directories a first install creates for install_path, as found
+    /// by `begin_install_log`; they are recorded for uninstall and removed again if the install
+    /// fails before the service is registered. While `updating`, registering the service is
+    /// reported as part of the overall Updating progress instead of a separate Installing phase.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "install", skip_all, fields(repo = %self.config.github_repo, tag = %release.tag_name, asset = %asset.name, updating))]
+    async fn install_release(
+        &mut self,
+        release: GitHubRelease,
+        asset: GitHubAsset,
+        requested_lock: ChannelLock,
+        created_dirs: &[PathBuf],
+        streaming: bool,
+        updating: bool,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        self.record_resolved(&release, &asset);
+
+        self.observer.message(format_args!("Installing {} version {}...", self.config.service_name, release.tag_name));
+
+        #[cfg(target_os = "linux")]
+        if let Some(kind) = package::PackageKind::of_asset(&asset) {
+            return self.install_package_release(&release, &asset, kind, requested_lock, updating, cancel).await;
+        }
+
+        // A single root can't be detected, nor a signature checked, before the whole stream has been read
+        let streaming = streaming
+            && asset.format() == Some(ArchiveFormat::TarGz)
+            && !(self.config.strip_single_root && self.config.strip_components == 0)
+            && self.config.signature.is_none();
+        self.check_disk_space(&asset, streaming)?;
+
+        // An update runs the hook itself, before stopping the service
+        if !updating {
+            self.run_hook("pre-install", self.config.pre_install_hook.as_deref(), &release.tag_name)?;
+        }
+
+        // A first install that fails before the service is registered leaves nothing behind
+        let partial_install = if updating { None } else { cleanup::DirGuard::for_created_dirs(created_dirs) };
+        if !updating {
+            // Recorded before anything is written, so a failed install can be retried (see `installation_origin`)
+            uninstall_manifest::write_created_dirs(&self.config.get_created_dirs_path(), created_dirs)?;
+        }
+
+        let extracted = async {
+            if streaming {
+                self.clean_install_dir(updating)?;
+                self.observer.message(format_args!("Downloading and extracting {} to {}...", asset.name, self.config.install_path.display()));
+                self.stream_extract_tar_gz(&asset, &self.config.install_path, cancel).await
+            } else {
+                // The download is deleted when this goes out of scope, unless it was cut short and can be resumed
+                let temp_dir = self.create_temp_dir().await?;
+                let result = self.download_and_extract(&release, &asset, temp_dir.path(), updating, cancel).await;
+                if let Err(e) = &result {
+                    self.keep_partial_download(temp_dir, &asset, e);
+                }
+                result
+            }
+        }
+        .await;
+        if let Err(e) = extracted {
+            // A cancelled extraction keeps the files it wrote, so installing again resumes it
+            if cancel.is_cancelled() && extract_state::is_pending(&self.config.install_path) {
+                if let Some(guard) = partial_install {
+                    guard.disarm();
+                }
+                self.observer.message(format_args!("Extraction cancelled; install again to resume it"));
+            }
+            return Err(e);
+        }
+        self.fetch_file_manifest(&release).await?;
+
+        // From here on the service may be registered, so the files stay for uninstall to remove
+        if let Some(guard) = partial_install {
+            guard.disarm();
+        }
+
+        self.register_installation(&release.tag_name, requested_lock, updating, cancel).await?;
+
+        if !updating {
+            self.observer.message(format_args!("Installation complete!"));
+        }
+        Ok(())
+    }
+
+    /// Download `asset` into `temp_dir`, check its signature and extract it into install_path
+    async fn download_and_extract(
+        &self,
+        release: &GitHubRelease,
+        asset: &GitHubAsset,
+        temp_dir: &std::path::Path,
+        updating: bool,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        self.observer.message(format_args!("Downloading {}...", asset.name));
+        let download_path = temp_dir.join(asset.local_file_name());
+        if !self.fetch_by_patch(release, asset, &download_path, cancel).await? {
+            self.fetch_asset(asset, &download_path, cancel).await?;
+        }
+        self.verify_signature(release, asset, &download_path).await?;
+        self.clean_install_dir(updating)?;
+
+        self.observer.message(format_args!("Extracting to {}...", self.config.install_path.display()));
+        self.extract_archive_cancellable(&download_path, &self.config.install_path, cancel)
+    }
+
+    /// Create the directory downloads go in, falling back to the system temp directory when
+    /// the default one next to install_path can't be created; it is removed again when the
+    /// returned guard is dropped
+    async fn create_temp_dir(&self) -> Result<cleanup::DirGuard> {
+        let temp_dir = self.config.get_temp_dir();
+        match tokio::fs::create_dir_all(&temp_dir).await {
+            Ok(()) => Ok(cleanup::DirGuard::new(temp_dir)),
+            Err(e) if self.config.temp_dir.is_some() => {
+                Err(e).context(format!("Failed to create temp directory '{}'", temp_dir.display()))
+            }
+            Err(_) => {
+                let fallback = std::env::temp_dir().join(format!("oim-{}", self.config.service_name));
+                tokio::fs::create_dir_all(&fallback).await?;
+                Ok(cleanup::DirGuard::new(fallback))
+            }
+        }
+    }
+
+    /// Keep `temp_dir` when `error` is a network failure partway through downloading `asset`
+    /// into it, so running the operation again resumes the download; otherwise the guard is
+    /// dropped and the directory removed
+    pub(crate) fn keep_partial_download(&self, temp_dir: cleanup::DirGuard, asset: &GitHubAsset, error: &anyhow::Error) {
+        let download_path = temp_dir.path().join(asset.local_file_name());
+        if retry::is_transient(error) && resume_marker_path(&download_path).exists() {
+            self.observer.message(format_args!("Keeping the partial download in {} to resume it", temp_dir.path().display()));
+            temp_dir.disarm();
+        }
+    }
+
+    /// Turn freshly extracted files into a running installation: permissions, verification,
+    /// service registration, health check, channel lock and the recorded version
+    async fn register_installation(
+        &mut self,
+        tag_name: &str,
+        requested_lock: ChannelLock,
+        updating: bool,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        let lock_path = self.config.get_channel_lock_path();
+
+        if self.config.clear_quarantine {
+            quarantine::clear_quarantine(&self.config.install_path)?;
+        }
+
+        // Set directory permissions on Windows
+        #[cfg(target_os = "windows")]
+        {
+            win::set_directory_permissions(&self.config)
+                .context("Failed to set directory permissions")?;
+        }
+
+        #[cfg(unix)]
+        if let Some(policy) = &self.config.file_permission_policy {
+            self.apply_permission_policy(policy)?;
+        }
+
+        // Make sure the binary landed and actually works before registering it as a service
+        binaries::verify_extracted(&self.config, || self.find_installed_executable())?;
+        self.run_post_install_verify(tag_name)?;
+
+        // Platform-specific installation
+        let (phase, phase_start, phase_end) = if updating {
+            (State::Updating, 0.6, 0.7)
+        } else {
+            (State::Installing, 0.0, 1.0)
+        };
+        self.broadcast_progress(phase.clone(), phase_start);
+
+        self.install_service(tag_name)?;
+
+        if self.config.create_shortcuts || self.config.binaries.iter().any(|spec| spec.shortcut) {
+            self.create_shortcuts()?;
+        }
+
+        #[cfg(unix)]
+        binaries::link_binaries(&self.config)?;
+
+        self.broadcast_progress(phase, phase_end);
+
+        self.start_and_verify_service()?;
+
+        if let Some(check) = &self.config.health_check {
+            self.observer.message(format_args!("Waiting for {} to pass its health check ({})...", self.config.service_name, check.probe));
+            health::wait_until_healthy(&self.config.service_name, check, self.config.proxy.as_deref(), cancel).await?;
+        }
+        self.run_hook("post-install", self.config.post_install_hook.as_deref(), tag_name)?;
+
+        // Pin the installation to this repo and channel on first install
+        if channel_lock::read(&lock_path)?.is_none() {
+            channel_lock::write(&lock_path, &requested_lock)?;
+        }
+
+        // Update internal state
+        let version_str = tag_name.trim_start_matches('v');
+        self.current_version = Some(Version::parse(version_str)?);
+        self.is_installed = true;
+        Ok(())
+    }
+
+    /// Register the service with the platform's service manager
+    #[tracing::instrument(skip_all, fields(service = %self.config.service_name, tag = tag_name))]
+    fn install_service(&self, tag_name: &str) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            win::install_service(&self.config, tag_name)?;
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            nix::install_service(&self.config, tag_name)?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac::install_service(&self.config, tag_name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the Start Menu shortcuts (Windows) or application launchers (Linux) for the installed
+    /// executables; uninstall removes them with the service
+    ///
+    /// The primary executable gets one when create_shortcuts or its own `shortcut` is set, every
+    /// other binary when its `shortcut` is set.
+    fn create_shortcuts(&self) -> Result<()> {
+        let primary = self.config.create_shortcuts || self.config.get_primary_binary().is_some_and(|spec| spec.shortcut);
+        let secondary = self.config.secondary_shortcuts();
+        let locate = |spec: &BinarySpec| {
+            binaries::locate(&self.config.install_path, &spec.name)
+                .context(format!("Binary '{}' not found in '{}'", spec.name, self.config.install_path.display()))
+        };
+
+        #[cfg(target_os = "windows")]
+        {
+            if primary {
+                let exe_path = win::find_executable(&self.config)?;
+                win::create_shortcut(&exe_path, self.config.get_display_name(), self.config.desktop_shortcut)?;
+            }
+            for (spec, name) in &secondary {
+                win::create_shortcut(&locate(spec)?, name, self.config.desktop_shortcut)?;
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if primary {
+                let exe_path = nix::find_executable(&self.config)?;
+                nix::create_shortcut(&self.config, &nix::shortcut_path(&self.config), &exe_path, self.config.get_display_name())?;
+            }
+            for (spec, name) in &secondary {
+                let path = nix::binary_shortcut_path(&self.config, &spec.name);
+                nix::create_shortcut(&self.config, &path, &locate(spec)?, name)?;
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let _ = (primary, secondary, locate);
+
+        Ok(())
+    }
+
+    /// Fail with `InsufficientSpace` before downloading if the temp or install volume is too full
+    ///
+    /// The download needs the asset's size in the temp directory (unless it's streamed), and
+    /// extraction `min_free_bytes` or a conservative multiple of the asset's size.
+    fn check_disk_space(&self, asset: &GitHubAsset, streaming: bool) -> Result<()> {
+        let extracted = self
+            .config
+            .min_free_bytes
+            .unwrap_or_else(|| asset.size.saturating_mul(EXTRACTION_SPACE_MULTIPLIER));
+        let temp_dir = self.config.get_temp_dir();
+
+        let mut requirements = vec![(self.config.install_path.as_path(), extracted)];
+        if !streaming {
+            requirements.push((temp_dir.as_path(), asset.size));
+        }
+        preflight::check_space(&requirements)
+    }
+
+    /// Repair an existing installation by re-downloading and re-extracting the installed version
+    ///
+    /// Unlike `update`, this always targets `current_version`, to put back missing or corrupt
+    /// files (see `verify_installation`). Files are extracted over install_path without deleting
+    /// anything, and files that already exist in the configured `data_dirs` are left as they are.
+    /// The service is stopped while files are replaced and restarted afterwards. Fails if no
+    /// installed version is found.
+    pub async fn repair(&mut self) -> Result<(), InstallError> {
+        if !self.is_installed && !self.detect_installation()? {
+            return Err(anyhow::anyhow!("No installation found to repair. Use install() instead.").into());
+        }
+
+        let _guard = self.begin_operation()?;
+        self.install_log.begin(&self.config.get_install_log_path(), "repair");
+        let result = self.repair_release().await;
+        self.install_log.finish(&result, self.reboot_required());
+        result?;
+        self.broadcast_progress(State::Complete, 1.0);
+        Ok(())
+    }
+
+    async fn repair_release(&mut self) -> Result<()> {
+        let version = self
+            .current_version
+            .clone()
+            .context("The installed version is unknown, so there is nothing to repair to")?;
+        self.observer.message(format_args!("Repairing {} {}...", self.config.service_name, version));
+
+        let release = self.get_release_by_tag(&version.to_string()).await?;
+        let asset = self.select_asset(&release)?;
+
+        let lock_path = self.config.get_channel_lock_path();
+        let channel = match channel_lock::read(&lock_path)? {
+            Some(locked) => locked.channel,
+            None => narrowest_channel(&release),
+        };
+        channel_lock::verify(&lock_path, &self.requested_channel_lock(channel))?;
+
+        self.record_resolved(&release, &asset);
+        self.check_disk_space(&asset, false)?;
+
+        let temp_dir = self.create_temp_dir().await?;
+        if let Err(e) = self.repair_from(&release, &asset, temp_dir.path()).await {
+            self.keep_partial_download(temp_dir, &asset, &e);
+            return Err(e);
+        }
+
+        self.observer.message(format_args!("Repair complete!"));
+        Ok(())
+    }
+
+    /// Download the release into `temp_dir` and restore install_path from it
+    async fn repair_from(&self, release: &GitHubRelease, asset: &GitHubAsset, temp_dir: &std::path::Path) -> Result<()> {
+        self.observer.message(format_args!("Downloading {}...", asset.name));
+        let download_path = temp_dir.join(asset.local_file_name());
+        self.fetch_asset(asset, &download_path, &CancellationToken::new()).await?;
+        self.verify_signature(release, asset, &download_path).await?;
+
+        self.stop_service_gracefully()?;
+
+        // Extract next to the download first, so data_dirs can be skipped when copying over
+        let staging_dir = temp_dir.join("repair");
+        self.extract_archive(&download_path, &staging_dir)?;
+
+        self.observer.message(format_args!("Restoring files in {}...", self.config.install_path.display()));
+        let keep_paths = uninstall::relative_keep_paths(&self.config.install_path, &self.config.data_dirs);
+        backup::overlay_tree(&staging_dir, &self.config.install_path, &keep_paths)?;
+        self.fetch_file_manifest(release).await?;
+
+        if self.config.clear_quarantine {
+            quarantine::clear_quarantine(&self.config.install_path)?;
+        }
+
+        // Set directory permissions on Windows
+        #[cfg(target_os = "windows")]
+        {
+            win::set_directory_permissions(&self.config)
+                .context("Failed to set directory permissions")?;
+        }
+
+        #[cfg(unix)]
+        if let Some(policy) = &self.config.file_permission_policy {
+            self.apply_permission_policy(policy)?;
+        }
+
+        binaries::verify_extracted(&self.config, || self.find_installed_executable())?;
+        self.run_post_install_verify(&release.tag_name)?;
+
+        self.broadcast_progress(State::Installing, 1.0);
+        self.start_and_verify_service()
+    }
+
+    /// Stop the service and wait until it has exited, force-killing it after `force_stop_after`
+    /// if configured, so files are never replaced while it may still be writing
+    fn stop_service_gracefully(&self) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        win::request_stop(&self.config)?;
+
+        #[cfg(target_os = "linux")]
+        nix::request_stop(&self.config)?;
+
+        #[cfg(target_os = "macos")]
+        mac::request_stop(&self.config)?;
+
+        // Leave time for a forced kill to take effect even if it's configured past stop_timeout
+        let deadline = match self.config.force_stop_after {
+            Some(after) => self.config.stop_timeout.max(after + FORCE_STOP_GRACE),
+            None => self.config.stop_timeout,
+        };
+
+        let started = Instant::now();
+        let mut killed = false;
+        loop {
+            #[cfg(target_os = "windows")]
+            let stopped = win::service_stopped(&self.config)?;
+
+            #[cfg(target_os = "linux")]
+            let stopped = nix::service_stopped(&self.config)?;
+
+            #[cfg(target_os = "macos")]
+            let stopped = mac::service_stopped(&self.config)?;
+
+            #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+            let stopped = true;
+
+            if stopped {
+                return Ok(());
+            }
+
+            let elapsed = started.elapsed();
+            if !killed && self.config.force_stop_after.is_some_and(|after| elapsed >= after) {
+                self.observer.warning(format_args!("Service '{}' did not stop in time, forcing it to exit", self.config.service_name));
+
+                #[cfg(target_os = "windows")]
+                win::kill_service(&self.config)?;
+
+                #[cfg(target_os = "linux")]
+                nix::kill_service(&self.config)?;
+
+                #[cfg(target_os = "macos")]
+                mac::kill_service(&self.config)?;
+
+                killed = true;
+            }
+
+            if elapsed >= deadline {
+                return Err(error::ServiceFailure(format!(
+                    "Service '{}' did not stop within {} seconds; not updating while it may still be writing data",
+                    self.config.service_name,
+                    deadline.as_secs()
+                )).into());
+            }
+
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    /// Start the service and poll until it is running, reporting progress as Finalizing
+    fn start_and_verify_service(&self) -> Result<()> {
+        self.broadcast_progress(State::Finalizing, 0.0);
+
+        #[cfg(target_os = "windows")]
+        {
+            win::start_service(&self.config)?;
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            nix::start_service(&self.config)?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac::start_service(&self.config)?;
+        }
+
+        for attempt in 0..SERVICE_START_TIMEOUT_SECS {
+            #[cfg(target_os = "windows")]
+            let started = win::service_started(&self.config)?;
+
+            #[cfg(target_os = "linux")]
+            let started = nix::service_started(&self.config)?;
+
+            #[cfg(target_os = "macos")]
+            let started = mac::service_started(&self.config)?;
+
+            #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+            let started = true;
+
+            if started {
+                self.broadcast_progress(State::Finalizing, 1.0);
+                return Ok(());
+            }
+
+            self.broadcast_progress(State::Finalizing, attempt as f32 / SERVICE_START_TIMEOUT_SECS as f32);
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+
+        Err(error::ServiceFailure(format!(
+            "Service '{}' did not become active within {} seconds",
+            self.config.service_name,
+            SERVICE_START_TIMEOUT_SECS
+        )).into())
+    }
+
+    /// Re-create, re-enable and start the service from the current config without touching installed files
+    ///
+    /// This targets the "files OK, service broken" case (e.g. the service was disabled or deleted)
+    /// and verifies the service comes up. Use `repair()` to re-download the files as well.
+    pub fn repair_service(&self) -> Result<(), InstallError> {
+        if self.installed_version()?.is_none() {
+            return Err(anyhow::anyhow!("No installation found. Use install() instead.").into());
+        }
+
+        self.observer.message(format_args!("Repairing {} service...", self.config.service_name));
+
+        #[cfg(target_os = "windows")]
+        {
+            win::repair_service(&self.config)?;
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            nix::repair_service(&self.config)?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac::repair_service(&self.config)?;
+        }
+
+        self.start_and_verify_service()?;
+
+        self.observer.message(format_args!("Service repair complete!"));
+        Ok(())
+    }
+
+    /// Update an existing installation on the specified channel, safe to call from a polling timer
+    ///
+    /// Returns `Updated` once an update was applied and `UpToDate` if there was none. Returns
+    /// `Busy` instead of failing while another operation runs on this manager, a clone or another
+    /// process, and `CheckedRecently` without checking again if a check within
+    /// `update_check_interval` found the installed version current. Cancelling `cancel` during
+    /// the download or extraction fails with `Cancelled`, after rolling back to the previous
+    /// installation if `rollback_on_failure` is set.
+    pub async fn update(&mut self, channel: ReleaseChannel, cancel: &CancellationToken) -> Result<UpdateStatus, InstallError> {
+        if self.busy.load(Ordering::Acquire) {
+            return Ok(UpdateStatus::Busy);
+        }
+
+        if !self.is_installed && !self.detect_installation()? {
+            self.refuse_foreign()?;
+            return Err(anyhow::anyhow!("No installation found. Use install() instead.").into());
+        }
+
+        if self.recent_update_check() == Some(false) {
+            return Ok(UpdateStatus::CheckedRecently { update_available: false });
+        }
+
+        if self.dry_run {
+            return Ok(match self.plan_update(channel).await? {
+                Some(plan) => {
+                    self.observer.message(format_args!("{}", plan));
+                    UpdateStatus::UpdateAvailable
+                }
+                None => {
+                    self.observer.message(format_args!("Already up to date!"));
+                    UpdateStatus::UpToDate
+                }
+            });
+        }
+
+        let _guard = match self.begin_operation() {
+            Ok(guard) => guard,
+            Err(e) if e.downcast_ref::<OperationInProgress>().is_some() => return Ok(UpdateStatus::Busy),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Check before stopping the service so a mismatch leaves it running
+        let requested_lock = self.requested_channel_lock(channel);
+        channel_lock::verify(&self.config.get_channel_lock_path(), &requested_lock)?;
+
+        let Some((release, asset)) = self.resolve_update(channel).await? else {
+            self.observer.message(format_args!("Already up to date!"));
+            return Ok(UpdateStatus::UpToDate);
+        };
+
+        self.update_to(release, asset, requested_lock, cancel).await?;
+        Ok(UpdateStatus::Updated)
+    }
+
+    /// Replace the installed version with the resolved release: stop the service, back up the
+    /// install directory, install over it and start the service, rolling back on failure
+    async fn update_to(
+        &mut self,
+        release: GitHubRelease,
+        asset: GitHubAsset,
+        requested_lock: ChannelLock,
+        cancel: &CancellationToken,
+    ) -> Result<(), InstallError> {
+        // Opened first so a failing hook, stop or backup is recorded too
+        self.install_log.begin(&self.config.get_install_log_path(), "update");
+
+        // The package manager replaces a package's files itself
+        #[cfg(target_os = "linux")]
+        let packaged = package::PackageKind::of_asset(&asset).is_some();
+        #[cfg(not(target_os = "linux"))]
+        let packaged = false;
+
+        let previous_version = self.current_version.clone();
+        let backup = match self.prepare_update(&release, packaged) {
+            Ok(backup) => backup,
+            Err(e) => {
+                let failed = Err(e);
+                self.install_log.finish(&failed, self.reboot_required());
+                return failed.map_err(InstallError::from);
+            }
+        };
+
+        self.broadcast_progress(State::Updating, 0.2);
+
+        let result = self.install_and_start(release, asset, requested_lock, cancel).await;
+
+        // Close the entry before a rollback replaces install_path, which may hold the log
+        self.install_log.finish(&result, self.reboot_required());
+
+        match (result, backup) {
+            (Ok(()), Some(backup)) => {
+                if let Err(e) = backup.discard() {
+                    self.observer.warning(format_args!("{:#}", e));
+                }
+            }
+            (Ok(()), None) => {}
+            (Err(e), Some(backup)) => {
+                self.observer.message(format_args!("Update failed, restoring the previous installation..."));
+                return match self.roll_back(backup, previous_version) {
+                    Ok(()) => Err(e.into()),
+                    Err(rollback_error) => Err(e
+                        .context(format!("Restoring the previous installation also failed: {:#}", rollback_error))
+                        .into()),
+                };
+            }
+            (Err(e), None) => return Err(e.into()),
+        }
+
+        self.broadcast_progress(State::Updating, 1.0);
+        self.broadcast_progress(State::Complete, 1.0);
+
+        self.observer.message(format_args!("Update complete!"));
+        Ok(())
+    }
+
+    /// Run the pre-install hook, stop the service and back up install_path for a rollback
+    fn prepare_update(&mut self, release: &GitHubRelease, packaged: bool) -> Result<Option<backup::InstallBackup>> {
+        match &self.current_version {
+            Some(current) => self.observer.message(format_args!("Updating from {} to {}...", current, release.tag_name)),
+            None => self.observer.message(format_args!("Updating to {}...", release.tag_name)),
+        }
+
+        self.broadcast_progress(State::Updating, 0.0);
+        self.run_hook("pre-install", self.config.pre_install_hook.as_deref(), &release.tag_name)?;
+
+        // Wait for the service to shut down cleanly before touching its files
+        self.stop_service_gracefully()?;
+
+        if self.config.rollback_on_failure && self.config.install_path.exists() && !packaged {
+            Ok(Some(backup::InstallBackup::create(&self.config.install_path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Install the resolved release over the existing files and make sure the service is running
+    async fn install_and_start(
+        &mut self,
+        release: GitHubRelease,
+        asset: GitHubAsset,
+        requested_lock: ChannelLock,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        // Perform installation (which will overwrite existing files)
+        self.install_release(release, asset, requested_lock, &[], false, true, cancel).await?;
+
+        // install_release has registered the service and started it
+        self.broadcast_progress(State::Updating, 0.8);
+        Ok(())
+    }
+
+    /// Restore the files from before a failed update, re-register the service for them and start it
+    fn roll_back(&mut self, backup: backup::InstallBackup, previous_version: Option<Version>) -> Result<()> {
+        // The new version may have started before failing
+        let _ = self.stop_service_gracefully();
+
+        // Keep the log entry of the failed update and the other records written since the backup
+        backup.restore(&self.config.metadata_paths())?;
+
+        #[cfg(target_os = "windows")]
+        win::repair_service(&self.config)?;
+
+        #[cfg(target_os = "linux")]
+        nix::repair_service(&self.config)?;
+
+        #[cfg(target_os = "macos")]
+        mac::repair_service(&self.config)?;
+
+        if let Some(version) = &previous_version {
+            #[cfg(target_os = "windows")]
+            win::set_installed_version(&self.config, &version.to_string())?;
+
+            #[cfg(target_os = "linux")]
+            nix::set_installed_version(&self.config, &version.to_string())?;
+
+            #[cfg(target_os = "macos")]
+            mac::set_installed_version(&self.config, &version.to_string())?;
+        }
+        self.current_version = previous_version;
+
+        self.start_and_verify_service()?;
+
+        self.observer.message(format_args!("Restored the previous installation."));
+        Ok(())
+    }
+
+    /// Uninstall the application
+    ///
+    /// Removes the service and everything in install_path except the configured `data_dirs`,
+    /// printing a warning that lists what will be deleted first.
+    pub async fn uninstall(&mut self) -> Result<(), InstallError> {
+        self.uninstall_with_options(UninstallOptions::default()).await
+    }
+
+    /// Uninstall the application, keeping the given files or directories (relative to install_path)
+    /// in addition to the configured `data_dirs`
+    ///
+    /// The service is still removed; only the kept files survive.
+    pub async fn uninstall_preserving(&mut self, keep: &[PathBuf]) -> Result<(), InstallError> {
+        let options = UninstallOptions {
+            keep_paths: keep.to_vec(),
+            ..UninstallOptions::default()
+        };
+        self.uninstall_with_options(options).await
+    }
+
+    /// Describe everything the current installation created (files, service, registry values)
+    ///
+    /// Callers can add startup entries and shortcuts they created before writing it out.
+    pub fn uninstall_manifest(&self) -> Result<UninstallManifest, InstallError> {
+        #[cfg(target_os = "windows")]
+        let version = win::get_installed_version(&self.config)?;
+
+        #[cfg(target_os = "linux")]
+        let version = nix::get_installed_version(&self.config)?;
+
+        #[cfg(target_os = "macos")]
+        let version = mac::get_installed_version(&self.config)?;
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        let version: Option<Version> = None;
+
+        Ok(UninstallManifest::capture(&self.config, version.map(|v| v.to_string()))?)
+    }
+
+    /// Write a self-contained uninstall manifest that `uninstall_from_manifest` can consume later
+    pub fn write_uninstaller(&self, path: &std::path::Path) -> Result<(), InstallError> {
+        Ok(self.uninstall_manifest()?.write(path)?)
+    }
+
+    /// Remove an installation described by a manifest from `write_uninstaller`, without needing
+    /// the original configuration
+    ///
+    /// Only the files listed in the manifest are removed. Returns true if a reboot is needed to
+    /// finish removing locked files (Windows).
+    pub fn uninstall_from_manifest(path: &std::path::Path) -> Result<bool, InstallError> {
+        Self::manifest_uninstall(path, &Observer::default())
+    }
+
+    /// Like `uninstall_from_manifest`, reporting progress and warnings to `observer`
+    pub fn uninstall_from_manifest_observed(path: &std::path::Path, observer: Arc<dyn InstallObserver>) -> Result<bool, InstallError> {
+        Self::manifest_uninstall(path, &Observer::new(observer))
+    }
+
+    fn manifest_uninstall(path: &std::path::Path, observer: &Observer) -> Result<bool, InstallError> {
+        let manifest = UninstallManifest::read(path)?;
+        let _lock = lock::OperationLock::acquire(&manifest.service_name)?;
+
+        observer.message(format_args!("Uninstalling {} from manifest...", manifest.service_name));
+
+        #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
+        let mut reboot_required = false;
+        let config = manifest.to_config();
+
+        // The service may already be gone; the remaining cleanup is still worth doing
+        #[cfg(target_os = "windows")]
+        match win::uninstall_service(&config) {
+            Ok(pending) => reboot_required |= pending,
+            Err(e) => observer.warning(format_args!("Failed to remove service: {:#}", e)),
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Err(e) = nix::uninstall_service(&config) {
+            observer.warning(format_args!("Failed to remove service: {:#}", e));
+        }
+
+        #[cfg(target_os = "macos")]
+        if let Err(e) = mac::uninstall_service(&config) {
+            observer.warning(format_args!("Failed to remove service: {:#}", e));
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        let _ = config;
+
+        if manifest.remove_files()? {
+            reboot_required = true;
+        }
+
+        observer.message(format_args!("Uninstall complete!"));
+        Ok(reboot_required)
+    }
+
+    /// Uninstall the application, keeping any files matched by the options' keep globs or kept
+    /// paths, and the configured `data_dirs`
+    ///
+    /// The service is removed regardless of what is kept.
+    pub async fn uninstall_with_options(&mut self, options: UninstallOptions) -> Result<(), InstallError> {
+        let _guard = self.begin_operation()?;
+
+        // Check registry/filesystem directly instead of relying on self.is_installed
+        // since the manager may have been newly created
+        if !self.detect_installation()? {
+            self.refuse_foreign()?;
+            return Err(anyhow::anyhow!("No installation found in registry.").into());
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(kind) = self.installed_package() {
+            return Ok(self.uninstall_package(kind)?);
+        }
+
+        let keep_patterns = uninstall::compile_keep_globs(&options.keep_globs)?;
+        let keep_paths = uninstall::relative_keep_paths(
+            &self.config.install_path,
+            self.config.data_dirs.iter().chain(&options.keep_paths),
+        );
+        let created_dirs_path = self.config.get_created_dirs_path();
+        let created_dirs = uninstall_manifest::read_created_dirs(&created_dirs_path)?;
+
+        self.observer.message(format_args!("Uninstalling {}...", self.config.service_name));
+
+        if self.config.install_path.exists() {
+            let install_path = self.config.install_path.display();
+            if keep_patterns.is_empty() && keep_paths.is_empty() {
+                self.observer.warning(format_args!(
+                    "Deleting {} and everything in it, including any configuration or data stored there",
+                    install_path
+                ));
+            } else {
+                let kept: Vec<String> = options
+                    .keep_globs
+                    .iter()
+                    .cloned()
+                    .chain(keep_paths.iter().map(|path| path.display().to_string()))
+                    .collect();
+                self.observer.warning(format_args!("Deleting everything in {} except {}", install_path, kept.join(", ")));
+            }
+        }
+
+        // Platform-specific service removal
+        #[cfg(target_os = "windows")]
+        {
+            if win::uninstall_service(&self.config)? {
+                self.reboot_pending.store(true, Ordering::Relaxed);
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            nix::uninstall_service(&self.config)?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac::uninstall_service(&self.config)?;
+        }
+
+        #[cfg(unix)]
+        binaries::unlink_binaries(&self.config)?;
+
+        // Remove installation directory (except for files the caller asked to keep)
+        if self.config.install_path.exists()
+            && uninstall::sweep_install_dir(&self.config.install_path, &keep_patterns, &keep_paths)?
+        {
+            self.reboot_pending.store(true, Ordering::Relaxed);
+        }
+
+        // A fresh install afterwards may use any source
+        let _ = std::fs::remove_file(self.config.get_channel_lock_path());
+        let _ = std::fs::remove_file(&created_dirs_path);
+
+        // Parent directories the install created, as long as nothing else lives in them
+        uninstall_manifest::remove_created_dirs(&created_dirs);
+
+        self.is_installed = false;
+        self.current_version = None;
+
+        self.observer.message(format_args!("Uninstall complete!"));
+        Ok(())
+    }
+}
+
+/// Mark a file executable by everyone who can read it (Unix only)
+#[cfg(unix)]
+fn set_executable(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | ((permissions.mode() & 0o444) >> 2));
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
+/// Find the non-draft release tagged with `version`, or fail listing the versions that exist
+fn find_release_by_version(releases: Vec<GitHubRelease>, version: &Version, github_repo: &str) -> Result<GitHubRelease> {
+    let mut available = Vec::new();
+
+    for release in releases.into_iter().filter(|release| !release.draft) {
+        match Version::parse(release.tag_name.trim_start_matches('v')) {
+            Ok(candidate) if &candidate == version => return Ok(release),
+            Ok(candidate) => available.push(candidate.to_string()),
+            Err(_) => {}
+        }
+    }
+
+    let available = if available.is_empty() { "none".to_string() } else { available.join(", ") };
+    Err(error::AssetNotFound(format!(
+        "Version {} was not found in repository '{}'. Available versions: {}",
+        version, github_repo, available
+    ))
+    .into())
+}
+
+/// Parse releases into `ReleaseInfo`s sorted by descending version, skipping drafts and non-version tags
+fn release_infos(releases: Vec<GitHubRelease>) -> Vec<ReleaseInfo> {
+    let mut infos: Vec<ReleaseInfo> = releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| {
+            let version = Version::parse(release.tag_name.trim_start_matches('v')).ok()?;
+            Some(ReleaseInfo {
+                channel: narrowest_channel(&release),
+                version,
+                tag: release.tag_name,
+                prerelease: release.prerelease,
+                published_at: release.published_at,
+                assets: release.assets,
+            })
+        })
+        .collect();
+
+    infos.sort_by(|a, b| b.version.cmp(&a.version));
+    infos
+}
+
+/// The most stable channel a release belongs to
+fn narrowest_channel(release: &GitHubRelease) -> ReleaseChannel {
+    [ReleaseChannel::Release, ReleaseChannel::Beta]
+        .into_iter()
+        .find(|&channel| channel_version(release, channel).is_some())
+        .unwrap_or(ReleaseChannel::Alpha)
+}
+
+/// Parse a release's version if the release belongs to the given channel
+fn channel_version(release: &GitHubRelease, channel: ReleaseChannel) -> Option<Version> {
+    if release.draft {
+        return None;
+    }
+
+    let version = Version::parse(release.tag_name.trim_start_matches('v')).ok()?;
+
+    let matches = match channel {
+        ReleaseChannel::Release => {
+            // Must not be marked as prerelease by GitHub AND have no semver pre-release
+            !release.prerelease && version.pre.is_empty()
+        }
+        ReleaseChannel::Beta => {
+            if release.prerelease {
+                // For GitHub prereleases, must be beta or rc
+                let pre_str = version.pre.to_string().to_lowercase();
+                pre_str.contains("beta") || pre_str.contains("rc")
+            } else {
+                // Stable releases match beta channel
+                true
             }
         }
+        ReleaseChannel::Alpha => {
+            // All versions match alpha channel
+            true
+        }
+    };
 
-        let mut extracted_bytes: u64 = 0;
+    matches.then_some(version)
+}
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let file_size = file.size();
-            let outpath = match file.enclosed_name() {
-                Some(path) => extract_to.join(path),
-                None => continue,
-            };
+/// First semantic version in a `--version` style output, e.g. "myapp v1.4.2 (abc123)"
+fn parse_reported_version(output: &str) -> Option<Version> {
+    output
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')'))
+        .find_map(|word| Version::parse(word.trim_start_matches(['v', 'V'])).ok())
+}
+
+/// Whether `release` was published at least `min_age` before `now`
+///
+/// Releases without a publish date never satisfy a minimum age.
+fn old_enough(release: &GitHubRelease, min_age: Option<Duration>, now: chrono::DateTime<chrono::Utc>) -> bool {
+    let Some(min_age) = min_age else {
+        return true;
+    };
+
+    release.published_at.is_some_and(|published_at| {
+        now.signed_duration_since(published_at)
+            .to_std()
+            .is_ok_and(|age| age >= min_age)
+    })
+}
+
+/// Run `exe_path` with the whitespace-separated arguments in `command`, failing with `CommandError`
+/// on a non-zero exit or timeout
+fn run_verify_command(
+    exe_path: &std::path::Path,
+    command: &str,
+    working_dir: &std::path::Path,
+    env: &HashMap<String, String>,
+) -> Result<()> {
+    command::run_command_with(
+        std::process::Command::new(exe_path)
+            .args(command.split_whitespace())
+            .current_dir(working_dir)
+            .envs(env),
+        command::COMMAND_TIMEOUT,
+        0,
+    )?;
+
+    Ok(())
+}
+
+/// File recording which asset a partial download at `dest_path` belongs to, so it can be resumed
+fn resume_marker_path(dest_path: &std::path::Path) -> PathBuf {
+    let mut marker_path = dest_path.as_os_str().to_os_string();
+    marker_path.push(".resume");
+    PathBuf::from(marker_path)
+}
+
+/// Parse the configuration URL `field`, which must be an absolute http or https URL
+fn parse_base_url(field: &str, url: &str) -> Result<reqwest::Url> {
+    let parsed = reqwest::Url::parse(url.trim()).context(format!("{} '{}' is not a valid URL", field, url))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        anyhow::bail!("{} '{}' must be an http or https URL with a host", field, url);
+    }
+    Ok(parsed)
+}
+
+/// Refuse an asset whose name says it's built for another operating system than `arch`, the
+/// last check before something clearly unrunnable is installed
+fn ensure_native(asset: GitHubAsset, arch: &Architecture) -> Result<GitHubAsset, InstallError> {
+    if arch.is_foreign(&asset.name) {
+        return Err(error::AssetNotFound(format!(
+            "Asset '{}' is built for another operating system than this platform ({:?})",
+            asset.name,
+            arch
+        )).into());
+    }
+    Ok(asset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_architecture_detect() {
+        let arch = Architecture::detect();
+        assert!(arch.is_ok());
+    }
+
+    #[test]
+    fn test_architecture_patterns() {
+        let arch = Architecture::WindowsX64;
+        let patterns = arch.asset_patterns();
+        assert!(patterns.contains(&"windows"));
+        assert!(patterns.contains(&"x64"));
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        )
+        .service_display_name("My Application".to_string())
+        .service_description("A test application".to_string())
+        .binary_name("myapp-bin".to_string())
+        .publisher("My Company".to_string());
+
+        assert_eq!(config.get_display_name(), "My Application");
+        assert_eq!(config.get_description(), "A test application");
+        assert_eq!(config.binary_name, Some("myapp-bin".to_string()));
+        assert_eq!(config.get_publisher(), "My Company");
+    }
+
+    #[test]
+    fn test_service_account_password_is_not_kept() {
+        let account = ServiceAccount::User {
+            name: r".\panel".to_string(),
+            password: Some("hunter2".to_string()),
+        };
+        let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string())
+            .service_account(account.clone());
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(!format!("{:?}", account).contains("hunter2"));
+
+        let loaded: InstallationConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.service_account, Some(ServiceAccount::User { name: r".\panel".to_string(), password: None }));
+        assert_eq!(ServiceAccount::LocalService.account_name(), Some(r"NT AUTHORITY\LocalService"));
+        assert_eq!(ServiceAccount::LocalSystem.account_name(), None);
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        );
+
+        assert_eq!(config.get_display_name(), "myapp");
+        assert_eq!(config.get_description(), "myapp Service");
+        assert_eq!(config.get_publisher(), "owner");
+        assert_eq!(config.get_working_directory(), &PathBuf::from("/opt/myapp"));
+        assert_eq!(config.get_temp_dir(), PathBuf::from("/opt/myapp.oim-tmp"));
+        assert_eq!(config.temp_dir(PathBuf::from("/scratch")).get_temp_dir(), PathBuf::from("/scratch/oim-myapp"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_custom_base_urls() {
+        let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string());
+        let manager = InstallationManager::new(config.clone());
+        assert_eq!(manager.api_url("repos/owner/repo/releases").unwrap(), "https://api.github.com/repos/owner/repo/releases");
+
+        let asset = GitHubAsset {
+            name: "app.zip".to_string(),
+            browser_download_url: "https://github.com/owner/repo/releases/download/v1.0.0/app.zip".to_string(),
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        assert_eq!(manager.download_url(&asset).unwrap(), asset.browser_download_url);
+
+        let manager = InstallationManager::new(
+            config
+                .clone()
+                .api_base_url("https://github.example.com/api/v3/".to_string())
+                .download_base_url("https://mirror.example.com/gh".to_string()),
+        );
+        assert_eq!(
+            manager.api_url("repos/owner/repo/releases").unwrap(),
+            "https://github.example.com/api/v3/repos/owner/repo/releases"
+        );
+        assert_eq!(
+            manager.download_url(&asset).unwrap(),
+            "https://mirror.example.com/gh/owner/repo/releases/download/v1.0.0/app.zip"
+        );
+
+        assert!(config.clone().api_base_url("github.example.com".to_string()).validate_urls().is_err());
+        assert!(config.download_base_url("ftp://mirror.example.com".to_string()).validate_urls().is_err());
+    }
+
+    #[test]
+    fn test_launch_runs_configured_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("bin")).unwrap();
+        for name in ["helper", "bin/server"] {
+            let script = dir.path().join(name);
+            std::fs::write(&script, format!("#!/bin/sh\necho \"{} $GREETING\" > \"$PWD/launched\"\n", name)).unwrap();
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let config = InstallationConfig::new(dir.path().to_path_buf(), "owner/repo".to_string(), "app".to_string())
+            .binary_name("server".to_string())
+            .service_env_var("GREETING".to_string(), "hello".to_string());
+        let manager = InstallationManager::new(config);
+
+        let status = manager.launch().unwrap().wait().unwrap();
+        assert!(status.success());
+        assert_eq!(std::fs::read_to_string(dir.path().join("launched")).unwrap(), "bin/server hello\n");
+
+        let empty = tempfile::tempdir().unwrap();
+        let manager = InstallationManager::with_defaults(empty.path().to_path_buf(), "owner/repo".to_string(), "app".to_string());
+        assert!(manager.launch().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_verify_command() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("app");
+        std::fs::write(&script, "#!/bin/sh\nif [ \"$1\" = \"--self-check\" ]; then exit 0; fi\necho \"doctor: broken\"\nexit 3\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let env = HashMap::new();
+
+        assert!(run_verify_command(&script, "--self-check", dir.path(), &env).is_ok());
+
+        let err = run_verify_command(&script, "doctor --quick", dir.path(), &env).unwrap_err();
+        assert!(err.to_string().contains("doctor: broken"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_verify_command_receives_hook_environment() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("app");
+        std::fs::write(&script, "#!/bin/sh\n[ \"$OIM_VERSION\" = \"1.2.3\" ] && [ \"$OIM_SERVICE_NAME\" = \"myapp\" ] && [ \"$DATA_DIR\" = \"/srv/data\" ]\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = InstallationConfig::new(dir.path().to_path_buf(), "owner/repo".to_string(), "myapp".to_string())
+            .service_env_var("DATA_DIR".to_string(), "/srv/data".to_string())
+            .service_env_var("OIM_VERSION".to_string(), "overridden".to_string());
+        let env = config.hook_environment("v1.2.3");
+
+        assert_eq!(env["OIM_INSTALL_PATH"], dir.path().to_string_lossy());
+        assert_eq!(env["OIM_WORKING_DIR"], dir.path().to_string_lossy());
+        assert!(run_verify_command(&script, "", dir.path(), &env).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_install_from_file_rejects_unsupported_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.rar");
+        std::fs::write(&archive_path, b"not extractable").unwrap();
+
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string());
+        let mut manager = InstallationManager::new(config);
+        let err = manager.install_from_file(&archive_path, Version::new(1, 2, 3)).await.unwrap_err();
+        assert!(matches!(err, InstallError::UnsupportedArchive(_)));
+        assert!(!dir.path().join("app").exists());
+        assert!(manager.current_version().is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_pre_install_hook_aborts_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.tar.gz");
+        std::fs::write(&archive_path, b"never extracted").unwrap();
+
+        let hook = ["sh", "-c", "echo \"stopping $OIM_SERVICE_NAME\"; exit 3"];
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "oim-hook-test".to_string())
+            .version_file_dir(dir.path().join("state").to_string_lossy().into_owned())
+            .pre_install_hook(hook.iter().map(|arg| arg.to_string()).collect());
+        let mut manager = InstallationManager::new(config);
+
+        let err = manager.install_from_file(&archive_path, Version::new(1, 2, 3)).await.unwrap_err();
+        let command_error = err.downcast_ref::<CommandError>().unwrap();
+        assert_eq!(command_error.stdout.trim(), "stopping oim-hook-test");
+        assert!(err.to_string().contains("pre-install hook"));
+        assert!(!dir.path().join("app").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_pre_install_hook_is_logged_for_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "oim-hook-test".to_string())
+            .version_file_dir(dir.path().join("state").to_string_lossy().into_owned())
+            .pre_install_hook(vec!["false".to_string()]);
+        let mut manager = InstallationManager::new(config);
+
+        let release = GitHubRelease {
+            tag_name: "v1.1.0".to_string(),
+            name: "v1.1.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
+        let asset = GitHubAsset {
+            name: "app.zip".to_string(),
+            browser_download_url: "https://github.com/owner/repo/releases/download/v1.1.0/app.zip".to_string(),
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let requested_lock = manager.requested_channel_lock(ReleaseChannel::Release);
+        let err = manager.update_to(release, asset, requested_lock, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.to_string().contains("pre-install hook"));
+
+        let log = manager.last_install_log().unwrap();
+        assert!(matches!(&log[0].event, LogEvent::Started { operation } if operation == "update"));
+        assert!(matches!(log.last().unwrap().event, LogEvent::Finished { success: false, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_failed_install_from_file_removes_created_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        std::fs::write(&archive_path, b"not a zip archive").unwrap();
+
+        // The install log lives inside install_path, as it does where the working directory is install_path
+        let install_path = dir.path().join("app");
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .version_file_dir(install_path.to_string_lossy().into_owned());
+        let mut manager = InstallationManager::new(config);
+
+        let err = manager.install_from_file(&archive_path, Version::new(1, 2, 3)).await.unwrap_err();
+        assert!(matches!(err, InstallError::UnsupportedArchive(_)));
+        assert!(!install_path.exists());
+    }
+
+    /// Serve `body` to every request, announcing `content_length` bytes (more than the body
+    /// makes the connection drop mid-download)
+    fn serve(body: Vec<u8>, content_length: usize) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/app.zip", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut byte = [0; 1];
+                while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap_or(0) == 1 {
+                    request.push(byte[0]);
+                }
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", content_length);
+                let _ = stream.write_all(&body);
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_fetch_asset_verifies_digest_without_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = b"tampered archive".to_vec();
+        let asset = GitHubAsset {
+            name: "app.zip".to_string(),
+            browser_download_url: serve(content.clone(), content.len()),
+            size: content.len() as u64,
+            digest: Some(format!("sha256:{}", "0".repeat(64))),
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let manager = InstallationManager::with_defaults(dir.path().join("app"), "owner/repo".to_string(), "app".to_string());
+
+        let dest = dir.path().join("app.zip");
+        let err = manager.fetch_asset(&asset, &dest, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.downcast_ref::<ChecksumMismatch>().is_some());
+        assert!(!dest.exists());
+    }
+
+    /// Publish `asset` as the only asset of release v1.0.0, returning the URL of an API serving
+    /// the releases list
+    fn serve_release(asset: GitHubAsset) -> String {
+        let release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![asset],
+        };
+        let releases = serde_json::to_vec(&[release]).unwrap();
+        serve(releases.clone(), releases.len()).trim_end_matches("app.zip").to_string()
+    }
+
+    /// Name of an asset built for this platform
+    fn platform_asset_name(extension: &str) -> String {
+        format!("app-{}-{}.{}", std::env::consts::OS, std::env::consts::ARCH, extension)
+    }
+
+    /// Publish `content` as this platform's asset of release v1.0.0, returning the URL of an API
+    /// serving the releases list and a download cache the asset is served from
+    fn serve_cached_release(dir: &std::path::Path, content: &[u8], extension: &str) -> (String, PathBuf) {
+        use sha2::{Digest, Sha256};
+
+        let sha256: String = Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect();
+        let asset_name = platform_asset_name(extension);
+        let cache_dir = dir.join("cache");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join(format!("{}-{}", sha256, asset_name)), content).unwrap();
+
+        let api = serve_release(GitHubAsset {
+            name: asset_name,
+            browser_download_url: "http://127.0.0.1:9/app.zip".to_string(),
+            size: content.len() as u64,
+            digest: Some(format!("sha256:{}", sha256)),
+            content_type: None,
+            state: None,
+            updated_at: None,
+        });
+        (api, cache_dir)
+    }
+
+    #[tokio::test]
+    async fn test_failed_extraction_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        // Served from the download cache, so only the releases list is fetched
+        let (api, cache_dir) = serve_cached_release(dir.path(), b"not a zip archive", "zip");
+
+        // The install log lives inside install_path, as it does where the working directory is install_path
+        let install_path = dir.path().join("app");
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .api_base_url(api)
+            .version_file_dir(install_path.to_string_lossy().into_owned())
+            .cache_dir(cache_dir)
+            .temp_dir(dir.path().join("tmp"));
+        let mut manager = InstallationManager::new(config);
+
+        let err = manager.install(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap_err();
+        assert!(matches!(err, InstallError::UnsupportedArchive(_)));
+        assert!(!dir.path().join("tmp/oim-app").exists());
+        assert!(!install_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_is_kept_for_resume() {
+        let content = vec![b'a'; 4096];
+        let asset = GitHubAsset {
+            name: platform_asset_name("tar.gz"),
+            browser_download_url: serve(content.clone(), content.len() * 2),
+            size: content.len() as u64 * 2,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let download_path = format!("tmp/oim-app/{}", asset.name);
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string())
+            .api_base_url(serve_release(asset))
+            .version_file_dir(dir.path().join("state").to_string_lossy().into_owned())
+            .temp_dir(dir.path().join("tmp"))
+            .retry_count(0);
+        let mut manager = InstallationManager::new(config);
+
+        // The connection drops halfway, so the partial download stays for the next install to resume
+        let err = manager.install(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.is_retryable());
+        let partial = dir.path().join(&download_path);
+        assert_eq!(std::fs::read(&partial).unwrap(), content);
+        assert!(resume_marker_path(&partial).exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_install_retries_after_failed_verify() {
+        let script = b"#!/bin/sh\nexit 1\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(script.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        builder.append_data(&mut header, "bin/myapp", &script[..]).unwrap();
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let (api, cache_dir) = serve_cached_release(dir.path(), &archive, "tar.gz");
+        let install_path = dir.path().join("app");
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "oim-test-retry".to_string())
+            .api_base_url(api)
+            .version_file_dir(dir.path().join("state").to_string_lossy().into_owned())
+            .cache_dir(cache_dir)
+            .binary_name("myapp".to_string())
+            .post_install_verify_command("--check".to_string());
+        let mut manager = InstallationManager::new(config);
+
+        // The verify command fails after extraction, leaving the files without a recorded version
+        let err = manager.install(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.downcast_ref::<CommandError>().is_some());
+        assert!(install_path.join("bin/myapp").is_file());
+        assert_eq!(manager.installation_origin().unwrap(), InstallationOrigin::Managed);
+
+        // Installing again replaces them rather than refusing them as someone else's
+        let err = manager.install(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap_err();
+        assert!(err.downcast_ref::<ForeignInstallation>().is_none());
+        assert!(err.downcast_ref::<CommandError>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_update_checks_are_debounced() {
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        )
+        .update_check_interval(Duration::from_secs(60));
+        let mut manager = InstallationManager::new(config);
+
+        // A clone mid-operation makes checks and updates no-ops
+        let clone = manager.clone();
+        let guard = clone.begin_operation().unwrap();
+        assert_eq!(manager.check_for_updates(ReleaseChannel::Release).await.unwrap(), UpdateStatus::Busy);
+        assert_eq!(
+            manager.update(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap(),
+            UpdateStatus::Busy
+        );
+        drop(guard);
+
+        // A check within the interval is reused without touching the network
+        *manager.last_update_check.lock().unwrap() = Some((Instant::now(), true));
+        let status = manager.check_for_updates(ReleaseChannel::Release).await.unwrap();
+        assert_eq!(status, UpdateStatus::CheckedRecently { update_available: true });
+        assert!(status.update_available());
+
+        // An update right after a check that found nothing is skipped
+        manager.is_installed = true;
+        *manager.last_update_check.lock().unwrap() = Some((Instant::now(), false));
+        assert_eq!(
+            manager.update(ReleaseChannel::Release, &CancellationToken::new()).await.unwrap(),
+            UpdateStatus::CheckedRecently { update_available: false }
+        );
+    }
+
+    #[test]
+    fn test_installation_manager_creation() {
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        );
+
+        let manager = InstallationManager::new(config);
+        assert!(!manager.is_installed());
+        assert!(manager.current_version().is_none());
+        assert!(manager.latest_version().is_none());
+    }
+
+    #[test]
+    fn test_progress_channel_capacity() {
+        let config = InstallationConfig::new(PathBuf::from("/opt/myapp"), "owner/repo".to_string(), "myapp".to_string())
+            .progress_channel_capacity(2);
+        let manager = InstallationManager::new(config);
+        let mut receiver = manager.subscribe();
+        for _ in 0..3 {
+            manager.broadcast_progress(State::Downloading, 0.5);
+        }
+        assert!(matches!(receiver.try_recv(), Err(broadcast::error::TryRecvError::Lagged(1))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_detect_installation() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string())
+            .version_file_dir(dir.path().to_string_lossy().into_owned());
+        let mut manager = InstallationManager::new(config.clone());
+        assert!(!manager.detect_installation().unwrap());
+
+        nix::set_installed_version(&config, "v1.2.3").unwrap();
+        assert!(manager.detect_installation().unwrap());
+        assert!(manager.is_installed());
+        assert_eq!(manager.current_version(), Some(&Version::new(1, 2, 3)));
+    }
+
+    #[test]
+    fn test_installation_manager_with_defaults() {
+        let manager = InstallationManager::with_defaults(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        );
+
+        assert_eq!(manager.config().service_name, "myapp");
+        assert_eq!(manager.config().github_repo, "owner/repo");
+    }
+
+    #[test]
+    fn test_render_asset_template() {
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        )
+        .asset_template("panel-{version}-{os}-{arch}.{ext}".to_string());
+
+        let release = GitHubRelease {
+            tag_name: "v1.4.2".to_string(),
+            name: "Release 1.4.2".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
+
+        assert_eq!(
+            config.render_asset_template(&release, &Architecture::LinuxArm64).as_deref(),
+            Some("panel-1.4.2-linux-aarch64.tar.gz")
+        );
+        assert_eq!(
+            config.render_asset_template(&release, &Architecture::WindowsX64).as_deref(),
+            Some("panel-1.4.2-windows-x86_64.zip")
+        );
+    }
+
+    #[test]
+    fn test_select_asset_with_template() {
+        let arch = Architecture::detect().unwrap();
+        let expected = format!("panel-1.0.0-{}-{}.{}", arch.os_name(), arch.arch_name(), arch.archive_extension());
+
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        )
+        .asset_template("panel-{version}-{os}-{arch}.{ext}".to_string());
+        let manager = InstallationManager::new(config);
+
+        let mut release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![
+                GitHubAsset {
+                    name: "panel-linux-windows-x64-arm64.zip".to_string(),
+                    browser_download_url: "https://example.com/decoy".to_string(),
+                    size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
+                },
+                GitHubAsset {
+                    name: expected.clone(),
+                    browser_download_url: format!("https://example.com/{}", expected),
+                    size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
+                },
+            ],
+        };
 
-            if file.name().ends_with('/') {
-                std::fs::create_dir_all(&outpath)?;
-            } else {
-                if let Some(p) = outpath.parent() && !p.exists() {
-                    std::fs::create_dir_all(p)?;
-                }
-                let mut outfile = std::fs::File::create(&outpath)?;
-                std::io::copy(&mut file, &mut outfile)?;
-            }
+        assert_eq!(manager.select_asset(&release).unwrap().name, expected);
 
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Some(mode) = file.unix_mode() {
-                    std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode))?;
-                }
-            }
+        release.assets.pop();
+        let err = manager.select_asset(&release).unwrap_err();
+        assert!(err.to_string().contains(&expected));
+    }
 
-            // Report progress based on bytes
-            extracted_bytes += file_size;
-            let progress = if total_bytes > 0 {
-                extracted_bytes as f32 / total_bytes as f32
-            } else {
-                1.0
-            };
-            self.broadcast_progress(State::Extracting, progress);
-        }
+    #[test]
+    fn test_extract_zip_over_size_cap_fails() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        writer.start_file("bomb.bin", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(&[0u8; 64 * 1024]).unwrap();
+        writer.finish().unwrap();
+
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string())
+            .max_uncompressed_size(1024);
+        let manager = InstallationManager::new(config);
 
-        Ok(())
+        let err = manager.extract_archive(&archive_path, &dir.path().join("app")).unwrap_err();
+        assert!(err.downcast_ref::<DecompressionLimitExceeded>().is_some());
+        assert!(!dir.path().join("app/bomb.bin").exists());
     }
 
-    /// Install a release from the specified channel
-    pub async fn install(&mut self, channel: ReleaseChannel) -> Result<()> {
-        let release = self.get_latest_release(channel).await?;
-        let asset = self.select_asset(&release)?;
-
-        println!("Installing {} version {}...", self.config.service_name, release.tag_name);
-        println!("Downloading {}...", asset.name);
+    #[test]
+    fn test_extract_skip_existing() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        for name in ["server", "config.toml"] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(b"new").unwrap();
+        }
+        writer.finish().unwrap();
 
-        // Create temporary download directory
-        let temp_dir = std::env::temp_dir().join(format!("oim-{}", self.config.service_name));
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        let install_path = dir.path().join("app");
+        std::fs::create_dir(&install_path).unwrap();
+        std::fs::write(install_path.join("config.toml"), b"edited").unwrap();
 
-        let download_path = temp_dir.join(&asset.name);
-        self.download_asset(&asset, &download_path).await?;
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .extract_policy(ExtractPolicy::SkipExisting);
+        InstallationManager::new(config).extract_archive(&archive_path, &install_path).unwrap();
+        assert_eq!(std::fs::read(install_path.join("server")).unwrap(), b"new");
+        assert_eq!(std::fs::read(install_path.join("config.toml")).unwrap(), b"edited");
+    }
 
-        println!("Extracting to {}...", self.config.install_path.display());
-        self.extract_archive(&download_path, &self.config.install_path)?;
+    #[test]
+    fn test_extract_resumes_interrupted_extraction() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        for name in ["server", "config.toml"] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(b"new").unwrap();
+        }
+        writer.finish().unwrap();
+
+        // An earlier run wrote the server before it was cancelled
+        let install_path = dir.path().join("app");
+        std::fs::create_dir(&install_path).unwrap();
+        let mut state = extract_state::ExtractState::open(&install_path, &archive_path).unwrap();
+        std::fs::write(install_path.join("server"), b"kept").unwrap();
+        state.record(&install_path.join("server")).unwrap();
+        drop(state);
+
+        let manager = InstallationManager::with_defaults(install_path.clone(), "owner/repo".to_string(), "app".to_string());
+        manager.extract_archive(&archive_path, &install_path).unwrap();
+        assert_eq!(std::fs::read(install_path.join("server")).unwrap(), b"kept");
+        assert_eq!(std::fs::read(install_path.join("config.toml")).unwrap(), b"new");
+        assert!(!extract_state::is_pending(&install_path));
+    }
 
-        // Set directory permissions on Windows
-        #[cfg(target_os = "windows")]
-        {
-            win::set_directory_permissions(&self.config.install_path)
-                .context("Failed to set directory permissions")?;
+    #[test]
+    fn test_failed_extraction_is_not_resumed() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        for name in ["server", "../evil"] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(b"new").unwrap();
         }
+        writer.finish().unwrap();
 
-        // Platform-specific installation
-        self.broadcast_progress(State::Installing, 0.0);
+        let install_path = dir.path().join("app");
+        std::fs::create_dir(&install_path).unwrap();
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .extract_policy(ExtractPolicy::CleanThenExtract);
+        let manager = InstallationManager::new(config);
+        let err = manager.extract_archive(&archive_path, &install_path).unwrap_err();
+        assert!(err.downcast_ref::<UnsafeArchiveEntry>().is_some());
+        assert!(!extract_state::is_pending(&install_path));
 
-        #[cfg(target_os = "windows")]
-        {
-            win::install_service(&self.config, &release.tag_name)?;
-        }
+        // The next install cleans up what the failed one left
+        manager.clean_install_dir(false).unwrap();
+        assert!(!install_path.join("server").exists());
+    }
 
-        #[cfg(target_os = "linux")]
-        {
-            nix::install_service(&self.config, &release.tag_name)?;
+    #[test]
+    fn test_clean_install_dir_keeps_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir(&install_path).unwrap();
+
+        // The records sit inside install_path, as they do where the working directory is install_path
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .version_file_dir(install_path.to_string_lossy().into_owned())
+            .extract_policy(ExtractPolicy::CleanThenExtract);
+        for path in config.metadata_paths() {
+            std::fs::write(path, b"{}").unwrap();
         }
+        std::fs::write(install_path.join("server"), b"old").unwrap();
 
-        self.broadcast_progress(State::Installing, 1.0);
+        let manager = InstallationManager::new(config.clone());
+        manager.clean_install_dir(true).unwrap();
+        assert!(!install_path.join("server").exists());
+        assert!(config.get_release_cache_path().exists());
+        assert!(config.metadata_paths().iter().all(|path| path.exists()));
+    }
 
-        // Update internal state
-        let version_str = release.tag_name.trim_start_matches('v');
-        self.current_version = Some(Version::parse(version_str)?);
-        self.is_installed = true;
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_zip_symlinks() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = zip::write::SimpleFileOptions::default();
+        let archive_path = dir.path().join("app.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        writer.start_file("lib/app-1.2.3", options.unix_permissions(0o755)).unwrap();
+        writer.write_all(b"#!/bin/sh").unwrap();
+        writer.add_symlink("bin/app", "../lib/app-1.2.3", options).unwrap();
+        writer.finish().unwrap();
+
+        let manager = InstallationManager::new(InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string()));
+        let extract_to = dir.path().join("app");
+        manager.extract_archive(&archive_path, &extract_to).unwrap();
+        assert!(extract_to.join("bin/app").symlink_metadata().unwrap().is_symlink());
+        assert_eq!(std::fs::read(extract_to.join("bin/app")).unwrap(), b"#!/bin/sh");
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        writer.add_symlink("escape", "../../outside", options).unwrap();
+        writer.finish().unwrap();
+        let err = manager.extract_archive(&archive_path, &dir.path().join("escaped")).unwrap_err();
+        assert!(matches!(err, InstallError::UnsupportedArchive(_)));
+        assert!(!dir.path().join("escaped/escape").exists());
+    }
 
-        // Cleanup
-        tokio::fs::remove_file(download_path).await?;
+    #[test]
+    fn test_extract_compressed_tar_round_trip() {
+        use std::io::Write;
+
+        fn build_tar<W: Write>(writer: W) -> W {
+            let mut builder = tar::Builder::new(writer);
+            for (path, contents) in [("app/myapp", &b"#!/bin/sh"[..]), ("app/config/settings.json", &b"{}"[..])] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o755);
+                header.set_cksum();
+                builder.append_data(&mut header, path, contents).unwrap();
+            }
+            builder.into_inner().unwrap()
+        }
 
-        println!("Installation complete!");
-        Ok(())
+        let dir = tempfile::tempdir().unwrap();
+        let create = |name: &str| std::fs::File::create(dir.path().join(name)).unwrap();
+        build_tar(xz2::write::XzEncoder::new(create("app.tar.xz"), 6)).finish().unwrap();
+        build_tar(bzip2::write::BzEncoder::new(create("app.tar.bz2"), bzip2::Compression::default())).finish().unwrap();
+
+        let manager = InstallationManager::with_defaults(dir.path().join("app"), "owner/repo".to_string(), "app".to_string());
+        for name in ["app.tar.xz", "app.tar.bz2"] {
+            let extract_to = dir.path().join(format!("{}-out", name));
+            manager.extract_archive(&dir.path().join(name), &extract_to).unwrap();
+            assert_eq!(std::fs::read(extract_to.join("app/myapp")).unwrap(), b"#!/bin/sh");
+            assert_eq!(std::fs::read(extract_to.join("app/config/settings.json")).unwrap(), b"{}");
+        }
     }
 
-    /// Repair an existing installation (reinstall files without deleting existing ones)
-    /// This preserves configuration files and user data while updating application files
-    pub async fn repair(&mut self, channel: ReleaseChannel) -> Result<()> {
-        println!("Repairing {} installation...", self.config.service_name);
-
-        let release = self.get_latest_release(channel).await?;
-        let asset = self.select_asset(&release)?;
+    #[test]
+    fn test_extract_single_file_assets() {
+        use std::io::Write;
 
-        println!("Downloading {} version {}...", self.config.service_name, release.tag_name);
-        println!("Downloading {}...", asset.name);
+        let dir = tempfile::tempdir().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(dir.path().join("myapp-linux-x64.gz")).unwrap(), flate2::Compression::default());
+        encoder.write_all(b"\x7fELF gzipped").unwrap();
+        encoder.finish().unwrap();
+        std::fs::write(dir.path().join("myapp-linux-x64"), b"\x7fELF bare").unwrap();
 
-        // Create temporary download directory
-        let temp_dir = std::env::temp_dir().join(format!("oim-{}", self.config.service_name));
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "myapp".to_string())
+            .binary_name("server".to_string());
+        let manager = InstallationManager::new(config.clone());
+        let extract_to = dir.path().join("app");
 
-        let download_path = temp_dir.join(&asset.name);
-        self.download_asset(&asset, &download_path).await?;
+        manager.extract_archive(&dir.path().join("myapp-linux-x64.gz"), &extract_to).unwrap();
+        assert_eq!(std::fs::read(extract_to.join("myapp-linux-x64")).unwrap(), b"\x7fELF gzipped");
 
-        println!("Extracting to {}... (existing files will be preserved)", self.config.install_path.display());
-        // Extract overwrites files but doesn't delete existing ones
-        self.extract_archive(&download_path, &self.config.install_path)?;
+        manager.extract_archive(&dir.path().join("myapp-linux-x64"), &extract_to).unwrap();
+        let installed = extract_to.join(config.get_binary_file_name());
+        assert_eq!(std::fs::read(&installed).unwrap(), b"\x7fELF bare");
 
-        // Set directory permissions on Windows
-        #[cfg(target_os = "windows")]
+        #[cfg(unix)]
         {
-            win::set_directory_permissions(&self.config.install_path)
-                .context("Failed to set directory permissions")?;
+            use std::os::unix::fs::PermissionsExt;
+            assert_ne!(std::fs::metadata(&installed).unwrap().permissions().mode() & 0o111, 0);
+        }
+    }
+
+    #[test]
+    fn test_extract_strips_single_root() {
+        use std::io::Write;
+
+        fn build_archives(dir: &std::path::Path, entries: &[&str]) {
+            let encoder = flate2::write::GzEncoder::new(std::fs::File::create(dir.join("app.tar.gz")).unwrap(), flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(dir.join("app.zip")).unwrap());
+            for path in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(path.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, path, path.as_bytes()).unwrap();
+                zip.start_file(*path, zip::write::SimpleFileOptions::default()).unwrap();
+                zip.write_all(path.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+            zip.finish().unwrap();
         }
 
-        // Update version in registry/config without reinstalling service
-        self.broadcast_progress(State::Installing, 0.5);
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string())
+            .strip_single_root(true);
+        let manager = InstallationManager::new(config.clone());
+
+        // Everything inside app-1.2.3/: the folder is dropped
+        build_archives(dir.path(), &["app-1.2.3/bin/app", "app-1.2.3/README.md"]);
+        for name in ["app.tar.gz", "app.zip"] {
+            let extract_to = dir.path().join(format!("{}-single", name));
+            manager.extract_archive(&dir.path().join(name), &extract_to).unwrap();
+            assert_eq!(std::fs::read(extract_to.join("bin/app")).unwrap(), b"app-1.2.3/bin/app");
+            assert!(extract_to.join("README.md").exists());
+            assert!(!extract_to.join("app-1.2.3").exists());
+        }
 
-        #[cfg(target_os = "windows")]
-        {
-            win::set_installed_version(&self.config, &release.tag_name)?;
+        // A file next to the folder: nothing is stripped
+        build_archives(dir.path(), &["app-1.2.3/bin/app", "LICENSE"]);
+        for name in ["app.tar.gz", "app.zip"] {
+            let extract_to = dir.path().join(format!("{}-mixed", name));
+            manager.extract_archive(&dir.path().join(name), &extract_to).unwrap();
+            assert!(extract_to.join("app-1.2.3/bin/app").exists());
+            assert!(extract_to.join("LICENSE").exists());
         }
 
-        #[cfg(target_os = "linux")]
-        {
-            nix::set_installed_version(&self.config, &release.tag_name)?;
+        // An explicit count applies regardless, and entries used up by it are skipped
+        let manager = InstallationManager::new(config.strip_components(2));
+        for name in ["app.tar.gz", "app.zip"] {
+            let extract_to = dir.path().join(format!("{}-two", name));
+            manager.extract_archive(&dir.path().join(name), &extract_to).unwrap();
+            assert_eq!(std::fs::read_dir(&extract_to).unwrap().count(), 1);
+            assert!(extract_to.join("app").exists());
         }
+    }
 
-        self.broadcast_progress(State::Installing, 1.0);
+    #[test]
+    fn test_extract_tar_gz_rejects_path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.tar.gz");
+
+        // tar::Builder refuses to write `..` paths, so set the name bytes directly
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(&archive_path).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.as_gnu_mut().unwrap().name[..7].copy_from_slice(b"../evil");
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, &b"evil"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string());
+        let manager = InstallationManager::new(config);
 
-        // Update internal state
-        let version_str = release.tag_name.trim_start_matches('v');
-        self.current_version = Some(Version::parse(version_str)?);
-        self.is_installed = true;
+        let err = manager.extract_archive(&archive_path, &dir.path().join("app")).unwrap_err();
+        assert!(err.downcast_ref::<UnsafeArchiveEntry>().is_some());
+        assert!(!dir.path().join("evil").exists());
+    }
 
-        // Cleanup
-        tokio::fs::remove_file(download_path).await?;
+    #[test]
+    fn test_parse_reported_version() {
+        assert_eq!(parse_reported_version("myapp v1.4.2 (abc123)"), Some(Version::new(1, 4, 2)));
+        assert_eq!(parse_reported_version("1.5.0\n"), Some(Version::new(1, 5, 0)));
+        assert_eq!(
+            parse_reported_version("server version 2.0.0-beta.1, built today"),
+            Some(Version::parse("2.0.0-beta.1").unwrap())
+        );
+        assert_eq!(parse_reported_version("usage: myapp [options]"), None);
+    }
 
-        println!("Repair complete!");
-        Ok(())
+    #[test]
+    fn test_old_enough() {
+        let now = chrono::Utc::now();
+        let published = |days_ago: Option<i64>| GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "v1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: days_ago.map(|days| now - chrono::Duration::days(days)),
+            assets: vec![],
+        };
+        let three_days = Some(Duration::from_secs(3 * 24 * 60 * 60));
+
+        assert!(old_enough(&published(Some(0)), None, now));
+        assert!(old_enough(&published(None), None, now));
+        assert!(old_enough(&published(Some(7)), three_days, now));
+        assert!(old_enough(&published(Some(3)), three_days, now));
+        assert!(!old_enough(&published(Some(1)), three_days, now));
+        assert!(!old_enough(&published(Some(-1)), three_days, now));
+        assert!(!old_enough(&published(None), three_days, now));
     }
 
-    /// Update an existing installation on the specified channel
-    pub async fn update(&mut self, channel: ReleaseChannel) -> Result<()> {
-        if !self.is_installed {
-            anyhow::bail!("No installation found. Use install() instead.");
-        }
+    #[test]
+    fn test_newest_release_with_asset_respects_min_age() {
+        let arch = Architecture::detect().unwrap();
+        let config = InstallationConfig::new(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        )
+        .min_release_age(Duration::from_secs(2 * 24 * 60 * 60));
+        let manager = InstallationManager::new(config);
 
-        let has_update = self.check_for_updates(channel).await?;
-        if !has_update {
-            println!("Already up to date!");
-            return Ok(());
-        }
+        let now = chrono::Utc::now();
+        let release = |tag: &str, days_ago: i64| {
+            let name = format!("myapp-{}-{}.tar.gz", arch.os_name(), arch.arch_name());
+            GitHubRelease {
+                tag_name: tag.to_string(),
+                name: tag.to_string(),
+                body: String::new(),
+                prerelease: false,
+                draft: false,
+                published_at: Some(now - chrono::Duration::days(days_ago)),
+                assets: vec![GitHubAsset {
+                    browser_download_url: format!("https://example.com/{}/{}", tag, name),
+                    name,
+                    size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
+                }],
+            }
+        };
 
-        println!(
-            "Updating from {} to {}...",
-            self.current_version.as_ref().unwrap(),
-            self.latest_version.as_ref().unwrap()
-        );
+        // Newest first, as GitHub returns them
+        let releases = vec![release("v1.2.0", 0), release("v1.1.0", 1), release("v1.0.0", 5)];
+        let (selected, _, version) = manager.newest_release_with_asset(releases, ReleaseChannel::Release).unwrap();
+        assert_eq!(selected.tag_name, "v1.0.0");
+        assert_eq!(version, Version::new(1, 0, 0));
 
-        self.broadcast_progress(State::Updating, 0.0);
+        assert!(manager.newest_release_with_asset(vec![release("v1.2.0", 0)], ReleaseChannel::Release).is_err());
+    }
 
-        // Platform-specific service stop
-        #[cfg(target_os = "windows")]
-        {
-            win::stop_service(&self.config)?;
-        }
+    #[test]
+    fn test_channel_version() {
+        let release = |tag: &str, prerelease: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            body: String::new(),
+            prerelease,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
 
-        #[cfg(target_os = "linux")]
-        {
-            nix::stop_service(&self.config)?;
-        }
+        assert!(channel_version(&release("v1.0.0", false), ReleaseChannel::Release).is_some());
+        assert!(channel_version(&release("v1.1.0-beta.1", true), ReleaseChannel::Release).is_none());
+        assert!(channel_version(&release("v1.1.0-beta.1", true), ReleaseChannel::Beta).is_some());
+        assert!(channel_version(&release("v1.1.0-alpha.1", true), ReleaseChannel::Beta).is_none());
+        assert!(channel_version(&release("v1.1.0-alpha.1", true), ReleaseChannel::Alpha).is_some());
+        assert!(channel_version(&release("nightly", true), ReleaseChannel::Alpha).is_none());
 
-        self.broadcast_progress(State::Updating, 0.2);
+        let draft = GitHubRelease { draft: true, ..release("v2.0.0", false) };
+        assert!(channel_version(&draft, ReleaseChannel::Release).is_none());
+    }
 
-        // Perform installation (which will overwrite existing files)
-        self.install(channel).await?;
+    #[test]
+    fn test_channel_matches_tag_suffix() {
+        let release = |tag: &str, prerelease: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            body: String::new(),
+            prerelease,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
 
-        self.broadcast_progress(State::Updating, 0.8);
+        assert!(Channel::Stable.matches(&release("v1.0.0", false)).is_some());
+        assert!(Channel::Stable.matches(&release("v1.0.0", true)).is_none());
+        assert!(Channel::Beta.matches(&release("v1.1.0-beta.2", true)).is_some());
+        assert!(Channel::Beta.matches(&release("v1.1.0-RC1", true)).is_some());
+        assert!(Channel::Beta.matches(&release("v1.1.0", false)).is_none());
+        assert!(Channel::Beta.matches(&release("v1.1.0-beta.2", false)).is_none());
+        assert!(Channel::Nightly.matches(&release("v1.1.0-nightly.20240611", false)).is_none());
+        assert!(Channel::Beta.matches(&release("v1.1.0-nightly.20240611", true)).is_none());
+        assert!(Channel::Nightly.matches(&release("v1.1.0-nightly.20240611", true)).is_some());
+        assert!(Channel::Nightly.matches(&release("v1.1.0-beta.2", true)).is_none());
+        assert!(Channel::Nightly.matches(&release("nightly", true)).is_none());
+
+        let canary = Channel::Custom(Regex::new(r"-canary\.").unwrap());
+        assert_eq!(canary.matches(&release("v2.0.0-canary.3", true)).unwrap().to_string(), "2.0.0-canary.3");
+        assert!(canary.matches(&release("v2.0.0-beta.3", true)).is_none());
+        assert_eq!(canary.to_string(), r"tags matching /-canary\./");
+    }
 
-        // Platform-specific service start
-        #[cfg(target_os = "windows")]
-        {
-            win::start_service(&self.config)?;
-        }
+    #[test]
+    fn test_plan_for_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("myapp");
+        std::fs::create_dir(&install_path).unwrap();
+
+        let mut manager = InstallationManager::with_defaults(install_path.clone(), "owner/repo".to_string(), "myapp".to_string());
+        manager.current_version = Some(Version::new(1, 0, 0));
+        manager.set_dry_run(true);
+        assert!(manager.is_dry_run());
+
+        let asset = GitHubAsset {
+            browser_download_url: "https://example.com/myapp-linux-x64.tar.xz".to_string(),
+            name: "myapp-linux-x64.tar.xz".to_string(),
+            size: 2048,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let release = GitHubRelease {
+            tag_name: "v1.1.0".to_string(),
+            name: "v1.1.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![asset.clone()],
+        };
 
+        let plan = manager.plan_for(PlannedOperation::Update, &release, &asset);
+        assert_eq!(plan.archive_format, Some(ArchiveFormat::TarXz));
+        assert_eq!(plan.current_version, Some(Version::new(1, 0, 0)));
+        assert_eq!(plan.backup_path, Some(dir.path().join("myapp.bak")));
         #[cfg(target_os = "linux")]
-        {
-            nix::start_service(&self.config)?;
-        }
+        assert_eq!(plan.service_commands.first().map(String::as_str), Some("systemctl stop myapp"));
+        assert!(plan.to_string().starts_with("Update 1.0.0 -> v1.1.0"));
 
-        self.broadcast_progress(State::Updating, 1.0);
+        // A plan writes nothing
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
 
-        println!("Update complete!");
-        Ok(())
+        let plan = manager.plan_for(PlannedOperation::Install, &release, &asset);
+        assert_eq!(plan.backup_path, None);
+        assert_eq!(plan.current_version, None);
     }
 
-    /// Uninstall the application
-    pub async fn uninstall(&mut self) -> Result<()> {
-        // Check registry/filesystem directly instead of relying on self.is_installed
-        // since the manager may have been newly created
-        #[cfg(target_os = "windows")]
-        let has_installation = win::get_installed_version(&self.config)?.is_some();
+    #[test]
+    fn test_state_discriminants_are_stable() {
+        assert_eq!(State::Downloading as u8, 0);
+        assert_eq!(State::Finalizing as u8, 4);
+        assert_eq!(State::Verifying as u8, 5);
+        assert_eq!(State::Complete as u8, 6);
+        assert_eq!(State::Idle as u8, 7);
+    }
 
-        #[cfg(target_os = "linux")]
-        let has_installation = nix::get_installed_version(&self.config)?.is_some();
+    #[test]
+    fn test_release_body_tolerates_null_and_missing() {
+        let release: GitHubRelease = serde_json::from_str(
+            r#"{"tag_name": "v1.0.0", "name": "v1.0.0", "body": "- Fixed crash", "prerelease": false, "assets": []}"#,
+        ).unwrap();
+        assert_eq!(release.body, "- Fixed crash");
+
+        let release: GitHubRelease = serde_json::from_str(
+            r#"{"tag_name": "v1.0.0", "name": "v1.0.0", "body": null, "prerelease": false, "assets": []}"#,
+        ).unwrap();
+        assert_eq!(release.body, "");
+
+        let release: GitHubRelease = serde_json::from_str(
+            r#"{"tag_name": "v1.0.0", "name": "v1.0.0", "prerelease": false, "assets": []}"#,
+        ).unwrap();
+        assert_eq!(release.body, "");
+    }
 
-        if !has_installation {
-            anyhow::bail!("No installation found in registry.");
-        }
+    #[test]
+    fn test_release_infos_sorted_by_version() {
+        let release = |tag: &str, prerelease: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            body: String::new(),
+            prerelease,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
 
-        println!("Uninstalling {}...", self.config.service_name);
+        // GitHub orders by date, so a patch to an older line can come first
+        let infos = release_infos(vec![
+            release("v1.0.1", false),
+            release("v2.0.0-beta.1", true),
+            release("nightly", true),
+            GitHubRelease { draft: true, ..release("v3.0.0", false) },
+            release("v1.10.0", false),
+        ]);
+
+        let tags: Vec<&str> = infos.iter().map(|info| info.tag.as_str()).collect();
+        assert_eq!(tags, ["v2.0.0-beta.1", "v1.10.0", "v1.0.1"]);
+        assert_eq!(infos[0].channel, ReleaseChannel::Beta);
+        assert_eq!(infos[1].channel, ReleaseChannel::Release);
+    }
 
-        // Platform-specific service removal
-        #[cfg(target_os = "windows")]
-        {
-            win::uninstall_service(&self.config)?;
-        }
+    #[test]
+    fn test_find_release_by_version() {
+        let release = |tag: &str, prerelease: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            body: String::new(),
+            prerelease,
+            draft: false,
+            published_at: None,
+            assets: vec![],
+        };
+        let releases = || vec![
+            GitHubRelease { draft: true, ..release("v1.3.0", false) },
+            release("v1.2.0-beta.1", true),
+            release("1.1.0", false),
+            release("nightly", true),
+        ];
+
+        let found = find_release_by_version(releases(), &Version::parse("1.1.0").unwrap(), "owner/repo").unwrap();
+        assert_eq!(found.tag_name, "1.1.0");
+        assert_eq!(narrowest_channel(&found), ReleaseChannel::Release);
+
+        let beta = find_release_by_version(releases(), &Version::parse("1.2.0-beta.1").unwrap(), "owner/repo").unwrap();
+        assert_eq!(narrowest_channel(&beta), ReleaseChannel::Beta);
+
+        // Drafts can't be installed, so they're neither matched nor listed
+        let err = find_release_by_version(releases(), &Version::new(1, 3, 0), "owner/repo").unwrap_err();
+        assert!(err.to_string().contains("Available versions: 1.2.0-beta.1, 1.1.0"));
+        assert!(matches!(InstallError::from(err), InstallError::AssetNotFound(_)));
+    }
 
-        #[cfg(target_os = "linux")]
-        {
-            nix::uninstall_service(&self.config)?;
-        }
+    #[test]
+    fn test_select_asset_skips_unsupported_formats() {
+        let arch = Architecture::detect().unwrap();
+        let manager = InstallationManager::with_defaults(
+            PathBuf::from("/opt/myapp"),
+            "owner/repo".to_string(),
+            "myapp".to_string(),
+        );
 
-        // Remove installation directory
-        if self.config.install_path.exists() {
-            tokio::fs::remove_dir_all(&self.config.install_path).await?;
-        }
+        let stem = format!("myapp-{}-{}", arch.os_name(), arch.arch_name());
+        let asset = |name: String| GitHubAsset {
+            browser_download_url: format!("https://example.com/{}", name),
+            name,
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let mut release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![
+                asset(format!("{}.{}.sha256", stem, arch.archive_extension())),
+                asset(format!("{}.msi", stem)),
+                asset(format!("{}.{}", stem, arch.archive_extension())),
+            ],
+        };
 
-        self.is_installed = false;
-        self.current_version = None;
+        let selected = manager.select_asset(&release).unwrap();
+        assert_eq!(selected.name, format!("{}.{}", stem, arch.archive_extension()));
 
-        println!("Uninstall complete!");
-        Ok(())
+        release.assets.pop();
+        let err = manager.select_asset(&release).unwrap_err().to_string();
+        assert!(err.contains(&format!("{}.msi", stem)));
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
+    #[cfg(target_os = "linux")]
     #[test]
-    fn test_architecture_detect() {
-        let arch = Architecture::detect();
-        assert!(arch.is_ok());
+    fn test_user_scope_version_file_dir() {
+        let config = InstallationConfig::new(PathBuf::from("/opt/app"), "owner/repo".to_string(), "app".to_string());
+        assert_eq!(config.get_version_file_dir(), PathBuf::from("/var/lib/oim"));
+
+        let config = config.install_scope(InstallScope::User);
+        let dir = config.get_version_file_dir();
+        assert!(dir.is_absolute() && dir.ends_with("oim"));
+        assert_ne!(dir, PathBuf::from("/var/lib/oim"));
+
+        let config = config.version_file_dir("/srv/oim".to_string());
+        assert_eq!(config.get_version_file_dir(), PathBuf::from("/srv/oim"));
     }
 
     #[test]
-    fn test_architecture_patterns() {
-        let arch = Architecture::WindowsX64;
-        let patterns = arch.asset_patterns();
-        assert!(patterns.contains(&"windows"));
-        assert!(patterns.contains(&"x64"));
+    fn test_asset_filter_skips_debug_build() {
+        let arch = Architecture::detect().unwrap();
+        let stem = format!("server-{}-{}", arch.os_name(), arch.arch_name());
+        let asset = |name: String| GitHubAsset {
+            browser_download_url: format!("https://example.com/{}", name),
+            name,
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![
+                asset(format!("{}-debug.{}", stem, arch.archive_extension())),
+                asset(format!("{}.{}", stem, arch.archive_extension())),
+            ],
+        };
+        let expected = format!("{}.{}", stem, arch.archive_extension());
+
+        // The heuristic alone takes the first match
+        let manager = InstallationManager::with_defaults(PathBuf::from("/opt/server"), "owner/repo".to_string(), "server".to_string());
+        assert_ne!(manager.select_asset(&release).unwrap().name, expected);
+        assert_eq!(manager.select_asset_with(&release, |a| !a.name.contains("-debug")).unwrap().name, expected);
+
+        let config = InstallationConfig::new(PathBuf::from("/opt/server"), "owner/repo".to_string(), "server".to_string())
+            .asset_filter(Regex::new(r"^server-[a-z]+-[a-z0-9_]+\.").unwrap());
+        let manager = InstallationManager::new(config.clone());
+        assert_eq!(manager.select_asset(&release).unwrap().name, expected);
+
+        // The filter survives a config round trip as its pattern
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: InstallationConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.asset_filter.unwrap().as_str(), config.asset_filter.unwrap().as_str());
+
+        let err = manager.select_asset_with(&release, |_| false).unwrap_err().to_string();
+        assert!(err.contains("Excluded by the asset filter"));
     }
 
     #[test]
-    fn test_config_builder() {
-        let config = InstallationConfig::new(
+    fn test_select_asset_uses_upload_state_and_content_type() {
+        let arch = Architecture::detect().unwrap();
+        let manager = InstallationManager::with_defaults(
             PathBuf::from("/opt/myapp"),
             "owner/repo".to_string(),
             "myapp".to_string(),
-        )
-        .service_display_name("My Application".to_string())
-        .service_description("A test application".to_string())
-        .binary_name("myapp-bin".to_string());
+        );
 
-        assert_eq!(config.get_display_name(), "My Application");
-        assert_eq!(config.get_description(), "A test application");
-        assert_eq!(config.binary_name, Some("myapp-bin".to_string()));
+        let stem = format!("myapp-{}-{}", arch.os_name(), arch.arch_name());
+        let uploading = GitHubAsset {
+            browser_download_url: "https://example.com/uploading".to_string(),
+            name: format!("{}.{}", stem, arch.archive_extension()),
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: Some("starter".to_string()),
+            updated_at: None,
+        };
+        let untyped_name = GitHubAsset {
+            browser_download_url: "https://example.com/bundle".to_string(),
+            name: format!("{}-bundle", stem),
+            content_type: Some("application/zip".to_string()),
+            state: Some("uploaded".to_string()),
+            ..uploading.clone()
+        };
+        let release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![uploading.clone(), untyped_name],
+        };
+
+        let selected = manager.select_asset(&release).unwrap();
+        assert_eq!(selected.browser_download_url, "https://example.com/bundle");
+        assert_eq!(selected.format(), Some(ArchiveFormat::Zip));
+        assert_eq!(selected.local_file_name(), format!("{}-bundle.zip", stem));
+
+        let only_uploading = GitHubRelease { assets: vec![uploading], ..release };
+        let err = manager.select_asset(&only_uploading).unwrap_err().to_string();
+        assert!(err.contains("Still uploading"));
     }
 
     #[test]
-    fn test_config_defaults() {
+    fn test_newest_release_with_asset_walks_back() {
+        let arch = Architecture::detect().unwrap();
         let config = InstallationConfig::new(
             PathBuf::from("/opt/myapp"),
             "owner/repo".to_string(),
             "myapp".to_string(),
-        );
+        )
+        .require_latest(false);
+        let manager = InstallationManager::new(config);
 
-        assert_eq!(config.get_display_name(), "myapp");
-        assert_eq!(config.get_description(), "myapp Service");
-        assert_eq!(config.get_working_directory(), &PathBuf::from("/opt/myapp"));
+        let asset = |name: String| GitHubAsset {
+            browser_download_url: format!("https://example.com/{}", name),
+            name,
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        let releases = vec![
+            GitHubRelease {
+                tag_name: "v1.1.0".to_string(),
+                name: "Release 1.1.0".to_string(),
+                body: String::new(),
+                prerelease: false,
+                draft: false,
+                published_at: None,
+                assets: vec![asset("myapp-solaris-sparc.tar.gz".to_string())],
+            },
+            GitHubRelease {
+                tag_name: "v1.0.0".to_string(),
+                name: "Release 1.0.0".to_string(),
+                body: String::new(),
+                prerelease: false,
+                draft: false,
+                published_at: None,
+                assets: vec![asset(format!("myapp-{}-{}.{}", arch.os_name(), arch.arch_name(), arch.archive_extension()))],
+            },
+        ];
+
+        let (release, _, version) = manager.newest_release_with_asset(releases.clone(), ReleaseChannel::Release).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(version, Version::new(1, 0, 0));
+
+        assert!(manager.newest_release_with_asset(releases[..1].to_vec(), ReleaseChannel::Release).is_err());
     }
 
     #[test]
-    fn test_installation_manager_creation() {
-        let config = InstallationConfig::new(
+    fn test_select_asset_for_every_architecture() {
+        let names = [
+            "myapp-windows-x86_64.zip",
+            "myapp-windows-arm64.zip",
+            "myapp-linux-x86_64.tar.gz",
+            "myapp-linux-arm64.tar.gz",
+            "myapp-macos-x86_64.tar.gz",
+            "myapp-macos-arm64.tar.gz",
+        ];
+        let release = GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: names
+                .iter()
+                .map(|name| GitHubAsset {
+                    name: name.to_string(),
+                    browser_download_url: format!("https://example.com/{}", name),
+                    size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
+                })
+                .collect(),
+        };
+
+        let mut manager = InstallationManager::new(InstallationConfig::new(
             PathBuf::from("/opt/myapp"),
             "owner/repo".to_string(),
             "myapp".to_string(),
-        );
+        ));
+        for (arch, expected) in Architecture::ALL.iter().zip(names) {
+            assert_eq!(manager.select_asset_for(&release, arch).unwrap().name, expected, "{:?}", arch);
+        }
+        // "darwin" names macOS, not Windows
+        assert_eq!(Architecture::WindowsX64.match_score("myapp-darwin-x64.tar.gz"), 0);
+        assert_eq!(Architecture::MacOSX64.match_score("myapp-darwin-x64.tar.gz"), 3);
 
-        let manager = InstallationManager::new(config);
-        assert!(!manager.is_installed());
-        assert!(manager.current_version().is_none());
-        assert!(manager.latest_version().is_none());
+        manager.set_target_architecture(Architecture::MacOSArm64);
+        assert_eq!(manager.select_asset(&release).unwrap().name, "myapp-macos-arm64.tar.gz");
     }
 
     #[test]
-    fn test_installation_manager_with_defaults() {
-        let manager = InstallationManager::with_defaults(
-            PathBuf::from("/opt/myapp"),
+    fn test_select_asset_rejects_foreign_os() {
+        let release = |names: &[&str]| GitHubRelease {
+            tag_name: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            body: String::new(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: names
+                .iter()
+                .map(|name| GitHubAsset {
+                    name: name.to_string(),
+                    browser_download_url: format!("https://example.com/{}", name),
+                    size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
+                })
+                .collect(),
+        };
+        let manager = InstallationManager::new(InstallationConfig::new(
+            PathBuf::from("/opt/server"),
             "owner/repo".to_string(),
-            "myapp".to_string(),
-        );
-
-        assert_eq!(manager.config().service_name, "myapp");
-        assert_eq!(manager.config().github_repo, "owner/repo");
+            "server".to_string(),
+        ));
+
+        // The CPU matches, but the only OS named is the wrong one
+        let ambiguous = release(&["server-win-x64.zip", "server-universal.zip"]);
+        let err = manager.select_asset_for(&ambiguous, &Architecture::LinuxX64).unwrap_err();
+        assert!(matches!(err, InstallError::AssetNotFound(_)));
+        assert_eq!(manager.select_asset_for(&ambiguous, &Architecture::WindowsX64).unwrap().name, "server-win-x64.zip");
+
+        // A CPU-only match is fine as long as it doesn't name another OS
+        let cpu_only = release(&["server-darwin-arm64.tar.gz", "server-win64-arm64.zip", "server-arm64.tar.gz"]);
+        assert_eq!(manager.select_asset_for(&cpu_only, &Architecture::LinuxArm64).unwrap().name, "server-arm64.tar.gz");
+        assert_eq!(manager.select_asset_for(&cpu_only, &Architecture::WindowsArm64).unwrap().name, "server-win64-arm64.zip");
+
+        // Even an explicit template can't install a binary built for another OS
+        let manager = InstallationManager::new(manager.config().clone().asset_template("server-win-x64.zip".to_string()));
+        let err = manager.select_asset_for(&ambiguous, &Architecture::LinuxX64).unwrap_err();
+        assert!(err.to_string().contains("another operating system"));
     }
 
     #[test]
@@ -1091,17 +6135,28 @@ mod tests {
         let release = GitHubRelease {
             tag_name: "v1.0.0".to_string(),
             name: "Release 1.0.0".to_string(),
+            body: String::new(),
             prerelease: false,
+            draft: false,
+            published_at: None,
             assets: vec![
                 GitHubAsset {
                     name: "myapp-windows-x64.zip".to_string(),
                     browser_download_url: "https://example.com/myapp-windows-x64.zip".to_string(),
                     size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
                 },
                 GitHubAsset {
                     name: "myapp-linux-x64.tar.gz".to_string(),
                     browser_download_url: "https://example.com/myapp-linux-x64.tar.gz".to_string(),
                     size: 1024,
+                    digest: None,
+                    content_type: None,
+                    state: None,
+                    updated_at: None,
                 },
             ],
         };
@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Error returned when another process is already installing, updating or uninstalling the same service
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<OperationInProgress>()`.
+#[derive(Debug, Clone)]
+pub struct OperationInProgress {
+    pub service_name: String,
+}
+
+impl fmt::Display for OperationInProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Another install, update or uninstall of '{}' is already in progress. Please wait for it to finish and try again.",
+            self.service_name
+        )
+    }
+}
+
+impl std::error::Error for OperationInProgress {}
+
+/// Cross-process lock keyed by service name, released when dropped
+///
+/// Uses an flock'd lockfile in the temp directory on Unix and a named mutex on Windows,
+/// so the lock is also released if the holding process dies.
+#[derive(Debug)]
+pub(crate) struct OperationLock {
+    #[cfg(not(target_os = "windows"))]
+    _file: std::fs::File,
+    #[cfg(target_os = "windows")]
+    handle: isize,
+}
+
+impl OperationLock {
+    /// Try to acquire the lock for a service without blocking
+    #[cfg(not(target_os = "windows"))]
+    pub fn acquire(service_name: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("oim-{}.lock", service_name));
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .context(format!("Failed to open lock file '{}'", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(std::fs::TryLockError::WouldBlock) => Err(OperationInProgress {
+                service_name: service_name.to_string(),
+            }
+            .into()),
+            Err(std::fs::TryLockError::Error(e)) => {
+                Err(e).context(format!("Failed to lock '{}'", path.display()))
+            }
+        }
+    }
+
+    /// Try to acquire the lock for a service without blocking
+    #[cfg(target_os = "windows")]
+    pub fn acquire(service_name: &str) -> Result<Self> {
+        match crate::win::create_operation_mutex(&format!("Global\\oim-{}", service_name))? {
+            Some(handle) => Ok(Self { handle }),
+            None => Err(OperationInProgress {
+                service_name: service_name.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for OperationLock {
+    fn drop(&mut self) {
+        crate::win::close_operation_mutex(self.handle);
+    }
+}
+
+/// Marks a manager (and its clones) as busy for the duration of an operation, optionally
+/// holding the cross-process lock as well
+#[derive(Debug)]
+pub(crate) struct OperationGuard {
+    busy: Arc<AtomicBool>,
+    lock: Option<OperationLock>,
+}
+
+impl OperationGuard {
+    /// Mark `busy`, failing with `OperationInProgress` if it is already set
+    pub fn begin(busy: &Arc<AtomicBool>, service_name: &str) -> Result<Self> {
+        if busy.swap(true, Ordering::AcqRel) {
+            return Err(OperationInProgress {
+                service_name: service_name.to_string(),
+            }
+            .into());
+        }
+
+        Ok(Self { busy: busy.clone(), lock: None })
+    }
+
+    /// Also hold the cross-process lock until the guard is dropped
+    pub fn with_lock(mut self, lock: Option<OperationLock>) -> Self {
+        self.lock = lock;
+        self
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.lock.take();
+        self.busy.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let service_name = format!("oim-lock-test-{}", std::process::id());
+
+        let lock = OperationLock::acquire(&service_name).unwrap();
+        let err = OperationLock::acquire(&service_name).unwrap_err();
+        assert!(err.downcast_ref::<OperationInProgress>().is_some());
+
+        drop(lock);
+        assert!(OperationLock::acquire(&service_name).is_ok());
+    }
+
+    #[test]
+    fn test_guard_marks_busy_until_dropped() {
+        let busy = Arc::new(AtomicBool::new(false));
+
+        let guard = OperationGuard::begin(&busy, "app").unwrap();
+        assert!(busy.load(Ordering::Acquire));
+        assert!(OperationGuard::begin(&busy, "app").is_err());
+
+        drop(guard);
+        assert!(!busy.load(Ordering::Acquire));
+        assert!(OperationGuard::begin(&busy, "app").is_ok());
+    }
+}
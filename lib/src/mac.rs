@@ -0,0 +1,381 @@
+use anyhow::{Context, Result};
+use semver::Version;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::{InstallationConfig, RestartPolicy};
+use crate::command::{capture_command, run_command_with, COMMAND_RETRIES, COMMAND_TIMEOUT};
+use crate::service_manager::resolve_tool;
+
+/// Get the installed version from version file
+pub fn get_installed_version(config: &InstallationConfig) -> Result<Option<Version>> {
+    let version_file = config.get_version_file_dir().join(format!("{}.version", config.service_name));
+
+    match std::fs::read_to_string(&version_file) {
+        Ok(version_str) => {
+            let version = Version::parse(version_str.trim().trim_start_matches('v'))
+                .context("Failed to parse version from file")?;
+            Ok(Some(version))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Store version information
+pub fn set_installed_version(config: &InstallationConfig, version: &str) -> Result<()> {
+    let version_dir = config.get_version_file_dir();
+    std::fs::create_dir_all(&version_dir)
+        .context("Failed to create version directory")?;
+
+    std::fs::write(version_dir.join(format!("{}.version", config.service_name)), version)
+        .context("Failed to write version file")?;
+
+    Ok(())
+}
+
+/// Store installation path
+fn set_install_path(config: &InstallationConfig, path: &Path) -> Result<()> {
+    let version_dir = config.get_version_file_dir();
+    std::fs::create_dir_all(&version_dir)
+        .context("Failed to create version directory")?;
+
+    std::fs::write(version_dir.join(format!("{}.path", config.service_name)), path.to_string_lossy().as_ref())
+        .context("Failed to write path file")?;
+
+    Ok(())
+}
+
+/// Remove version and path files
+fn remove_metadata_files(config: &InstallationConfig) -> Result<()> {
+    let version_dir = config.get_version_file_dir();
+    let _ = std::fs::remove_file(version_dir.join(format!("{}.version", config.service_name)));
+    let _ = std::fs::remove_file(version_dir.join(format!("{}.path", config.service_name)));
+
+    Ok(())
+}
+
+/// Path of the LaunchDaemon property list for a service
+pub fn plist_path(config: &InstallationConfig) -> PathBuf {
+    PathBuf::from(format!("/Library/LaunchDaemons/{}.plist", config.service_name))
+}
+
+/// Resolve launchctl from PATH, reporting a missing tool as `ServiceManagerUnavailable`
+fn launchctl() -> Result<Command> {
+    let path = resolve_tool("launchctl", None, std::env::var_os("PATH").as_deref())?;
+    Ok(Command::new(path))
+}
+
+/// Find the main executable in the installation directory
+pub fn find_executable(config: &InstallationConfig) -> Result<PathBuf> {
+    let install_path = &config.install_path;
+
+    // If a custom binary name is specified, look for that specifically
+    if let Some(binary_name) = config.get_binary_name() {
+        for candidate in [install_path.join(binary_name), install_path.join("bin").join(binary_name)] {
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    // Otherwise, look for any executable file, then in bin/
+    for dir in [install_path.clone(), install_path.join("bin")] {
+        if let Some(path) = find_executable_in(&dir)? {
+            return Ok(path);
+        }
+    }
+
+    anyhow::bail!("No executable found in installation directory")
+}
+
+fn find_executable_in(dir: &Path) -> Result<Option<PathBuf>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && std::fs::metadata(&path)?.permissions().mode() & 0o111 != 0 {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Create the LaunchDaemon property list for a service
+fn create_launchd_plist(config: &InstallationConfig, exe_path: &Path) -> String {
+    let label = escape_xml(&config.service_name);
+    let log_path = escape_xml(&format!("/Library/Logs/{}.log", config.service_name));
+    let keep_alive = match config.restart_policy {
+        RestartPolicy::Always => "<true/>".to_string(),
+        RestartPolicy::OnFailure => "<dict>\n        <key>SuccessfulExit</key>\n        <false/>\n    </dict>".to_string(),
+        RestartPolicy::Never => "<false/>".to_string(),
+    };
+    let env = config.get_service_env();
+    let environment = if env.is_empty() {
+        String::new()
+    } else {
+        let entries: String = env
+            .into_iter()
+            .map(|(key, value)| format!("        <key>{}</key>\n        <string>{}</string>\n", escape_xml(key), escape_xml(value)))
+            .collect();
+        format!("    <key>EnvironmentVariables</key>\n    <dict>\n{}    </dict>\n", entries)
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>WorkingDirectory</key>
+    <string>{}</string>
+{}    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    {}
+    <key>ThrottleInterval</key>
+    <integer>{}</integer>
+    <key>StandardOutPath</key>
+    <string>{}</string>
+    <key>StandardErrorPath</key>
+    <string>{}</string>
+</dict>
+</plist>
+"#,
+        label,
+        escape_xml(&exe_path.to_string_lossy()),
+        escape_xml(&config.get_working_directory().to_string_lossy()),
+        environment,
+        keep_alive,
+        config.get_restart_sec(),
+        log_path,
+        log_path
+    )
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write the LaunchDaemon plist; launchd loads it at boot from then on
+fn register_service(config: &InstallationConfig) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let exe_path = find_executable(config)?;
+    let plist_path = plist_path(config);
+
+    std::fs::write(&plist_path, create_launchd_plist(config, &exe_path))
+        .context("Failed to write LaunchDaemon plist. Make sure you have root privileges.")?;
+
+    // launchd refuses plists that are group or world writable
+    std::fs::set_permissions(&plist_path, std::fs::Permissions::from_mode(0o644))
+        .context("Failed to set LaunchDaemon plist permissions")?;
+
+    Ok(())
+}
+
+/// Install a LaunchDaemon and record the installed version
+///
+/// The service is registered but not started; use start_service and service_started.
+pub fn install_service(config: &InstallationConfig, version: &str) -> Result<()> {
+    register_service(config)?;
+
+    set_installed_version(config, version)?;
+    set_install_path(config, &config.install_path)?;
+
+    Ok(())
+}
+
+/// Describe the service commands an install (or update, which stops the service first) would run
+pub fn planned_service_commands(config: &InstallationConfig, updating: bool) -> Vec<String> {
+    let plist_path = plist_path(config);
+
+    let mut commands = Vec::new();
+    if updating {
+        commands.push(format!("launchctl unload {}", plist_path.display()));
+    }
+    commands.push(format!("write {}", plist_path.display()));
+    commands.push(format!("launchctl load -w {}", plist_path.display()));
+    commands
+}
+
+/// Re-create the LaunchDaemon plist from the current config without touching installed files
+pub fn repair_service(config: &InstallationConfig) -> Result<()> {
+    // Unload first so the rewritten plist is picked up on the next load
+    let _ = capture_command(launchctl()?.arg("unload").arg(plist_path(config)), COMMAND_TIMEOUT);
+    register_service(config)
+}
+
+/// Output of `launchctl list <label>`, or None if the job isn't loaded
+fn list_job(config: &InstallationConfig) -> Result<Option<String>> {
+    let output = capture_command(launchctl()?.arg("list").arg(&config.service_name), COMMAND_TIMEOUT)
+        .context("Failed to check service status")?;
+
+    Ok(output.status.success().then_some(output.stdout))
+}
+
+/// Read an integer entry such as `"PID" = 123;` from `launchctl list <label>` output
+fn list_value(output: &str, key: &str) -> Option<i64> {
+    let prefix = format!("\"{}\" = ", key);
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(&prefix))
+        .and_then(|value| value.trim_end_matches(';').trim().parse().ok())
+}
+
+/// Process ID of the running job, if it is loaded and running
+fn service_pid(config: &InstallationConfig) -> Result<Option<i32>> {
+    Ok(list_job(config)?
+        .and_then(|output| list_value(&output, "PID"))
+        .and_then(|pid| i32::try_from(pid).ok())
+        .filter(|pid| *pid > 0))
+}
+
+/// Check whether a started service has come up, failing if it exited with an error
+pub fn service_started(config: &InstallationConfig) -> Result<bool> {
+    let Some(output) = list_job(config)? else {
+        return Ok(false);
+    };
+
+    if list_value(&output, "PID").is_some() {
+        return Ok(true);
+    }
+
+    match list_value(&output, "LastExitStatus") {
+        Some(status) if status != 0 => anyhow::bail!(
+            "Service '{}' exited with status {}. Check /Library/Logs/{}.log for details.",
+            config.service_name,
+            status,
+            config.service_name
+        ),
+        _ => Ok(false),
+    }
+}
+
+/// Load (and so start) a LaunchDaemon
+pub fn start_service(config: &InstallationConfig) -> Result<()> {
+    if service_pid(config)?.is_some() {
+        return Ok(());
+    }
+
+    // -w clears a disabled override so the job also loads at boot
+    run_command_with(launchctl()?.arg("load").arg("-w").arg(plist_path(config)), COMMAND_TIMEOUT, COMMAND_RETRIES)?;
+
+    // Already loaded but not running (e.g. after a crash loop); kick it
+    if service_pid(config)?.is_none() {
+        let _ = capture_command(launchctl()?.arg("start").arg(&config.service_name), COMMAND_TIMEOUT);
+    }
+
+    Ok(())
+}
+
+/// Stop a LaunchDaemon and wait for it to exit
+pub fn stop_service(config: &InstallationConfig) -> Result<()> {
+    request_stop(config)?;
+
+    for _ in 0..30 {
+        if service_stopped(config)? {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    Ok(())
+}
+
+/// Ask launchd to stop a service
+///
+/// The job is unloaded rather than stopped, since KeepAlive would restart it immediately.
+/// Without -w it is still loaded again at boot.
+pub fn request_stop(config: &InstallationConfig) -> Result<()> {
+    if list_job(config)?.is_none() {
+        return Ok(());
+    }
+
+    run_command_with(launchctl()?.arg("unload").arg(plist_path(config)), COMMAND_TIMEOUT, 0)?;
+    Ok(())
+}
+
+/// Check whether a service has fully stopped (a job that isn't loaded counts as stopped)
+pub fn service_stopped(config: &InstallationConfig) -> Result<bool> {
+    Ok(service_pid(config)?.is_none())
+}
+
+/// Path to the executable image of the service's running process, if it is running
+pub fn running_executable(config: &InstallationConfig) -> Result<Option<PathBuf>> {
+    let Some(pid) = service_pid(config)? else {
+        return Ok(None);
+    };
+
+    let mut buffer = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    // SAFETY: the buffer is valid for writes of its full length
+    let len = unsafe { libc::proc_pidpath(pid, buffer.as_mut_ptr().cast(), buffer.len() as u32) };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to query service process path");
+    }
+
+    buffer.truncate(len as usize);
+    Ok(Some(PathBuf::from(String::from_utf8_lossy(&buffer).into_owned())))
+}
+
+/// Forcefully kill the service's process
+pub fn kill_service(config: &InstallationConfig) -> Result<()> {
+    let Some(pid) = service_pid(config)? else {
+        return Ok(());
+    };
+
+    // SAFETY: kill has no memory safety requirements
+    if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to kill service");
+    }
+
+    Ok(())
+}
+
+/// Uninstall a LaunchDaemon
+pub fn uninstall_service(config: &InstallationConfig) -> Result<()> {
+    stop_service(config).ok();
+
+    let plist_path = plist_path(config);
+    if plist_path.exists() {
+        std::fs::remove_file(&plist_path)
+            .context("Failed to remove LaunchDaemon plist")?;
+    }
+
+    remove_metadata_files(config)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_value() {
+        let output = "{\n\t\"LastExitStatus\" = 256;\n\t\"PID\" = 4242;\n\t\"Label\" = \"myapp\";\n};";
+        assert_eq!(list_value(output, "PID"), Some(4242));
+        assert_eq!(list_value(output, "LastExitStatus"), Some(256));
+        assert_eq!(list_value(output, "Label"), None);
+    }
+
+    #[test]
+    fn test_plist_escapes_values() {
+        let config = InstallationConfig::new(PathBuf::from("/opt/a&b"), "owner/repo".to_string(), "myapp".to_string());
+        let plist = create_launchd_plist(&config, Path::new("/opt/a&b/server"));
+        assert!(plist.contains("<string>/opt/a&amp;b/server</string>"));
+        assert!(plist.contains("<key>Label</key>\n    <string>myapp</string>"));
+    }
+}
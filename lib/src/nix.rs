@@ -2,12 +2,20 @@ use anyhow::{Context, Result};
 use semver::Version;
 use std::path::PathBuf;
 use std::process::Command;
-use crate::InstallationConfig;
+use crate::{InstallScope, InstallationConfig, RestartPolicy};
+use crate::command::{capture_command, run_command_with, COMMAND_RETRIES, COMMAND_TIMEOUT};
+use crate::service_manager::{resolve_tool, ServiceManagerUnavailable};
 
-/// Get the installed version from version file
+/// Get the installed version from the package database (with `system_package`) or version file
 pub fn get_installed_version(config: &InstallationConfig) -> Result<Option<Version>> {
-    let version_file_dir = config.get_version_file_dir();
-    let version_file = PathBuf::from(version_file_dir).join(format!("{}.version", config.service_name));
+    if config.system_package
+        && let Some(kind) = crate::package::PackageKind::detect()
+        && let Some(version) = crate::package::installed_version(kind, config.get_package_name())?
+    {
+        return Ok(Some(version));
+    }
+
+    let version_file = config.get_version_file_dir().join(format!("{}.version", config.service_name));
 
     if !version_file.exists() {
         return Ok(None);
@@ -15,7 +23,7 @@ pub fn get_installed_version(config: &InstallationConfig) -> Result<Option<Versi
 
     match std::fs::read_to_string(&version_file) {
         Ok(version_str) => {
-            let version = Version::parse(version_str.trim())
+            let version = Version::parse(version_str.trim().trim_start_matches('v'))
                 .context("Failed to parse version from file")?;
             Ok(Some(version))
         }
@@ -25,7 +33,7 @@ pub fn get_installed_version(config: &InstallationConfig) -> Result<Option<Versi
 
 /// Store version information
 pub fn set_installed_version(config: &InstallationConfig, version: &str) -> Result<()> {
-    let version_dir = PathBuf::from(config.get_version_file_dir());
+    let version_dir = config.get_version_file_dir();
     std::fs::create_dir_all(&version_dir)
         .context("Failed to create version directory")?;
 
@@ -38,7 +46,7 @@ pub fn set_installed_version(config: &InstallationConfig, version: &str) -> Resu
 
 /// Store installation path
 fn set_install_path(config: &InstallationConfig, path: &std::path::Path) -> Result<()> {
-    let version_dir = PathBuf::from(config.get_version_file_dir());
+    let version_dir = config.get_version_file_dir();
     std::fs::create_dir_all(&version_dir)
         .context("Failed to create version directory")?;
 
@@ -52,7 +60,7 @@ fn set_install_path(config: &InstallationConfig, path: &std::path::Path) -> Resu
 
 /// Remove version and path files
 fn remove_metadata_files(config: &InstallationConfig) -> Result<()> {
-    let version_dir = PathBuf::from(config.get_version_file_dir());
+    let version_dir = config.get_version_file_dir();
     let version_file = version_dir.join(format!("{}.version", config.service_name));
     let path_file = version_dir.join(format!("{}.path", config.service_name));
 
@@ -62,14 +70,165 @@ fn remove_metadata_files(config: &InstallationConfig) -> Result<()> {
     Ok(())
 }
 
-/// Find the main executable in the installation directory
-fn find_executable(config: &InstallationConfig) -> Result<PathBuf> {
-    use std::os::unix::fs::PermissionsExt;
+/// Resolve an XDG base directory, falling back to `fallback` under the home directory when the
+/// variable is unset or not absolute, as the XDG spec requires
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match std::env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(fallback),
+    }
+}
+
+/// The user's data directory ($XDG_DATA_HOME, ~/.local/share by default)
+pub fn user_data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Path of the service's unit file for the configured install scope
+pub fn unit_file_path(config: &InstallationConfig) -> PathBuf {
+    let dir = match config.install_scope {
+        InstallScope::System => PathBuf::from("/etc/systemd/system"),
+        InstallScope::User => xdg_dir("XDG_CONFIG_HOME", ".config").join("systemd/user"),
+    };
+    dir.join(format!("{}.service", config.service_name))
+}
+
+/// Path of the application launcher written when `create_shortcuts` is set
+///
+/// User installs go to the user's applications directory, system installs to
+/// /usr/local/share/applications so every user sees them.
+pub fn shortcut_path(config: &InstallationConfig) -> PathBuf {
+    applications_dir(config).join(format!("{}.desktop", config.service_name))
+}
+
+/// Path of the launcher for a binary other than the primary one
+pub fn binary_shortcut_path(config: &InstallationConfig, binary: &str) -> PathBuf {
+    applications_dir(config).join(format!("{}-{}.desktop", config.service_name, binary))
+}
+
+/// Paths of every launcher install may write for `config`
+pub fn shortcut_paths(config: &InstallationConfig) -> Vec<PathBuf> {
+    std::iter::once(shortcut_path(config))
+        .chain(config.secondary_shortcuts().into_iter().map(|(spec, _)| binary_shortcut_path(config, &spec.name)))
+        .collect()
+}
+
+fn applications_dir(config: &InstallationConfig) -> PathBuf {
+    match config.install_scope {
+        InstallScope::System => PathBuf::from("/usr/local/share/applications"),
+        InstallScope::User => user_data_dir().join("applications"),
+    }
+}
+
+/// Write a `.desktop` launcher at `path` named `name` that runs `target`
+pub fn create_shortcut(config: &InstallationConfig, path: &std::path::Path, target: &std::path::Path, name: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .context("Failed to create applications directory")?;
+    }
+
+    std::fs::write(path, desktop_entry(name, &config.get_description(), target, config.get_working_directory()))
+        .context(format!("Failed to write launcher '{}'", path.display()))?;
+
+    Ok(())
+}
+
+/// Remove the launchers written by `create_shortcut`, if there are any
+pub fn remove_shortcuts(config: &InstallationConfig) -> Result<()> {
+    for path in shortcut_paths(config) {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context(format!("Failed to remove launcher '{}'", path.display()));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Contents of a desktop entry launching `target` from `working_dir`
+fn desktop_entry(name: &str, comment: &str, target: &std::path::Path, working_dir: &std::path::Path) -> String {
+    // Exec arguments are double-quoted, with these characters escaped inside the quotes, and
+    // the value is then escaped like any other string
+    let mut exec = String::from("\"");
+    for c in target.to_string_lossy().chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            exec.push('\\');
+        }
+        exec.push(c);
+    }
+    exec.push('"');
+
+    // String values can't span lines
+    let single_line = |value: &str| value.replace('\n', " ");
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nComment={}\nExec={}\nPath={}\nTerminal=false\n",
+        single_line(name),
+        single_line(comment),
+        exec.replace('\\', "\\\\"),
+        working_dir.display()
+    )
+}
+
+/// Get the number of inodes available to unprivileged users on the filesystem holding `path`
+///
+/// Returns None for filesystems without a fixed inode table (e.g. btrfs reports zero total inodes).
+pub fn available_inodes(path: &std::path::Path) -> Result<Option<u64>> {
+    use std::os::unix::ffi::OsStrExt;
 
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .context("Path contains a null byte")?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context(format!("Failed to query filesystem for '{}'", path.display()));
+    }
+
+    if stat.f_files == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(stat.f_favail as u64))
+}
+
+/// Build a systemctl command, failing with `ServiceManagerUnavailable` if systemctl is missing
+/// or systemd isn't running (e.g. inside a container whose PID 1 is a shell)
+fn systemctl(config: &InstallationConfig) -> Result<Command> {
+    let path = resolve_tool(
+        "systemctl",
+        config.systemctl_path.as_deref(),
+        std::env::var_os("PATH").as_deref(),
+    )?;
+
+    // Same check as sd_booted(3)
+    if !std::path::Path::new("/run/systemd/system").is_dir() {
+        let init = std::fs::read_to_string("/proc/1/comm")
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        return Err(ServiceManagerUnavailable {
+            tool: "systemctl".to_string(),
+            reason: format!(
+                "systemd is not running (PID 1 is '{}'). This looks like a container without an init system, where services can't be managed.",
+                init
+            ),
+        }
+        .into());
+    }
+
+    let mut command = Command::new(path);
+    if config.install_scope == InstallScope::User {
+        command.arg("--user");
+    }
+    Ok(command)
+}
+
+/// Find the main executable in the installation directory
+pub fn find_executable(config: &InstallationConfig) -> Result<PathBuf> {
     let install_path = &config.install_path;
 
     // If a custom binary name is specified, look for that specifically
-    if let Some(binary_name) = &config.binary_name {
+    if let Some(binary_name) = config.get_binary_name() {
         let exe_path = install_path.join(binary_name);
         if exe_path.exists() && exe_path.is_file() {
             return Ok(exe_path);
@@ -130,10 +289,25 @@ fn find_any_executable(install_path: &PathBuf) -> Result<PathBuf> {
 /// Create a systemd service unit file
 fn create_systemd_unit(
     config: &InstallationConfig,
-    exe_path: &PathBuf,
+    exe_path: &std::path::Path,
 ) -> Result<String> {
     let working_dir = config.get_working_directory();
     let description = config.get_description();
+    let wanted_by = match config.install_scope {
+        InstallScope::System => "multi-user.target",
+        InstallScope::User => "default.target",
+    };
+
+    let restart = match config.restart_policy {
+        RestartPolicy::Always => "always",
+        RestartPolicy::OnFailure => "on-failure",
+        RestartPolicy::Never => "no",
+    };
+    let environment: String = config
+        .get_service_env()
+        .into_iter()
+        .map(|(key, value)| format!("Environment={}\n", quote_environment(key, value)))
+        .collect();
 
     let unit_content = format!(
         r#"[Unit]
@@ -144,126 +318,157 @@ After=network.target
 Type=simple
 ExecStart={}
 WorkingDirectory={}
-Restart=always
-RestartSec=10
+{}Restart={}
+RestartSec={}
 StandardOutput=journal
 StandardError=journal
 
 [Install]
-WantedBy=multi-user.target
+WantedBy={}
 "#,
         description,
         exe_path.display(),
-        working_dir.display()
+        working_dir.display(),
+        environment,
+        restart,
+        config.get_restart_sec(),
+        wanted_by
     );
 
     Ok(unit_content)
 }
 
-/// Install a systemd service
-pub fn install_service(
-    config: &InstallationConfig,
-    version: &str,
-) -> Result<()> {
+/// Quote a `KEY=value` assignment for an `Environment=` line, escaping what systemd would
+/// otherwise interpret (quotes, backslashes and `%` specifiers)
+fn quote_environment(key: &str, value: &str) -> String {
+    let assignment = format!("{}={}", key, value)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('\n', "\\n");
+    format!("\"{}\"", assignment)
+}
+
+/// Write the unit file, reload systemd and enable the service
+fn register_service(config: &InstallationConfig) -> Result<()> {
     // Find the executable
     let exe_path = find_executable(config)?;
 
     // Create systemd unit file
     let unit_content = create_systemd_unit(config, &exe_path)?;
-    let unit_file_path = format!("/etc/systemd/system/{}.service", config.service_name);
+    let unit_file_path = unit_file_path(config);
 
     // Write the unit file
-    std::fs::write(&unit_file_path, unit_content)
-        .context("Failed to write systemd unit file. Make sure you have root privileges.")?;
+    match config.install_scope {
+        InstallScope::System => std::fs::write(&unit_file_path, unit_content)
+            .context("Failed to write systemd unit file. Make sure you have root privileges.")?,
+        InstallScope::User => {
+            if let Some(unit_dir) = unit_file_path.parent() {
+                std::fs::create_dir_all(unit_dir).context("Failed to create systemd user unit directory")?;
+            }
+            std::fs::write(&unit_file_path, unit_content).context("Failed to write systemd user unit file")?;
+        }
+    }
 
     // Reload systemd daemon
-    let output = Command::new("systemctl")
-        .arg("daemon-reload")
-        .output()
-        .context("Failed to reload systemd daemon")?;
-
-    if !output.status.success() {
-        anyhow::bail!("Failed to reload systemd daemon: {}",
-            String::from_utf8_lossy(&output.stderr));
-    }
+    run_command_with(systemctl(config)?.arg("daemon-reload"), COMMAND_TIMEOUT, COMMAND_RETRIES)?;
 
     // Enable the service
-    let output = Command::new("systemctl")
-        .arg("enable")
-        .arg(&config.service_name)
-        .output()
-        .context("Failed to enable service")?;
-
-    if !output.status.success() {
-        anyhow::bail!("Failed to enable service: {}",
-            String::from_utf8_lossy(&output.stderr));
-    }
+    run_command_with(systemctl(config)?.arg("enable").arg(&config.service_name), COMMAND_TIMEOUT, COMMAND_RETRIES)?;
+
+    Ok(())
+}
+
+/// Install a systemd service and record the installed version
+///
+/// The service is registered but not started; use start_service and service_started.
+pub fn install_service(
+    config: &InstallationConfig,
+    version: &str,
+) -> Result<()> {
+    register_service(config)?;
 
     // Store version and path
     set_installed_version(config, version)?;
     set_install_path(config, &config.install_path)?;
 
-    // Start the service
-    start_service(config)?;
+    Ok(())
+}
+
+/// Describe the service commands an install (or update, which stops the service first) would run
+pub fn planned_service_commands(config: &InstallationConfig, updating: bool) -> Vec<String> {
+    let mut systemctl = config
+        .systemctl_path
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "systemctl".to_string());
+    if config.install_scope == InstallScope::User {
+        systemctl.push_str(" --user");
+    }
+
+    let mut commands = Vec::new();
+    if updating {
+        commands.push(format!("{} stop {}", systemctl, config.service_name));
+    }
+    commands.push(format!("write {}", unit_file_path(config).display()));
+    commands.push(format!("{} daemon-reload", systemctl));
+    commands.push(format!("{} enable {}", systemctl, config.service_name));
+    commands.push(format!("{} start {}", systemctl, config.service_name));
+    commands
+}
+
+/// Re-create and re-enable the systemd service from the current config without touching
+/// installed files, clearing any previous failed state so it can be started again
+pub fn repair_service(config: &InstallationConfig) -> Result<()> {
+    register_service(config)?;
+
+    // Clear a previous failed state so systemd doesn't refuse to start it
+    let _ = capture_command(systemctl(config)?.arg("reset-failed").arg(&config.service_name), COMMAND_TIMEOUT);
 
     Ok(())
 }
 
+/// Check whether a started service has come up, failing if systemd reports it as failed
+pub fn service_started(config: &InstallationConfig) -> Result<bool> {
+    match service_state(config)?.as_str() {
+        "active" => Ok(true),
+        "failed" => anyhow::bail!(
+            "Service '{}' failed to start. Check `journalctl {}-u {}` for details.",
+            config.service_name,
+            if config.install_scope == InstallScope::User { "--user " } else { "" },
+            config.service_name
+        ),
+        _ => Ok(false),
+    }
+}
+
 /// Start a systemd service
 pub fn start_service(config: &InstallationConfig) -> Result<()> {
     // Check if service is already running
-    let status_output = Command::new("systemctl")
-        .arg("is-active")
-        .arg(&config.service_name)
-        .output()
-        .context("Failed to check service status")?;
-
-    let status = String::from_utf8_lossy(&status_output.stdout);
-    if status.trim() == "active" {
+    if service_state(config)? == "active" {
         return Ok(());
     }
 
     // Start the service
-    let output = Command::new("systemctl")
-        .arg("start")
-        .arg(&config.service_name)
-        .output()
-        .context("Failed to start service")?;
-
-    if !output.status.success() {
-        anyhow::bail!("Failed to start service: {}",
-            String::from_utf8_lossy(&output.stderr));
-    }
+    run_command_with(systemctl(config)?.arg("start").arg(&config.service_name), COMMAND_TIMEOUT, 0)?;
 
     Ok(())
 }
 
 /// Stop a systemd service
 pub fn stop_service(config: &InstallationConfig) -> Result<()> {
-    let output = Command::new("systemctl")
-        .arg("stop")
-        .arg(&config.service_name)
-        .output()
+    let output = capture_command(systemctl(config)?.arg("stop").arg(&config.service_name), COMMAND_TIMEOUT)
         .context("Failed to stop service")?;
 
-    if !output.status.success() {
-        // Don't fail if service is already stopped
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if !stderr.contains("not loaded") {
-            anyhow::bail!("Failed to stop service: {}", stderr);
-        }
+    // Don't fail if service is already stopped
+    if !output.status.success() && !output.stderr.contains("not loaded") {
+        anyhow::bail!("Failed to stop service: {}", output.stderr);
     }
 
     // Wait for service to stop
     for _ in 0..30 {
-        let status_output = Command::new("systemctl")
-            .arg("is-active")
-            .arg(&config.service_name)
-            .output()
-            .context("Failed to check service status")?;
-
-        let status = String::from_utf8_lossy(&status_output.stdout);
-        if status.trim() == "inactive" || status.trim() == "failed" {
+        let status = service_state(config)?;
+        if status == "inactive" || status == "failed" {
             break;
         }
 
@@ -273,43 +478,110 @@ pub fn stop_service(config: &InstallationConfig) -> Result<()> {
     Ok(())
 }
 
+/// Ask systemd to stop a service without waiting for the stop job to finish
+pub fn request_stop(config: &InstallationConfig) -> Result<()> {
+    let output = capture_command(
+        systemctl(config)?.arg("stop").arg("--no-block").arg(&config.service_name),
+        COMMAND_TIMEOUT,
+    )
+    .context("Failed to stop service")?;
+
+    if !output.status.success() && !output.stderr.contains("not loaded") {
+        anyhow::bail!("Failed to stop service: {}", output.stderr);
+    }
+
+    Ok(())
+}
+
+/// Check whether a service has fully stopped (a unit that doesn't exist counts as stopped)
+pub fn service_stopped(config: &InstallationConfig) -> Result<bool> {
+    let status = service_state(config)?;
+    Ok(!matches!(status.as_str(), "active" | "activating" | "deactivating" | "reloading"))
+}
+
+/// The unit's state as reported by `systemctl is-active` (e.g. "active", "inactive", "failed")
+fn service_state(config: &InstallationConfig) -> Result<String> {
+    let output = capture_command(systemctl(config)?.arg("is-active").arg(&config.service_name), COMMAND_TIMEOUT)
+        .context("Failed to check service status")?;
+    Ok(output.stdout)
+}
+
+/// Path to the executable image of the service's running main process, if it is running
+///
+/// `/proc/<pid>/exe` keeps pointing at the loaded binary even after the file on disk was replaced.
+pub fn running_executable(config: &InstallationConfig) -> Result<Option<PathBuf>> {
+    let output = run_command_with(
+        systemctl(config)?.arg("show").arg("--property=MainPID").arg("--value").arg(&config.service_name),
+        COMMAND_TIMEOUT,
+        0,
+    )?;
+
+    match output.stdout.parse::<u32>() {
+        Ok(0) | Err(_) => Ok(None),
+        Ok(pid) => Ok(Some(PathBuf::from(format!("/proc/{}/exe", pid)))),
+    }
+}
+
+/// Forcefully kill every process of a service
+pub fn kill_service(config: &InstallationConfig) -> Result<()> {
+    run_command_with(
+        systemctl(config)?.arg("kill").arg("--signal=SIGKILL").arg(&config.service_name),
+        COMMAND_TIMEOUT,
+        0,
+    )?;
+
+    Ok(())
+}
+
 /// Uninstall a systemd service
 pub fn uninstall_service(config: &InstallationConfig) -> Result<()> {
     // Stop the service first
     stop_service(config).ok();
 
     // Disable the service
-    let output = Command::new("systemctl")
-        .arg("disable")
-        .arg(&config.service_name)
-        .output()
+    let output = capture_command(systemctl(config)?.arg("disable").arg(&config.service_name), COMMAND_TIMEOUT)
         .context("Failed to disable service")?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if !stderr.contains("not loaded") {
-            eprintln!("Warning: Failed to disable service: {}", stderr);
-        }
+    if !output.status.success() && !output.stderr.contains("not loaded") {
+        tracing::warn!(service = %config.service_name, "Failed to disable service: {}", output.stderr);
     }
 
     // Remove the unit file
-    let unit_file_path = format!("/etc/systemd/system/{}.service", config.service_name);
-    std::fs::remove_file(&unit_file_path)
+    std::fs::remove_file(unit_file_path(config))
         .context("Failed to remove systemd unit file")?;
 
     // Reload systemd daemon
-    let output = Command::new("systemctl")
-        .arg("daemon-reload")
-        .output()
-        .context("Failed to reload systemd daemon")?;
-
-    if !output.status.success() {
-        eprintln!("Warning: Failed to reload systemd daemon: {}",
-            String::from_utf8_lossy(&output.stderr));
+    if let Err(e) = run_command_with(systemctl(config)?.arg("daemon-reload"), COMMAND_TIMEOUT, COMMAND_RETRIES) {
+        tracing::warn!("Failed to reload systemd daemon: {:#}", e);
     }
 
+    remove_shortcuts(config)?;
+
     // Remove metadata files
     remove_metadata_files(config)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_quote_environment() {
+        assert_eq!(quote_environment("PORT", "8080"), r#""PORT=8080""#);
+        assert_eq!(quote_environment("DATA", r#"/srv/my "panel" 100%"#), r#""DATA=/srv/my \"panel\" 100%%""#);
+        assert_eq!(quote_environment("PATHS", "C:\\a\nb"), r#""PATHS=C:\\a\nb""#);
+    }
+
+    #[test]
+    fn test_desktop_entry_quotes_exec() {
+        let entry = desktop_entry("My App", "My App\nService", Path::new("/opt/my app/$bin/app"), Path::new("/opt/my app"));
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("\nName=My App\n"));
+        assert!(entry.contains("\nComment=My App Service\n"));
+        assert!(entry.contains("\nExec=\"/opt/my app/\\\\$bin/app\"\n"));
+        assert!(entry.contains("\nPath=/opt/my app\n"));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::State;
+
+/// Receives status messages from the installation manager, which otherwise stays silent
+///
+/// Every method has an empty default so implementors only override what they show. Progress
+/// fractions are still broadcast through `subscribe()`.
+pub trait InstallObserver: Send + Sync {
+    /// The running operation entered a new phase
+    fn on_phase(&self, _state: State) {}
+
+    /// A status message, e.g. "Downloading app.tar.gz..."
+    fn on_message(&self, _message: &str) {}
+
+    /// A problem that doesn't fail the operation, e.g. a request that will be retried
+    fn on_warning(&self, _message: &str) {}
+}
+
+/// Observer that prints messages to stdout and warnings to stderr, for command-line tools
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleObserver;
+
+impl InstallObserver for ConsoleObserver {
+    fn on_message(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn on_warning(&self, message: &str) {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// The manager's observer, if one is set, and the last phase reported to it
+///
+/// Messages and warnings are also emitted as `tracing` events, inside the span of the phase
+/// that produced them. They are only formatted when there is an observer or a subscriber to
+/// receive them.
+#[derive(Clone, Default)]
+pub(crate) struct Observer {
+    observer: Option<Arc<dyn InstallObserver>>,
+    last_phase: Arc<Mutex<Option<State>>>,
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observer").field("set", &self.observer.is_some()).finish()
+    }
+}
+
+impl Observer {
+    pub fn new(observer: Arc<dyn InstallObserver>) -> Self {
+        Self {
+            observer: Some(observer),
+            last_phase: Arc::default(),
+        }
+    }
+
+    pub fn message(&self, message: fmt::Arguments<'_>) {
+        tracing::info!("{}", message);
+        if let Some(observer) = &self.observer {
+            observer.on_message(&message.to_string());
+        }
+    }
+
+    pub fn warning(&self, message: fmt::Arguments<'_>) {
+        tracing::warn!("{}", message);
+        if let Some(observer) = &self.observer {
+            observer.on_warning(&message.to_string());
+        }
+    }
+
+    /// Report `state` if it differs from the last phase reported
+    pub fn phase(&self, state: &State) {
+        let Some(observer) = &self.observer else {
+            return;
+        };
+
+        let mut last_phase = self.last_phase.lock().unwrap();
+        if last_phase.as_ref() != Some(state) {
+            *last_phase = Some(state.clone());
+            drop(last_phase);
+            observer.on_phase(state.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl InstallObserver for Recorder {
+        fn on_phase(&self, state: State) {
+            self.events.lock().unwrap().push(format!("phase {:?}", state));
+        }
+
+        fn on_message(&self, message: &str) {
+            self.events.lock().unwrap().push(message.to_string());
+        }
+    }
+
+    #[test]
+    fn test_phase_reported_once_per_change() {
+        let recorder = Arc::new(Recorder::default());
+        let observer = Observer::new(recorder.clone());
+
+        observer.phase(&State::Downloading);
+        observer.phase(&State::Downloading);
+        observer.message(format_args!("Extracting to {}...", "/opt/app"));
+        observer.phase(&State::Extracting);
+        observer.warning(format_args!("ignored by this observer"));
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            ["phase Downloading", "Extracting to /opt/app...", "phase Extracting"]
+        );
+
+        // Without an observer nothing is formatted or reported
+        Observer::default().message(format_args!("{}", "nobody listens"));
+    }
+}
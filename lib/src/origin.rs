@@ -0,0 +1,134 @@
+use semver::Version;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{command, InstallError, InstallationManager};
+
+/// How long a binary found in install_path gets to answer `--version`; one that doesn't know
+/// the flag may start its server instead, and is killed
+const VERSION_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Who put the application in install_path, as found by `installation_origin`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallationOrigin {
+    /// Installed by this library; the platform has its version recorded, or an install of ours
+    /// failed partway and left its files for the next install to replace
+    Managed,
+    /// install_path holds an executable but nothing is recorded, e.g. it came from a distro
+    /// package, a manual copy or an older layout this library doesn't recognize
+    Foreign {
+        executable: PathBuf,
+        /// What `<executable> --version` reported, if it reported a semantic version
+        version: Option<Version>,
+    },
+    /// Nothing is installed
+    None,
+}
+
+/// Error returned when install_path holds an installation this library didn't make
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<ForeignInstallation>()`.
+/// `install` refuses to overwrite such an installation and `update` and `uninstall` refuse to
+/// manage it; `install_with_options` with `force` installs over it.
+#[derive(Debug, Clone)]
+pub struct ForeignInstallation {
+    pub install_path: PathBuf,
+    pub executable: PathBuf,
+    pub version: Option<Version>,
+}
+
+impl fmt::Display for ForeignInstallation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' already holds ", self.install_path.display())?;
+        match &self.version {
+            Some(version) => write!(f, "version {} of ", version)?,
+            None => write!(f, "an installation of ")?,
+        }
+        write!(
+            f,
+            "'{}' that wasn't installed by this manager; remove it or install with `force` to replace it",
+            self.executable.display()
+        )
+    }
+}
+
+impl std::error::Error for ForeignInstallation {}
+
+impl InstallationManager {
+    /// Find out whether the application in install_path was installed by this library
+    ///
+    /// When the platform has no version recorded and no install of ours started in install_path,
+    /// it is searched for the configured binary (or any executable) and that is asked for its
+    /// `--version`. Running it means this can take a few seconds.
+    pub fn installation_origin(&self) -> Result<InstallationOrigin, InstallError> {
+        if self.installed_version()?.is_some() {
+            return Ok(InstallationOrigin::Managed);
+        }
+        // An interrupted extraction of ours, not yet registered, is left to be resumed
+        if !self.config.install_path.is_dir() || crate::extract_state::is_pending(&self.config.install_path) {
+            return Ok(InstallationOrigin::None);
+        }
+        // An install writes this record before anything else, and uninstall removes it
+        if self.config.get_created_dirs_path().is_file() {
+            return Ok(InstallationOrigin::Managed);
+        }
+        let Ok(executable) = self.find_installed_executable() else {
+            return Ok(InstallationOrigin::None);
+        };
+
+        let version = command::capture_command(
+            std::process::Command::new(&executable)
+                .arg("--version")
+                .current_dir(&self.config.install_path),
+            VERSION_QUERY_TIMEOUT,
+        )
+        .ok()
+        .and_then(|output| crate::parse_reported_version(&output.stdout).or_else(|| crate::parse_reported_version(&output.stderr)));
+
+        Ok(InstallationOrigin::Foreign { executable, version })
+    }
+
+    /// Fail with `ForeignInstallation` if install_path holds an installation this library didn't make
+    pub(crate) fn refuse_foreign(&self) -> Result<(), InstallError> {
+        match self.installation_origin()? {
+            InstallationOrigin::Foreign { executable, version } => Err(anyhow::Error::from(ForeignInstallation {
+                install_path: self.config.install_path.clone(),
+                executable,
+                version,
+            })
+            .into()),
+            InstallationOrigin::Managed | InstallationOrigin::None => Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::InstallationConfig;
+
+    #[test]
+    fn test_installation_origin_detects_foreign_install() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("myapp");
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "oim-test-origin".to_string())
+            .binary_name("myapp".to_string());
+        let manager = InstallationManager::new(config);
+        assert_eq!(manager.installation_origin().unwrap(), InstallationOrigin::None);
+
+        std::fs::create_dir_all(install_path.join("bin")).unwrap();
+        let executable = install_path.join("bin/myapp");
+        std::fs::write(&executable, "#!/bin/sh\necho \"myapp 2.3.1\"\n").unwrap();
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(
+            manager.installation_origin().unwrap(),
+            InstallationOrigin::Foreign { executable, version: Some(Version::new(2, 3, 1)) }
+        );
+        let err = manager.refuse_foreign().unwrap_err();
+        assert!(err.downcast_ref::<ForeignInstallation>().is_some());
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use semver::Version;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::command::{capture_command, run_command_with, COMMAND_TIMEOUT};
+use crate::service_manager::resolve_tool;
+use crate::{
+    channel_lock, Architecture, CancellationToken, ChannelLock, GitHubAsset, GitHubRelease, InstallationManager, State,
+};
+
+/// A distribution package format, installed through the host's package manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PackageKind {
+    /// Debian packages, installed with dpkg and apt-get
+    Deb,
+    /// RPM packages, installed with dnf or rpm
+    Rpm,
+}
+
+impl PackageKind {
+    fn extension(self) -> &'static str {
+        match self {
+            PackageKind::Deb => ".deb",
+            PackageKind::Rpm => ".rpm",
+        }
+    }
+
+    /// The package format an asset is in, judging by its name
+    pub fn of_asset(asset: &GitHubAsset) -> Option<Self> {
+        let name = asset.name.to_lowercase();
+        [PackageKind::Deb, PackageKind::Rpm].into_iter().find(|kind| name.ends_with(kind.extension()))
+    }
+
+    /// The format this host's package database holds, if it has dpkg or rpm
+    pub fn detect() -> Option<Self> {
+        if tool("dpkg").is_some() && Path::new("/var/lib/dpkg/status").is_file() {
+            Some(PackageKind::Deb)
+        } else if tool("rpm").is_some() && ["/var/lib/rpm", "/usr/lib/sysimage/rpm"].iter().any(|dir| Path::new(dir).is_dir()) {
+            Some(PackageKind::Rpm)
+        } else {
+            None
+        }
+    }
+}
+
+impl InstallationManager {
+    /// With `system_package` set, the `.deb` or `.rpm` asset for `arch` in `release`, if it
+    /// ships one in the format of this host's package manager
+    pub(crate) fn select_package_asset(&self, release: &GitHubRelease, arch: &Architecture) -> Option<GitHubAsset> {
+        if !self.config.system_package || !matches!(arch, Architecture::LinuxX64 | Architecture::LinuxArm64) {
+            return None;
+        }
+        let kind = PackageKind::detect()?;
+        release
+            .assets
+            .iter()
+            .filter(|asset| asset.is_uploaded() && PackageKind::of_asset(asset) == Some(kind))
+            .filter(|asset| self.config.asset_filter.as_ref().is_none_or(|filter| filter.is_match(&asset.name)))
+            .find(|asset| arch.match_score(&asset.name) > 0)
+            .cloned()
+    }
+
+    /// The package format this service is installed in, if the package manager has it
+    pub(crate) fn installed_package(&self) -> Option<PackageKind> {
+        if !self.config.system_package {
+            return None;
+        }
+        let kind = PackageKind::detect()?;
+        installed_version(kind, self.config.get_package_name()).ok().flatten().map(|_| kind)
+    }
+
+    /// Download a package asset and install it with the package manager, which also records
+    /// the version and (through the package's own scripts) sets up the service
+    pub(crate) async fn install_package_release(
+        &mut self,
+        release: &GitHubRelease,
+        asset: &GitHubAsset,
+        kind: PackageKind,
+        requested_lock: ChannelLock,
+        updating: bool,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        if !updating {
+            self.run_hook("pre-install", self.config.pre_install_hook.as_deref(), &release.tag_name)?;
+        }
+
+        // The package is deleted with the temp directory once installed
+        let temp_dir = self.create_temp_dir().await?;
+        let package_path = temp_dir.path().join(asset.local_file_name());
+        self.observer.message(format_args!("Downloading {}...", asset.name));
+        if let Err(e) = self.fetch_asset(asset, &package_path, cancel).await {
+            self.keep_partial_download(temp_dir, asset, &e);
+            return Err(e);
+        }
+        self.verify_signature(release, asset, &package_path).await?;
+        cancel.check()?;
+
+        self.broadcast_progress(State::Installing, 0.0);
+        self.observer.message(format_args!("Installing {} with the package manager...", asset.name));
+        install(kind, &package_path)?;
+        // apt-get may have resolved a failed install by removing the package again
+        if installed_version(kind, self.config.get_package_name())?.is_none() {
+            anyhow::bail!(
+                "Package '{}' is not installed after installing {}. Set package_name if the package is named differently.",
+                self.config.get_package_name(),
+                asset.name
+            );
+        }
+        self.broadcast_progress(State::Installing, 1.0);
+
+        self.start_and_verify_service()?;
+        if let Some(check) = &self.config.health_check {
+            self.observer.message(format_args!("Waiting for {} to pass its health check ({})...", self.config.service_name, check.probe));
+            crate::health::wait_until_healthy(&self.config.service_name, check, self.config.proxy.as_deref(), cancel).await?;
+        }
+        self.run_hook("post-install", self.config.post_install_hook.as_deref(), &release.tag_name)?;
+
+        let lock_path = self.config.get_channel_lock_path();
+        if channel_lock::read(&lock_path)?.is_none() {
+            channel_lock::write(&lock_path, &requested_lock)?;
+        }
+
+        self.current_version = Some(Version::parse(release.tag_name.trim_start_matches('v'))?);
+        self.is_installed = true;
+        if !updating {
+            self.observer.message(format_args!("Installation complete!"));
+        }
+        Ok(())
+    }
+
+    /// Remove the package with the package manager, whose scripts stop and remove the service
+    pub(crate) fn uninstall_package(&mut self, kind: PackageKind) -> Result<()> {
+        let name = self.config.get_package_name();
+        self.observer.message(format_args!("Removing package {}...", name));
+        remove(kind, name)?;
+
+        // A fresh install afterwards may use any source
+        let _ = std::fs::remove_file(self.config.get_channel_lock_path());
+        self.is_installed = false;
+        self.current_version = None;
+
+        self.observer.message(format_args!("Uninstall complete!"));
+        Ok(())
+    }
+}
+
+/// The version of package `name` the package database has installed
+pub(crate) fn installed_version(kind: PackageKind, name: &str) -> Result<Option<Version>> {
+    let output = match kind {
+        PackageKind::Deb => {
+            let Some(dpkg_query) = tool("dpkg-query") else {
+                return Ok(None);
+            };
+            capture_command(Command::new(dpkg_query).args(["-W", "-f=${db:Status-Status} ${Version}", name]), COMMAND_TIMEOUT)?
+        }
+        PackageKind::Rpm => {
+            let Some(rpm) = tool("rpm") else {
+                return Ok(None);
+            };
+            capture_command(Command::new(rpm).args(["-q", "--qf", "installed %{VERSION}", name]), COMMAND_TIMEOUT)?
+        }
+    };
+    if !output.status.success() {
+        return Ok(None);
+    }
+    // dpkg keeps removed packages whose configuration files are still around
+    Ok(output
+        .stdout
+        .trim()
+        .strip_prefix("installed ")
+        .and_then(|version| parse_package_version(kind, version)))
+}
+
+/// Install the package at `path`, pulling in its dependencies
+fn install(kind: PackageKind, path: &Path) -> Result<()> {
+    match kind {
+        PackageKind::Deb => {
+            let dpkg = tool("dpkg").context("dpkg was not found on PATH")?;
+            if run_command_with(apt_command(&dpkg).arg("-i").arg(path), COMMAND_TIMEOUT, 0).is_ok() {
+                return Ok(());
+            }
+            // dpkg leaves a package with missing dependencies unconfigured; apt-get fetches them and finishes it
+            let apt_get = tool("apt-get").context("dpkg could not install the package and apt-get was not found to fetch its dependencies")?;
+            run_command_with(apt_command(&apt_get).args(["install", "-f", "-y"]), COMMAND_TIMEOUT, 0)?;
+        }
+        PackageKind::Rpm => match tool("dnf") {
+            Some(dnf) => {
+                run_command_with(Command::new(dnf).args(["install", "-y"]).arg(path), COMMAND_TIMEOUT, 0)?;
+            }
+            None => {
+                let rpm = tool("rpm").context("rpm was not found on PATH")?;
+                run_command_with(Command::new(rpm).args(["-U", "--replacepkgs"]).arg(path), COMMAND_TIMEOUT, 0)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Remove the installed package `name`
+fn remove(kind: PackageKind, name: &str) -> Result<()> {
+    match kind {
+        PackageKind::Deb => match tool("apt-get") {
+            Some(apt_get) => run_command_with(apt_command(&apt_get).args(["remove", "-y", name]), COMMAND_TIMEOUT, 0)?,
+            None => run_command_with(Command::new(tool("dpkg").context("dpkg was not found on PATH")?).args(["-r", name]), COMMAND_TIMEOUT, 0)?,
+        },
+        PackageKind::Rpm => match tool("dnf") {
+            Some(dnf) => run_command_with(Command::new(dnf).args(["remove", "-y", name]), COMMAND_TIMEOUT, 0)?,
+            None => run_command_with(Command::new(tool("rpm").context("rpm was not found on PATH")?).args(["-e", name]), COMMAND_TIMEOUT, 0)?,
+        },
+    };
+    Ok(())
+}
+
+/// A dpkg or apt-get command that never stops to ask questions
+fn apt_command(program: &Path) -> Command {
+    let mut command = Command::new(program);
+    command.env("DEBIAN_FRONTEND", "noninteractive");
+    command
+}
+
+fn tool(name: &str) -> Option<PathBuf> {
+    resolve_tool(name, None, std::env::var_os("PATH").as_deref()).ok()
+}
+
+/// Turn a package version such as `1:2.4.0~beta1-1ubuntu2` (Debian) or `2.4.0` (RPM) into semver
+fn parse_package_version(kind: PackageKind, version: &str) -> Option<Version> {
+    // Drop the epoch, and the Debian revision after the last '-'
+    let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+    let version = match kind {
+        PackageKind::Deb => version.rsplit_once('-').map_or(version, |(upstream, _)| upstream),
+        PackageKind::Rpm => version,
+    };
+    Version::parse(&version.trim_start_matches('v').replacen('~', "-", 1))
+        .ok()
+        .or_else(|| crate::parse_reported_version(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_version() {
+        assert_eq!(parse_package_version(PackageKind::Deb, "1:2.4.0~beta1-1ubuntu2"), Version::parse("2.4.0-beta1").ok());
+        assert_eq!(parse_package_version(PackageKind::Deb, "2.4.0"), Some(Version::new(2, 4, 0)));
+        assert_eq!(parse_package_version(PackageKind::Rpm, "2.4.0"), Some(Version::new(2, 4, 0)));
+        assert_eq!(parse_package_version(PackageKind::Deb, "unversioned"), None);
+    }
+
+    #[test]
+    fn test_package_kind_of_asset() {
+        let asset = |name: &str| GitHubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+            size: 1024,
+            digest: None,
+            content_type: None,
+            state: None,
+            updated_at: None,
+        };
+        assert_eq!(PackageKind::of_asset(&asset("myapp_1.2.0_amd64.deb")), Some(PackageKind::Deb));
+        assert_eq!(PackageKind::of_asset(&asset("myapp-1.2.0-1.x86_64.rpm")), Some(PackageKind::Rpm));
+        assert_eq!(PackageKind::of_asset(&asset("myapp-linux-x64.tar.gz")), None);
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(unix)]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::path::Path;
+
+/// Unix modes applied across the install tree by `normalize_permissions`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePermissionPolicy {
+    /// Mode for regular files (defaults to 0o644)
+    pub file_mode: u32,
+    /// Mode for directories (defaults to 0o755)
+    pub dir_mode: u32,
+    /// Mode for executables (defaults to 0o755)
+    pub executable_mode: u32,
+    /// Glob patterns, relative to install_path, for files that must be executable (e.g. `bin/*`)
+    ///
+    /// The configured binary and files that already have an execute bit are always treated as executables.
+    #[serde(default)]
+    pub executable_globs: Vec<String>,
+}
+
+impl Default for FilePermissionPolicy {
+    fn default() -> Self {
+        Self {
+            file_mode: 0o644,
+            dir_mode: 0o755,
+            executable_mode: 0o755,
+            executable_globs: Vec::new(),
+        }
+    }
+}
+
+impl FilePermissionPolicy {
+    /// Create the default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the mode for regular files
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.file_mode = mode;
+        self
+    }
+
+    /// Set the mode for directories
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.dir_mode = mode;
+        self
+    }
+
+    /// Set the mode for executables
+    pub fn executable_mode(mut self, mode: u32) -> Self {
+        self.executable_mode = mode;
+        self
+    }
+
+    /// Treat files matching the given glob pattern as executables
+    pub fn executable_glob(mut self, pattern: String) -> Self {
+        self.executable_globs.push(pattern);
+        self
+    }
+}
+
+/// Re-apply the policy to every file and directory under `root`
+///
+/// `executables` lists paths that must be executable regardless of their current mode.
+/// Symlinks are left untouched.
+#[cfg(unix)]
+pub(crate) fn normalize_tree(root: &Path, policy: &FilePermissionPolicy, executables: &[std::path::PathBuf]) -> Result<()> {
+    let patterns = policy
+        .executable_globs
+        .iter()
+        .map(|g| glob::Pattern::new(g).context(format!("Invalid executable glob '{}'", g)))
+        .collect::<Result<Vec<_>>>()?;
+
+    set_mode(root, policy.dir_mode)?;
+    normalize_dir(root, root, policy, &patterns, executables)
+}
+
+#[cfg(unix)]
+fn normalize_dir(
+    root: &Path,
+    dir: &Path,
+    policy: &FilePermissionPolicy,
+    patterns: &[glob::Pattern],
+    executables: &[std::path::PathBuf],
+) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            set_mode(&path, policy.dir_mode)?;
+            normalize_dir(root, &path, policy, patterns, executables)?;
+        } else if file_type.is_file() {
+            let relative = path.strip_prefix(root)?;
+            let is_executable = entry.metadata()?.permissions().mode() & 0o111 != 0
+                || executables.iter().any(|e| e == &path)
+                || patterns.iter().any(|p| p.matches_path_with(relative, options));
+
+            let mode = if is_executable { policy.executable_mode } else { policy.file_mode };
+            set_mode(&path, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .context(format!("Failed to set permissions on '{}'", path.display()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn write_with_mode(root: &Path, relative: &str, mode: u32) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"data").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn mode_of(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn test_normalize_mixed_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        write_with_mode(&root, "server", 0o600);
+        write_with_mode(&root, "bin/helper", 0o700);
+        write_with_mode(&root, "scripts/start.sh", 0o600);
+        write_with_mode(&root, "config/server.yml", 0o666);
+        write_with_mode(&root, "data/world.db", 0o600);
+        std::fs::set_permissions(root.join("config"), std::fs::Permissions::from_mode(0o777)).unwrap();
+
+        let policy = FilePermissionPolicy::new()
+            .file_mode(0o640)
+            .dir_mode(0o750)
+            .executable_mode(0o750)
+            .executable_glob("scripts/*.sh".to_string());
+        normalize_tree(&root, &policy, &[root.join("server")]).unwrap();
+
+        assert_eq!(mode_of(&root), 0o750);
+        assert_eq!(mode_of(&root.join("config")), 0o750);
+        assert_eq!(mode_of(&root.join("server")), 0o750);
+        assert_eq!(mode_of(&root.join("bin/helper")), 0o750);
+        assert_eq!(mode_of(&root.join("scripts/start.sh")), 0o750);
+        assert_eq!(mode_of(&root.join("config/server.yml")), 0o640);
+        assert_eq!(mode_of(&root.join("data/world.db")), 0o640);
+    }
+
+    #[test]
+    fn test_invalid_executable_glob_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = FilePermissionPolicy::new().executable_glob("[".to_string());
+        assert!(normalize_tree(dir.path(), &policy, &[]).is_err());
+    }
+}
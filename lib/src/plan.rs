@@ -0,0 +1,60 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::ArchiveFormat;
+
+/// Which operation a plan describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlannedOperation {
+    Install,
+    Update,
+}
+
+/// What an install or update would do, worked out without writing files or touching the service
+///
+/// Returned by `plan_install`/`plan_update` so a GUI can show it before the user confirms, and
+/// printed by `install`/`update` in dry-run mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallPlan {
+    pub operation: PlannedOperation,
+    /// Tag of the release that would be installed
+    pub release_tag: String,
+    /// Version installed now, for updates
+    pub current_version: Option<Version>,
+    pub asset_name: String,
+    pub download_url: String,
+    /// Size of the download in bytes, as reported by GitHub
+    pub download_size: u64,
+    /// None if the asset's format couldn't be detected
+    pub archive_format: Option<ArchiveFormat>,
+    pub install_path: PathBuf,
+    /// Where the current installation would be backed up before an update, if rollback is enabled
+    pub backup_path: Option<PathBuf>,
+    /// Service manager commands that would run, in order
+    pub service_commands: Vec<String>,
+}
+
+impl fmt::Display for InstallPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.operation, &self.current_version) {
+            (PlannedOperation::Update, Some(current)) => writeln!(f, "Update {} -> {}", current, self.release_tag)?,
+            (PlannedOperation::Update, None) => writeln!(f, "Update to {}", self.release_tag)?,
+            (PlannedOperation::Install, _) => writeln!(f, "Install {}", self.release_tag)?,
+        }
+        writeln!(f, "  Download: {} ({} bytes)", self.download_url, self.download_size)?;
+        match self.archive_format {
+            Some(format) => writeln!(f, "  Archive:  {} ({:?})", self.asset_name, format)?,
+            None => writeln!(f, "  Archive:  {} (unknown format)", self.asset_name)?,
+        }
+        writeln!(f, "  Extract to: {}", self.install_path.display())?;
+        if let Some(backup_path) = &self.backup_path {
+            writeln!(f, "  Back up to: {}", backup_path.display())?;
+        }
+        for command in &self.service_commands {
+            writeln!(f, "  Run: {}", command)?;
+        }
+        Ok(())
+    }
+}
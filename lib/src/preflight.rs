@@ -0,0 +1,413 @@
+use anyhow::Result;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{InstallError, InstallationConfig};
+
+/// Error returned when the target filesystem has too few free inodes for the archive's entries
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<InsufficientInodes>()`.
+#[derive(Debug, Clone)]
+pub struct InsufficientInodes {
+    pub path: PathBuf,
+    pub required: u64,
+    pub available: u64,
+}
+
+impl fmt::Display for InsufficientInodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Not enough free inodes on the filesystem holding '{}': the archive has {} entries but only {} inodes are available. Free up files on that filesystem and try again.",
+            self.path.display(),
+            self.required,
+            self.available
+        )
+    }
+}
+
+impl std::error::Error for InsufficientInodes {}
+
+/// Error returned when the install path can't be installed to
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<InvalidInstallPath>()`.
+#[derive(Debug, Clone)]
+pub struct InvalidInstallPath {
+    pub path: PathBuf,
+    /// What is wrong with the path, e.g. "it is a file, not a directory"
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidInstallPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot install to '{}': {}", self.path.display(), self.reason)
+    }
+}
+
+impl std::error::Error for InvalidInstallPath {}
+
+impl InstallationConfig {
+    /// Check that `install_path` can be installed to before anything is downloaded
+    ///
+    /// The path must be absolute (or a relative path to a directory that exists), must not be a
+    /// file, and its parent directory must exist and be writable, as must the directory itself
+    /// if it exists. System directories such as `/`, `/usr` or `C:\Windows`, drive roots and the
+    /// home directory are refused, as uninstalling removes the install directory. Fails with
+    /// `InvalidInstallPath`; `install` calls this first, and a GUI can call it on a chosen folder
+    /// to show the problem inline.
+    pub fn validate(&self) -> Result<(), InstallError> {
+        Ok(validate_install_path(&self.install_path)?)
+    }
+}
+
+fn validate_install_path(path: &Path) -> Result<()> {
+    let invalid = |reason: String| InvalidInstallPath { path: path.to_path_buf(), reason };
+
+    if path.as_os_str().is_empty() {
+        return Err(invalid("no install path was given".to_string()).into());
+    }
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::fs::canonicalize(path)
+            .map_err(|_| invalid("it must be an absolute path".to_string()))?
+    };
+
+    if path.exists() && !path.is_dir() {
+        return Err(invalid("it is a file, not a directory".to_string()).into());
+    }
+    let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) else {
+        return Err(invalid("it is the root of a drive or filesystem".to_string()).into());
+    };
+    if !parent.is_dir() {
+        return Err(invalid(format!("the parent directory '{}' does not exist", parent.display())).into());
+    }
+    if is_system_directory(&path) {
+        return Err(invalid("it is a system directory, which uninstalling would delete".to_string()).into());
+    }
+
+    let writable_dir = if path.is_dir() { path.as_path() } else { parent };
+    if !is_writable(writable_dir) {
+        return Err(invalid(format!("'{}' is not writable; choose another location or run with elevated privileges", writable_dir.display())).into());
+    }
+
+    Ok(())
+}
+
+/// Whether `path` is a directory the operating system or users' files live in
+fn is_system_directory(path: &Path) -> bool {
+    // Compare resolved paths, so a symlink or a trailing separator can't slip past
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from);
+
+    #[cfg(windows)]
+    {
+        let lower = |path: &Path| path.to_string_lossy().trim_start_matches(r"\\?\").trim_end_matches('\\').to_lowercase();
+        let path = lower(&path);
+        let env_dir = |name: &str| std::env::var_os(name).map(|dir| lower(Path::new(&dir)));
+
+        // Anything under Windows itself, and the top of the shared locations
+        if let Some(system_root) = env_dir("SystemRoot")
+            && (path == system_root || path.starts_with(&format!("{}\\", system_root)))
+        {
+            return true;
+        }
+        ["ProgramFiles", "ProgramFiles(x86)", "ProgramData", "PUBLIC"]
+            .iter()
+            .filter_map(|name| env_dir(name))
+            .chain(home.as_deref().map(lower))
+            .any(|dir| dir == path)
+    }
+
+    #[cfg(not(windows))]
+    {
+        const SYSTEM_DIRS: &[&str] = &[
+            "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/lib64", "/opt", "/proc", "/root", "/run", "/sbin",
+            "/srv", "/sys", "/tmp", "/usr", "/usr/bin", "/usr/lib", "/usr/local", "/usr/local/bin", "/usr/sbin", "/var",
+            "/Applications", "/Library", "/System", "/Users", "/private", "/private/var",
+        ];
+        SYSTEM_DIRS.iter().any(|dir| path == Path::new(dir)) || home.is_some_and(|home| path == home)
+    }
+}
+
+/// Whether a file can be created in the directory `dir`
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".oim-write-test-{}", std::process::id()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Make sure the filesystem holding `path` can create `entry_count` more files (Linux only)
+#[cfg(target_os = "linux")]
+pub(crate) fn check_inodes(path: &Path, entry_count: u64) -> Result<()> {
+    if let Some(available) = crate::nix::available_inodes(path)?
+        && available < entry_count
+    {
+        return Err(InsufficientInodes {
+            path: path.to_path_buf(),
+            required: entry_count,
+            available,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Make sure the filesystem holding `path` can create `entry_count` more files (Linux only)
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn check_inodes(_path: &Path, _entry_count: u64) -> Result<()> {
+    Ok(())
+}
+
+/// Error returned when a volume doesn't have enough free space for the download or extraction
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<InsufficientSpace>()`.
+#[derive(Debug, Clone)]
+pub struct InsufficientSpace {
+    pub path: PathBuf,
+    /// Bytes the operation expects to need on that volume
+    pub needed: u64,
+    pub available: u64,
+}
+
+impl fmt::Display for InsufficientSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Not enough free disk space on the volume holding '{}': about {} bytes are needed but only {} are available. Free up space, or set min_free_bytes if the archive compresses less than expected.",
+            self.path.display(),
+            self.needed,
+            self.available
+        )
+    }
+}
+
+impl std::error::Error for InsufficientSpace {}
+
+/// Make sure each volume has room for what will be written to it
+///
+/// `requirements` pairs a path (which need not exist yet) with the bytes to be written under it.
+/// Paths on the same volume are added together. Volumes whose free space can't be determined
+/// are skipped.
+pub(crate) fn check_space(requirements: &[(&Path, u64)]) -> Result<()> {
+    let mut volumes: Vec<(PathBuf, u64)> = Vec::new();
+    for &(path, bytes) in requirements {
+        let existing = existing_ancestor(path);
+        match volumes.iter_mut().find(|(other, _)| same_volume(other, &existing)) {
+            Some((_, needed)) => *needed = needed.saturating_add(bytes),
+            None => volumes.push((existing, bytes)),
+        }
+    }
+
+    for (path, needed) in volumes {
+        if let Some(available) = available_space(&path)?
+            && available < needed
+        {
+            return Err(InsufficientSpace { path, needed, available }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// The deepest ancestor of `path` (or `path` itself) that exists
+fn existing_ancestor(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(unix)]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    // Compare drive letters or UNC shares
+    let root = |path: &Path| path.components().next().map(|c| c.as_os_str().to_string_lossy().to_lowercase());
+    root(a).is_some() && root(a) == root(b)
+}
+
+/// Free bytes available to unprivileged users on the volume holding `path`
+#[cfg(unix)]
+pub(crate) fn available_space(path: &Path) -> Result<Option<u64>> {
+    use anyhow::Context;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .context("Path contains a null byte")?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: c_path is a valid C string and stat a properly sized out-parameter
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context(format!("Failed to query free space for '{}'", path.display()));
+    }
+
+    Ok(Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64)))
+}
+
+/// Free bytes available to the current user on the volume holding `path`
+#[cfg(target_os = "windows")]
+pub(crate) fn available_space(path: &Path) -> Result<Option<u64>> {
+    crate::win::available_space(path).map(Some)
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+pub(crate) fn available_space(_path: &Path) -> Result<Option<u64>> {
+    Ok(None)
+}
+
+/// Reserve `len` bytes for a file about to be written, so a full disk fails up front and the
+/// data lands contiguously
+///
+/// The file's length is left unchanged, so an interrupted download can still be resumed from
+/// the bytes actually written. Filesystems or platforms that can't pre-allocate are skipped
+/// silently; running out of space is reported as an error.
+pub(crate) fn preallocate(file: &std::fs::File, len: u64) -> std::io::Result<()> {
+    if len == 0 {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let Ok(len) = libc::off_t::try_from(len) else {
+            return Ok(());
+        };
+
+        // SAFETY: fd is a valid open file descriptor borrowed from `file`
+        if unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len) } != 0 {
+            let err = std::io::Error::last_os_error();
+            if matches!(err.raw_os_error(), Some(libc::ENOSPC) | Some(libc::EFBIG)) {
+                return Err(err);
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use windows::Win32::Foundation::{ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL, HANDLE};
+        use windows::Win32::Storage::FileSystem::{FileAllocationInfo, SetFileInformationByHandle, FILE_ALLOCATION_INFO};
+
+        let Ok(size) = i64::try_from(len) else {
+            return Ok(());
+        };
+        let info = FILE_ALLOCATION_INFO { AllocationSize: size };
+
+        // SAFETY: the handle is borrowed from `file` and `info` outlives the call
+        let result = unsafe {
+            SetFileInformationByHandle(
+                HANDLE(file.as_raw_handle()),
+                FileAllocationInfo,
+                &info as *const FILE_ALLOCATION_INFO as *const std::ffi::c_void,
+                std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+            )
+        };
+
+        if let Err(err) = result
+            && (err.code() == ERROR_DISK_FULL.to_hresult() || err.code() == ERROR_HANDLE_DISK_FULL.to_hresult())
+        {
+            return Err(std::io::Error::other(err));
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    let _ = file;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_inodes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_inodes(dir.path(), 1).is_ok());
+
+        #[cfg(target_os = "linux")]
+        {
+            if crate::nix::available_inodes(dir.path()).unwrap().is_some() {
+                let err = check_inodes(dir.path(), u64::MAX).unwrap_err();
+                assert!(err.downcast_ref::<InsufficientInodes>().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_install_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let reason = |path: &Path| {
+            let err = validate_install_path(path).unwrap_err();
+            err.downcast_ref::<InvalidInstallPath>().unwrap().reason.clone()
+        };
+
+        assert!(validate_install_path(&dir.path().join("app")).is_ok());
+        assert!(validate_install_path(dir.path()).is_ok());
+
+        assert!(reason(Path::new("")).contains("no install path"));
+        assert!(reason(Path::new("relative/app")).contains("absolute"));
+        assert!(reason(&dir.path().join("missing/app")).contains("parent directory"));
+
+        let file = dir.path().join("app.txt");
+        std::fs::write(&file, b"").unwrap();
+        assert!(reason(&file).contains("not a directory"));
+
+        #[cfg(unix)]
+        {
+            assert!(reason(Path::new("/")).contains("root"));
+            assert!(reason(Path::new("/usr/")).contains("system directory"));
+        }
+        #[cfg(windows)]
+        {
+            assert!(reason(Path::new(r"C:\")).contains("root"));
+            assert!(reason(Path::new(r"C:\Windows\System32")).contains("system directory"));
+        }
+    }
+
+    #[test]
+    fn test_check_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not/created/yet");
+        assert!(check_space(&[(dir.path(), 1), (&missing, 1)]).is_ok());
+
+        if available_space(dir.path()).unwrap().is_some() {
+            let err = check_space(&[(&missing, u64::MAX)]).unwrap_err();
+            let space = err.downcast_ref::<InsufficientSpace>().unwrap();
+            assert_eq!(space.path, dir.path());
+
+            // Two halves that fit separately don't fit together on the same volume
+            let half = available_space(dir.path()).unwrap().unwrap() / 2 + 4096;
+            assert!(check_space(&[(dir.path(), half)]).is_ok());
+            assert!(check_space(&[(dir.path(), half), (&missing, half)]).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_preallocate() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = std::fs::File::create(dir.path().join("asset.tar.gz")).unwrap();
+        preallocate(&file, 1 << 20).unwrap();
+
+        // Space is reserved without growing the file, so a partial download's length stays accurate
+        assert_eq!(file.metadata().unwrap().len(), 0);
+    }
+}
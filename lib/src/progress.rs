@@ -0,0 +1,82 @@
+use futures::Stream;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc, watch};
+
+use crate::{State, StateProgress};
+
+/// Delivers every progress update to the `subscribe` receivers, the `progress_stream` streams and
+/// the `watch_progress` value, shared by a manager and its clones
+#[derive(Debug, Clone)]
+pub(crate) struct ProgressSender {
+    broadcast: broadcast::Sender<StateProgress>,
+    latest: Arc<watch::Sender<StateProgress>>,
+    streams: Arc<Mutex<Vec<mpsc::UnboundedSender<StateProgress>>>>,
+}
+
+impl ProgressSender {
+    /// `capacity` is how many updates a `subscribe` receiver can fall behind before it lags
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            broadcast: broadcast::channel(capacity).0,
+            latest: Arc::new(watch::channel(StateProgress::new(State::Idle, 0.0)).0),
+            streams: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn send(&self, progress: StateProgress) {
+        if let Ok(mut streams) = self.streams.lock() {
+            // Drop the senders of streams that were dropped
+            streams.retain(|tx| tx.send(progress.clone()).is_ok());
+        }
+        self.latest.send_replace(progress.clone());
+        let _ = self.broadcast.send(progress);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StateProgress> {
+        self.broadcast.subscribe()
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.broadcast.receiver_count()
+    }
+
+    /// An unbounded stream of every update from now on, ending once the last clone of the
+    /// manager is dropped
+    pub fn stream(&self) -> impl Stream<Item = StateProgress> + Send + Unpin + 'static {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.push(tx);
+        }
+        futures::stream::poll_fn(move |cx| rx.poll_recv(cx))
+    }
+
+    pub fn watch(&self) -> watch::Receiver<StateProgress> {
+        self.latest.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_progress_stream_is_lossless() {
+        let sender = ProgressSender::new(2);
+        let stream = sender.stream();
+        let mut receiver = sender.subscribe();
+        let watch = sender.watch();
+        assert!(matches!(watch.borrow().state, State::Idle));
+
+        for percent in 0..=10 {
+            sender.send(StateProgress::new(State::Extracting, percent as f32 / 10.0));
+        }
+        assert!(matches!(receiver.try_recv(), Err(broadcast::error::TryRecvError::Lagged(_))));
+        assert_eq!(watch.borrow().progress, 1.0);
+
+        drop(sender);
+        let received: Vec<f32> = stream.map(|update| update.progress).collect().await;
+        assert_eq!(received.len(), 11);
+        assert_eq!(received[10], 1.0);
+    }
+}
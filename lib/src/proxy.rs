@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use std::fmt;
+
+/// Hosts a health check probe must reach directly, whatever the proxy settings
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+/// Error returned when a request couldn't connect to the proxy it was routed through
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<ProxyUnreachable>()`. It
+/// means the proxy, not GitHub, is unreachable or misconfigured; the operation's error is still
+/// classified as `InstallError::Network`.
+#[derive(Debug, Clone)]
+pub struct ProxyUnreachable {
+    /// The proxy URL
+    pub proxy: String,
+    /// Where the proxy came from: "the proxy setting" or the environment variable's name
+    pub source: String,
+}
+
+impl fmt::Display for ProxyUnreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to connect through the proxy {} (from {}). Check that the proxy address is right and the proxy is running",
+            self.proxy, self.source
+        )
+    }
+}
+
+impl std::error::Error for ProxyUnreachable {}
+
+/// Route every request of `builder` through `proxy`, if one is configured
+///
+/// Without a configured proxy reqwest already honors `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY`
+/// and `NO_PROXY`. A configured proxy still skips the hosts in `NO_PROXY`, and with
+/// `local_only` set neither ever applies to the local machine.
+pub(crate) fn apply(builder: reqwest::ClientBuilder, proxy: Option<&str>, local_only: bool) -> Result<reqwest::ClientBuilder> {
+    let no_proxy = || {
+        let from_env = env_var(&["NO_PROXY", "no_proxy"]).map(|(_, hosts)| hosts).unwrap_or_default();
+        let hosts = if local_only { format!("{},{}", from_env, LOCAL_HOSTS) } else { from_env };
+        reqwest::NoProxy::from_string(&hosts)
+    };
+
+    match proxy {
+        Some(proxy) => {
+            let proxy = reqwest::Proxy::all(proxy).context(format!("Invalid proxy URL '{}'", proxy))?;
+            Ok(builder.proxy(proxy.no_proxy(no_proxy())))
+        }
+        // Health checks only ever talk to this machine, so an environment proxy is left out
+        None if local_only => Ok(builder.no_proxy()),
+        None => Ok(builder),
+    }
+}
+
+/// The proxy a request to `url` goes through and where it was set, if any
+pub(crate) fn proxy_for(configured: Option<&str>, url: &str) -> Option<(String, String)> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let bypassed = env_var(&["NO_PROXY", "no_proxy"]).is_some_and(|(_, hosts)| bypasses(&hosts, host));
+    if bypassed {
+        return None;
+    }
+
+    if let Some(proxy) = configured {
+        return Some((proxy.to_string(), "the proxy setting".to_string()));
+    }
+    let names: &[&str] = if url.scheme() == "https" {
+        &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+    } else {
+        &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+    };
+    env_var(names).map(|(name, proxy)| (proxy, name.to_string()))
+}
+
+/// Turn a request that never reached `url` into `ProxyUnreachable` if it was sent through a proxy
+pub(crate) fn unreachable(error: &reqwest::Error, configured: Option<&str>, url: &str) -> Option<ProxyUnreachable> {
+    if !error.is_connect() {
+        return None;
+    }
+    proxy_for(configured, url).map(|(proxy, source)| ProxyUnreachable { proxy, source })
+}
+
+/// Whether the `NO_PROXY`-style list `hosts` excludes `host`, by exact name or domain suffix
+fn bypasses(hosts: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+    hosts.split(',').map(|entry| entry.trim().to_lowercase()).any(|entry| {
+        let domain = entry.trim_start_matches('.');
+        entry == "*" || (!domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain))))
+    })
+}
+
+/// The first of the environment variables `names` that is set and not empty
+fn env_var(names: &[&'static str]) -> Option<(&'static str, String)> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.trim().is_empty()).map(|value| (*name, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_proxy_matching() {
+        assert!(bypasses("example.com, .corp.local", "api.example.com"));
+        assert!(bypasses("example.com", "EXAMPLE.com"));
+        assert!(bypasses(".corp.local", "build.corp.local"));
+        assert!(bypasses("*", "github.com"));
+        assert!(!bypasses("example.com", "notexample.com"));
+        assert!(!bypasses("", "github.com"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_configured_proxy() {
+        // Nothing listens on a port that was just released
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let proxy = format!("http://127.0.0.1:{}", port);
+        let client = apply(reqwest::Client::builder(), Some(&proxy), false).unwrap().build().unwrap();
+
+        let error = client.get("http://github.invalid/").send().await.unwrap_err();
+        let unreachable = unreachable(&error, Some(&proxy), "http://github.invalid/").unwrap();
+        assert_eq!(unreachable.proxy, proxy);
+        assert!(unreachable.to_string().contains("the proxy setting"));
+
+        assert!(apply(reqwest::Client::builder(), Some("not a url"), false).is_err());
+    }
+}
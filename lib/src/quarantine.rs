@@ -0,0 +1,61 @@
+use anyhow::Result;
+use std::path::Path;
+
+/// Clear OS download-quarantine markers from every file under `root`, so the OS doesn't block
+/// the installed binaries from running
+///
+/// Removes the `com.apple.quarantine` xattr on macOS and the Mark-of-the-Web
+/// `Zone.Identifier` stream on Windows. Does nothing on other platforms.
+pub(crate) fn clear_quarantine(root: &Path) -> Result<()> {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        for entry in std::fs::read_dir(root)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                clear_quarantine(&entry.path())?;
+            } else if file_type.is_file() {
+                clear_file(&entry.path())?;
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let _ = root;
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn clear_file(path: &Path) -> Result<()> {
+    use anyhow::Context;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .context("Path contains a null byte")?;
+
+    let result = unsafe { libc::removexattr(c_path.as_ptr(), c"com.apple.quarantine".as_ptr(), libc::XATTR_NOFOLLOW) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() != Some(libc::ENOATTR) {
+            return Err(error).context(format!("Failed to clear quarantine on '{}'", path.display()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn clear_file(path: &Path) -> Result<()> {
+    use anyhow::Context;
+
+    let mut stream = path.as_os_str().to_os_string();
+    stream.push(":Zone.Identifier");
+
+    match std::fs::remove_file(&stream) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context(format!("Failed to clear Mark-of-the-Web on '{}'", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::GitHubRelease;
+
+/// The releases list from GitHub's API as last fetched, for serving repeated lookups from disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedReleases {
+    /// Repository the list belongs to, so a changed `github_repo` never reads another's releases
+    pub github_repo: String,
+    /// When GitHub last confirmed the list, either by sending it or with 304 Not Modified
+    pub fetched_at: DateTime<Utc>,
+    /// GitHub's `etag` for the list, sent back as `If-None-Match` once the cache is stale
+    pub etag: Option<String>,
+    pub releases: Vec<GitHubRelease>,
+}
+
+impl CachedReleases {
+    /// Whether the list was confirmed less than `ttl` ago
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        let age = Utc::now().signed_duration_since(self.fetched_at);
+        age.to_std().is_ok_and(|age| age < ttl)
+    }
+}
+
+/// Read the cache file at `path` if it holds the releases of `github_repo`
+///
+/// A missing, unreadable or corrupted cache is treated as empty.
+pub(crate) fn read(path: &Path, github_repo: &str) -> Option<CachedReleases> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str::<CachedReleases>(&content)
+        .ok()
+        .filter(|cached| cached.github_repo == github_repo)
+}
+
+pub(crate) fn write(path: &Path, cached: &CachedReleases) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string(cached)?;
+    std::fs::write(path, content).context(format!("Failed to write release cache '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.releases.json");
+        let cached = CachedReleases {
+            github_repo: "owner/repo".to_string(),
+            fetched_at: Utc::now() - chrono::Duration::seconds(120),
+            etag: Some("W/\"abc\"".to_string()),
+            releases: Vec::new(),
+        };
+        write(&path, &cached).unwrap();
+
+        let loaded = read(&path, "owner/repo").unwrap();
+        assert_eq!(loaded.etag.as_deref(), Some("W/\"abc\""));
+        assert!(loaded.is_fresh(Duration::from_secs(300)));
+        assert!(!loaded.is_fresh(Duration::from_secs(60)));
+        assert!(read(&path, "owner/other").is_none());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(read(&path, "owner/repo").is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::cancel::CancellationToken;
+use crate::observer::Observer;
+
+/// Delay before the first retry; doubled for each one after that
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// An HTTP response whose status suggests trying again later (5xx, 408, 429)
+#[derive(Debug)]
+pub(crate) struct TransientStatus(pub String);
+
+impl fmt::Display for TransientStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TransientStatus {}
+
+/// Whether a response with this status is worth retrying
+pub(crate) fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Whether `error` is a timeout, dropped connection or transient server error
+pub(crate) fn is_transient(error: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+
+    error.chain().any(|e| {
+        if e.is::<TransientStatus>() {
+            return true;
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout() || e.is_connect() || e.is_request() || e.is_body()
+                || e.status().is_some_and(is_transient_status);
+        }
+        if let Some(e) = e.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        false
+    })
+}
+
+/// How long to wait before retry number `attempt` (starting at 1)
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// Run `operation`, retrying transient failures up to `retries` more times with exponential backoff
+///
+/// Each retry is reported to `observer` as a warning. Stops early with the last error once
+/// `cancel` is cancelled.
+pub(crate) async fn with_backoff<T, F, Fut>(
+    retries: u32,
+    cancel: &CancellationToken,
+    observer: &Observer,
+    mut operation: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if attempt < retries && !cancel.is_cancelled() && is_transient(&e) => {
+                attempt += 1;
+                let delay = backoff(attempt);
+                observer.warning(format_args!("{:#}; retrying in {:.1}s ({}/{})", e, delay.as_secs_f32(), attempt, retries));
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(4), Duration::from_secs(4));
+        assert_eq!(backoff(10), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_only_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result = with_backoff(3, &CancellationToken::new(), &Observer::default(), || async {
+            match calls.fetch_add(1, Ordering::Relaxed) {
+                0 => Err(anyhow::Error::from(TransientStatus("503".to_string()))),
+                1 => Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into()),
+                _ => Ok("done"),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = with_backoff(3, &CancellationToken::new(), &Observer::default(), || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(anyhow::anyhow!("404 Not Found"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
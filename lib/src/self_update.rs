@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+use crate::{ArchiveFormat, CancellationToken, InstallError, InstallationManager, checksum};
+
+impl InstallationManager {
+    /// Replace the running program at `current_exe` with the newest stable release's build of it
+    ///
+    /// The release asset must be a bare executable whose name starts with the current
+    /// executable's name, e.g. `installer-linux-x86_64` for `installer`. It is downloaded next to
+    /// `current_exe` and must match a published checksum (the GitHub digest or a checksum file)
+    /// and the configured signature, if any, before anything is replaced. On Unix the new file is
+    /// renamed over the old one; on Windows the running executable is renamed aside and deleted
+    /// on the next reboot. The new version takes effect the next time the program starts.
+    ///
+    /// Returns the version installed, or None when `current_exe` already matches the newest
+    /// release's checksum.
+    pub async fn self_update(&self, current_exe: &Path) -> Result<Option<Version>, InstallError> {
+        Ok(self.replace_current_exe(current_exe).await?)
+    }
+
+    async fn replace_current_exe(&self, current_exe: &Path) -> Result<Option<Version>> {
+        let stem = current_exe
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .context(format!("Invalid executable path: {}", current_exe.display()))?
+            .to_lowercase();
+
+        let release = self.fetch_latest_release().await?;
+        let asset = self.select_asset_with(&release, |asset| {
+            asset.format() == Some(ArchiveFormat::Binary) && asset.name.to_lowercase().starts_with(&stem)
+        })?;
+        let version = Version::parse(release.tag_name.trim_start_matches('v'))?;
+
+        let expected = match asset.checksum() {
+            Some(expected) => expected,
+            None => self.fetch_checksum_for(&release, &asset.name).await?.context(format!(
+                "Release {} publishes no checksum for '{}'; refusing to replace the running executable with an unverified download",
+                release.tag_name, asset.name
+            ))?,
+        };
+        if checksum::verify_file(current_exe, &asset.name, &expected).is_ok() {
+            return Ok(None);
+        }
+
+        self.observer.message(format_args!("Downloading {} {}...", asset.name, release.tag_name));
+        let download_path = sibling_path(current_exe, ".oim-new");
+        self.download_asset(&asset, &download_path, &CancellationToken::new()).await?;
+
+        let verified = async {
+            self.verify_checksum(&download_path, &asset.name, &expected)?;
+            self.verify_signature(&release, &asset, &download_path).await
+        }
+        .await;
+        if let Err(e) = verified {
+            let _ = std::fs::remove_file(&download_path);
+            return Err(e);
+        }
+
+        crate::set_executable(&download_path)?;
+        replace_executable(current_exe, &download_path)
+            .context(format!("Failed to replace '{}' with the downloaded update", current_exe.display()))?;
+
+        self.observer.message(format_args!("Updated {} to {}; restart it to use the new version", current_exe.display(), version));
+        Ok(Some(version))
+    }
+}
+
+/// `path` with `suffix` appended to its file name, in the same directory
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut sibling = path.as_os_str().to_owned();
+    sibling.push(suffix);
+    PathBuf::from(sibling)
+}
+
+/// Move `replacement` over `current_exe`, which may be the running executable
+#[cfg(not(target_os = "windows"))]
+fn replace_executable(current_exe: &Path, replacement: &Path) -> Result<()> {
+    // Renaming replaces the directory entry; the running process keeps its open copy
+    std::fs::rename(replacement, current_exe)?;
+    Ok(())
+}
+
+/// Move `replacement` over `current_exe`, which may be the running executable
+#[cfg(target_os = "windows")]
+fn replace_executable(current_exe: &Path, replacement: &Path) -> Result<()> {
+    // A running executable can't be overwritten or deleted, but it can be renamed aside
+    let aside = sibling_path(current_exe, ".oim-old");
+    let _ = std::fs::remove_file(&aside);
+    std::fs::rename(current_exe, &aside)?;
+    if let Err(e) = std::fs::rename(replacement, current_exe) {
+        let _ = std::fs::rename(&aside, current_exe);
+        return Err(e.into());
+    }
+    crate::win::schedule_delete_on_reboot(&aside)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let current_exe = dir.path().join("installer");
+        std::fs::write(&current_exe, b"old").unwrap();
+        let replacement = sibling_path(&current_exe, ".oim-new");
+        assert_eq!(replacement, dir.path().join("installer.oim-new"));
+        std::fs::write(&replacement, b"new").unwrap();
+
+        replace_executable(&current_exe, &replacement).unwrap();
+        assert_eq!(std::fs::read(&current_exe).unwrap(), b"new");
+        assert!(!replacement.exists());
+    }
+}
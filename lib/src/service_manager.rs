@@ -0,0 +1,73 @@
+use anyhow::Result;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error returned when the system's service manager can't be used, e.g. `systemctl` is missing
+/// or the process runs in a container without systemd as PID 1
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<ServiceManagerUnavailable>()`.
+#[derive(Debug, Clone)]
+pub struct ServiceManagerUnavailable {
+    /// The tool that could not be used (e.g. "systemctl")
+    pub tool: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ServiceManagerUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot manage services with '{}': {}", self.tool, self.reason)
+    }
+}
+
+impl std::error::Error for ServiceManagerUnavailable {}
+
+/// Resolve a service manager binary from an explicit path or by searching `path_var`
+/// (the value of PATH)
+pub(crate) fn resolve_tool(tool: &str, configured: Option<&Path>, path_var: Option<&OsStr>) -> Result<PathBuf> {
+    if let Some(path) = configured {
+        if path.is_file() {
+            return Ok(path.to_path_buf());
+        }
+        return Err(ServiceManagerUnavailable {
+            tool: tool.to_string(),
+            reason: format!("the configured path '{}' does not exist", path.display()),
+        }
+        .into());
+    }
+
+    path_var
+        .into_iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join(tool))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            ServiceManagerUnavailable {
+                tool: tool.to_string(),
+                reason: "it was not found on PATH. Install it or set its path in the configuration.".to_string(),
+            }
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("systemctl");
+        std::fs::write(&tool, b"").unwrap();
+
+        let path_var = std::env::join_paths([dir.path().join("missing"), dir.path().to_path_buf()]).unwrap();
+        assert_eq!(resolve_tool("systemctl", None, Some(&path_var)).unwrap(), tool);
+        assert_eq!(resolve_tool("systemctl", Some(&tool), None).unwrap(), tool);
+
+        let err = resolve_tool("systemctl", None, Some(OsStr::new(""))).unwrap_err();
+        assert_eq!(err.downcast_ref::<ServiceManagerUnavailable>().unwrap().tool, "systemctl");
+
+        let missing = dir.path().join("nope");
+        assert!(resolve_tool("systemctl", Some(&missing), Some(&path_var)).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use crate::checksum::decode_hex;
+
+/// How release assets are signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// Minisign signatures (`minisign -S`), checked against a minisign public key such as "RWQf6LRC..."
+    Minisign,
+    /// Detached Ed25519 signatures over the whole asset, stored raw (64 bytes) or as hex, checked
+    /// against a public key given as 64 hex digits
+    Ed25519,
+}
+
+/// Public key release assets must be signed with, and where their signatures are published
+///
+/// When configured, an asset without a valid signature asset next to it in the release is
+/// never extracted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureConfig {
+    pub scheme: SignatureScheme,
+    /// Minisign public key (the base64 line, or the whole `minisign.pub` file), or hex Ed25519 key
+    pub public_key: String,
+    /// Appended to an asset's name to get its signature asset's name (defaults to ".minisig" for
+    /// Minisign and ".sig" for Ed25519)
+    pub asset_suffix: String,
+}
+
+impl SignatureConfig {
+    /// Verify minisign signatures published as `<asset>.minisig`
+    pub fn minisign(public_key: String) -> Self {
+        Self {
+            scheme: SignatureScheme::Minisign,
+            public_key,
+            asset_suffix: ".minisig".to_string(),
+        }
+    }
+
+    /// Verify Ed25519 signatures published as `<asset>.sig`
+    pub fn ed25519(public_key: String) -> Self {
+        Self {
+            scheme: SignatureScheme::Ed25519,
+            public_key,
+            asset_suffix: ".sig".to_string(),
+        }
+    }
+
+    /// Set the suffix that turns an asset's name into its signature asset's name
+    pub fn asset_suffix(mut self, suffix: String) -> Self {
+        self.asset_suffix = suffix;
+        self
+    }
+
+    /// Name of the signature asset for `asset_name`
+    pub fn signature_asset_name(&self, asset_name: &str) -> String {
+        format!("{}{}", asset_name, self.asset_suffix)
+    }
+}
+
+/// Error returned when a release asset's signature is missing or doesn't verify against the
+/// configured public key
+///
+/// Retrieve it from an operation's error with `err.downcast_ref::<SignatureInvalid>()`.
+#[derive(Debug, Clone)]
+pub struct SignatureInvalid {
+    pub asset: String,
+    /// Why the signature was rejected
+    pub reason: String,
+}
+
+impl fmt::Display for SignatureInvalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Refusing to install '{}': {}", self.asset, self.reason)
+    }
+}
+
+impl std::error::Error for SignatureInvalid {}
+
+/// Check the file at `path` against the contents of its signature asset
+pub(crate) fn verify_file(config: &SignatureConfig, path: &Path, asset_name: &str, signature: &[u8]) -> Result<()> {
+    let invalid = |reason: String| SignatureInvalid {
+        asset: asset_name.to_string(),
+        reason,
+    };
+
+    match config.scheme {
+        SignatureScheme::Minisign => {
+            let public_key = minisign_verify::PublicKey::decode(&config.public_key)
+                .or_else(|_| minisign_verify::PublicKey::from_base64(config.public_key.trim()))
+                .map_err(|e| anyhow::anyhow!("The configured minisign public key is malformed: {}", e))?;
+            let signature = std::str::from_utf8(signature)
+                .ok()
+                .and_then(|text| minisign_verify::Signature::decode(text).ok())
+                .ok_or_else(|| invalid("its minisign signature is malformed".to_string()))?;
+
+            match public_key.verify_stream(&signature) {
+                Ok(mut verifier) => {
+                    let mut file = std::fs::File::open(path)
+                        .context(format!("Failed to open '{}' for signature verification", path.display()))?;
+                    let mut buffer = vec![0; 64 * 1024];
+                    loop {
+                        let read = file.read(&mut buffer)?;
+                        if read == 0 {
+                            break;
+                        }
+                        verifier.update(&buffer[..read]);
+                    }
+                    verifier.finalize()
+                }
+                // Signatures from older minisign versions sign the file itself rather than its hash
+                Err(minisign_verify::Error::UnsupportedLegacyMode) => {
+                    public_key.verify(&std::fs::read(path)?, &signature, true)
+                }
+                Err(e) => Err(e),
+            }
+            .map_err(|e| invalid(e.to_string()))?;
+        }
+        SignatureScheme::Ed25519 => {
+            let key_bytes = decode_hex::<32>(config.public_key.trim())
+                .context("The configured Ed25519 public key must be 64 hex digits")?;
+            let public_key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes)
+                .context("The configured Ed25519 public key is not a valid key")?;
+
+            let signature_bytes = match <[u8; 64]>::try_from(signature) {
+                Ok(raw) => raw,
+                Err(_) => std::str::from_utf8(signature)
+                    .ok()
+                    .and_then(|text| decode_hex::<64>(text.trim()))
+                    .ok_or_else(|| invalid("its Ed25519 signature is neither 64 raw bytes nor 128 hex digits".to_string()))?,
+            };
+
+            let content = std::fs::read(path)
+                .context(format!("Failed to read '{}' for signature verification", path.display()))?;
+            public_key
+                .verify_strict(&content, &ed25519_dalek::Signature::from_bytes(&signature_bytes))
+                .map_err(|_| invalid("the signature doesn't match the configured public key".to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minisign_signature() {
+        // Test vector from the minisign-verify crate: a prehashed signature of "test"
+        let config = SignatureConfig::minisign("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3".to_string());
+        let signature = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+        assert_eq!(config.signature_asset_name("app.tar.gz"), "app.tar.gz.minisig");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.tar.gz");
+        std::fs::write(&path, b"test").unwrap();
+        verify_file(&config, &path, "app.tar.gz", signature.as_bytes()).unwrap();
+
+        std::fs::write(&path, b"tampered").unwrap();
+        let err = verify_file(&config, &path, "app.tar.gz", signature.as_bytes()).unwrap_err();
+        assert!(err.downcast_ref::<SignatureInvalid>().is_some());
+
+        let err = verify_file(&config, &path, "app.tar.gz", b"not a signature").unwrap_err();
+        assert!(err.downcast_ref::<SignatureInvalid>().is_some());
+    }
+
+    #[test]
+    fn test_ed25519_signature() {
+        use ed25519_dalek::Signer;
+
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key: String = signing_key.verifying_key().as_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        let config = SignatureConfig::ed25519(public_key);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.zip");
+        std::fs::write(&path, b"release contents").unwrap();
+        let signature = signing_key.sign(b"release contents").to_bytes();
+        let hex_signature: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+
+        verify_file(&config, &path, "app.zip", &signature).unwrap();
+        verify_file(&config, &path, "app.zip", format!("{}\n", hex_signature).as_bytes()).unwrap();
+
+        std::fs::write(&path, b"release contents, modified").unwrap();
+        let err = verify_file(&config, &path, "app.zip", &signature).unwrap_err();
+        assert!(err.downcast_ref::<SignatureInvalid>().is_some());
+    }
+}
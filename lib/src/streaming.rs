@@ -0,0 +1,171 @@
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc::Receiver;
+
+use crate::archive::ExtractionBudget;
+use crate::cancel::CancellationToken;
+use crate::extract_state::ExtractState;
+
+/// Blocking reader over chunks sent from an async download loop
+///
+/// Reaches end of stream once the sending side is dropped.
+pub(crate) struct ChannelReader {
+    rx: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    consumed: Arc<AtomicU64>,
+}
+
+impl ChannelReader {
+    pub fn new(rx: Receiver<Vec<u8>>) -> Self {
+        Self { rx, chunk: Vec::new(), pos: 0, consumed: Arc::default() }
+    }
+
+    /// Counter of bytes handed out so far, readable after the reader has been moved into a decoder
+    fn consumed(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.consumed)
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        self.consumed.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Extract a gzip-compressed tar stream into `extract_to` with the first `strip` directories
+/// of each entry removed (leaving existing files alone with `skip_existing`), then drain whatever follows the end-of-archive marker so the sender
+/// sees the whole download
+///
+/// Fails with `DecompressionLimitExceeded` before writing an entry that would take the total
+/// past `max_uncompressed_size`. After each entry, `on_entry` is called with the number of
+/// compressed bytes consumed so far.
+pub(crate) fn extract_tar_gz_stream(
+    reader: ChannelReader,
+    extract_to: &Path,
+    max_uncompressed_size: Option<u64>,
+    strip: usize,
+    skip_existing: bool,
+    cancel: &CancellationToken,
+    mut on_entry: impl FnMut(u64),
+) -> Result<()> {
+    let mut budget = ExtractionBudget::new(max_uncompressed_size);
+    let consumed = reader.consumed();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    for entry in archive.entries()? {
+        cancel.check()?;
+        let mut entry = entry?;
+        crate::archive::unpack_tar_entry(&mut entry, extract_to, strip, skip_existing, &mut ExtractState::disabled(), &mut budget)?;
+        on_entry(consumed.load(Ordering::Relaxed));
+    }
+
+    let mut reader = archive.into_inner().into_inner();
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_gz_with(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_extract_from_chunked_stream() {
+        let archive = tar_gz_with(&[("bin/app", b"binary"), ("config.yml", b"port: 80")]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        let sender = std::thread::spawn(move || {
+            for chunk in archive.chunks(7) {
+                tx.blocking_send(chunk.to_vec()).unwrap();
+            }
+        });
+
+        let mut positions = Vec::new();
+        extract_tar_gz_stream(ChannelReader::new(rx), dir.path(), None, 0, false, &CancellationToken::new(), |consumed| {
+            positions.push(consumed)
+        })
+        .unwrap();
+        sender.join().unwrap();
+
+        // One report per entry, moving forward through the compressed stream
+        assert_eq!(positions.len(), 2);
+        assert!(positions[0] > 0 && positions[0] <= positions[1]);
+
+        assert_eq!(std::fs::read(dir.path().join("bin/app")).unwrap(), b"binary");
+        assert_eq!(std::fs::read(dir.path().join("config.yml")).unwrap(), b"port: 80");
+    }
+
+    #[test]
+    fn test_stream_over_size_cap_fails() {
+        let archive = tar_gz_with(&[("small", &[1u8; 512]), ("bomb", &[0u8; 8192])]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        tx.try_send(archive).unwrap();
+        drop(tx);
+
+        let err = extract_tar_gz_stream(ChannelReader::new(rx), dir.path(), Some(4096), 0, false, &CancellationToken::new(), |_| {}).unwrap_err();
+        assert!(err.downcast_ref::<crate::DecompressionLimitExceeded>().is_some());
+        assert!(dir.path().join("small").exists());
+        assert!(!dir.path().join("bomb").exists());
+    }
+
+    #[test]
+    fn test_truncated_stream_fails() {
+        let archive = tar_gz_with(&[("bin/app", &[7u8; 4096])]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        tx.try_send(archive[..archive.len() / 2].to_vec()).unwrap();
+        drop(tx);
+
+        assert!(extract_tar_gz_stream(ChannelReader::new(rx), dir.path(), None, 0, false, &CancellationToken::new(), |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_cancelled_before_first_entry() {
+        let archive = tar_gz_with(&[("bin/app", b"binary")]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        tx.try_send(archive).unwrap();
+        drop(tx);
+
+        let cancel = CancellationToken::new();
+        cancel.clone().cancel();
+
+        let err = extract_tar_gz_stream(ChannelReader::new(rx), dir.path(), None, 0, false, &cancel, |_| {}).unwrap_err();
+        assert!(err.downcast_ref::<crate::Cancelled>().is_some());
+        assert!(!dir.path().join("bin/app").exists());
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+
+/// How far back download speed is averaged over
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Longest single pause while throttling, so cancellation is noticed promptly
+const THROTTLE_SLICE: Duration = Duration::from_millis(100);
+
+/// Time constant of the smoothed speed behind time-remaining estimates
+const ETA_SMOOTHING: Duration = Duration::from_secs(5);
+
+/// Bytes between download updates when the size is unknown and there's no percentage to follow
+const UNSIZED_REPORT_STEP: u64 = 1024 * 1024;
+
+/// Download speed averaged over the reads of the last second, plus a slower-moving average
+/// for estimating the time remaining
+pub(crate) struct TransferRate {
+    samples: VecDeque<(Instant, u64)>,
+    smoothed: Option<f64>,
+    /// Whole percent (or MiB, for an unknown size) of the last update sent to subscribers
+    reported: Option<u64>,
+}
+
+impl TransferRate {
+    pub fn new() -> Self {
+        Self { samples: VecDeque::new(), smoothed: None, reported: None }
+    }
+
+    /// Record that `bytes` were just read
+    pub fn record(&mut self, bytes: u64) {
+        self.record_at(Instant::now(), bytes);
+    }
+
+    fn record_at(&mut self, now: Instant, bytes: u64) {
+        let previous = self.samples.back().map(|(at, _)| *at);
+        self.samples.push_back((now, bytes));
+        while self.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+
+        // Exponential moving average weighted by the time since the last read, so the estimate
+        // settles at the same pace however often chunks arrive
+        if let Some(current) = self.bytes_per_sec() {
+            self.smoothed = Some(match (self.smoothed, previous) {
+                (Some(smoothed), Some(previous)) => {
+                    let elapsed = now.duration_since(previous).as_secs_f64();
+                    let weight = 1.0 - (-elapsed / ETA_SMOOTHING.as_secs_f64()).exp();
+                    smoothed + weight * (current - smoothed)
+                }
+                _ => current,
+            });
+        }
+    }
+
+    /// Bytes per second across the window, or None until two reads are far enough apart to measure
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        let (first, _) = self.samples.front()?;
+        let (last, _) = self.samples.back()?;
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        // The first sample's bytes arrived before the window being measured started
+        let bytes: u64 = self.samples.iter().skip(1).map(|(_, bytes)| bytes).sum();
+        Some(bytes as f64 / elapsed)
+    }
+
+    /// Whether `done` of `total` bytes (0 for unknown) moved progress on by a whole percent, or a
+    /// whole MiB when the size is unknown, since the last update that was sent
+    pub fn should_report(&mut self, done: u64, total: u64) -> bool {
+        let step = done.saturating_mul(100).checked_div(total).unwrap_or(done / UNSIZED_REPORT_STEP);
+        if self.reported == Some(step) {
+            return false;
+        }
+        self.reported = Some(step);
+        true
+    }
+
+    /// Estimated time to receive `remaining` more bytes at the smoothed speed, once it can be measured
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        let speed = self.smoothed.filter(|speed| *speed > 0.0)?;
+        Duration::try_from_secs_f64(remaining as f64 / speed).ok()
+    }
+}
+
+/// Caps the average rate of one download by pausing after reads that get ahead of the limit
+pub(crate) struct Throttle {
+    bytes_per_sec: Option<u64>,
+    started: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    /// A throttle for `bytes_per_sec` (None or 0 for unlimited), starting now
+    pub fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.filter(|limit| *limit > 0),
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Record that `bytes` were just read and pause until the average rate is back under the
+    /// limit, returning early once `cancel` is cancelled
+    pub async fn pace(&mut self, bytes: u64, cancel: &CancellationToken) {
+        let mut remaining = self.delay_at(Instant::now(), bytes);
+        while !remaining.is_zero() && !cancel.is_cancelled() {
+            let slice = remaining.min(THROTTLE_SLICE);
+            tokio::time::sleep(slice).await;
+            remaining -= slice;
+        }
+    }
+
+    /// How long to pause after reading `bytes` at `now`
+    fn delay_at(&mut self, now: Instant, bytes: u64) -> Duration {
+        self.bytes += bytes;
+        let Some(limit) = self.bytes_per_sec else {
+            return Duration::ZERO;
+        };
+
+        let allowed_after = Duration::from_secs_f64(self.bytes as f64 / limit as f64);
+        allowed_after.saturating_sub(now.duration_since(self.started))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_uses_last_second() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+
+        rate.record_at(start, 1000);
+        assert_eq!(rate.bytes_per_sec(), None);
+
+        rate.record_at(start + Duration::from_millis(500), 1000);
+        assert_eq!(rate.bytes_per_sec(), Some(2000.0));
+
+        // A slow stretch pushes the fast reads out of the window
+        rate.record_at(start + Duration::from_millis(2500), 100);
+        rate.record_at(start + Duration::from_millis(3000), 100);
+        assert_eq!(rate.bytes_per_sec(), Some(200.0));
+    }
+
+    #[test]
+    fn test_eta_smooths_speed() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        rate.record_at(start, 1000);
+        assert_eq!(rate.eta(10_000), None);
+
+        // A steady 1000 bytes per second
+        for tick in 1..=10 {
+            rate.record_at(start + Duration::from_millis(tick * 100), 100);
+        }
+        assert_eq!(rate.eta(10_000), Some(Duration::from_secs(10)));
+
+        // A momentary burst to 10x the speed barely moves the estimate
+        rate.record_at(start + Duration::from_millis(1100), 1000);
+        let eta = rate.eta(10_000).unwrap();
+        assert!(eta > Duration::from_secs(8) && eta < Duration::from_secs(10), "{:?}", eta);
+    }
+
+    #[test]
+    fn test_should_report_whole_percent_steps() {
+        let mut rate = TransferRate::new();
+        assert!(rate.should_report(0, 1000));
+        assert!(!rate.should_report(9, 1000));
+        assert!(rate.should_report(10, 1000));
+        assert!(!rate.should_report(19, 1000));
+        assert!(rate.should_report(1000, 1000));
+
+        let mut unknown_size = TransferRate::new();
+        assert!(unknown_size.should_report(0, 0));
+        assert!(!unknown_size.should_report(UNSIZED_REPORT_STEP - 1, 0));
+        assert!(unknown_size.should_report(UNSIZED_REPORT_STEP, 0));
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let mut throttle = Throttle::new(Some(1000));
+        let start = throttle.started;
+
+        // 500 bytes at once is half a second's worth
+        assert_eq!(throttle.delay_at(start, 500), Duration::from_millis(500));
+
+        // Having slept that off, the next 500 bytes earn the same pause
+        assert_eq!(throttle.delay_at(start + Duration::from_millis(500), 500), Duration::from_millis(500));
+
+        // A slow stretch banks no credit beyond the average
+        assert_eq!(throttle.delay_at(start + Duration::from_secs(5), 100), Duration::ZERO);
+
+        let mut unlimited = Throttle::new(Some(0));
+        assert_eq!(unlimited.delay_at(Instant::now(), u64::MAX / 2), Duration::ZERO);
+    }
+}
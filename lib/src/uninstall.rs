@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Options controlling what an uninstall removes from the install directory
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UninstallOptions {
+    /// Glob patterns, relative to install_path, for files that should survive the uninstall
+    /// (e.g. `**/*.db` or `config/*.yml`)
+    pub keep_globs: Vec<String>,
+    /// Files or directories, relative to install_path, that should survive the uninstall along
+    /// with everything inside them
+    #[serde(default)]
+    pub keep_paths: Vec<PathBuf>,
+}
+
+impl UninstallOptions {
+    /// Create options that remove everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep files matching the given glob pattern
+    pub fn keep_glob(mut self, pattern: String) -> Self {
+        self.keep_globs.push(pattern);
+        self
+    }
+
+    /// Keep a file or directory (relative to install_path) and everything inside it
+    pub fn keep_path(mut self, path: PathBuf) -> Self {
+        self.keep_paths.push(path);
+        self
+    }
+}
+
+/// Parse keep globs up front so bad patterns are reported before anything is removed
+pub(crate) fn compile_keep_globs(keep_globs: &[String]) -> Result<Vec<glob::Pattern>> {
+    keep_globs
+        .iter()
+        .map(|g| glob::Pattern::new(g).context(format!("Invalid keep glob '{}'", g)))
+        .collect()
+}
+
+/// Make kept paths relative to the install directory
+///
+/// Absolute paths outside `root` are dropped since the sweep never reaches them.
+pub(crate) fn relative_keep_paths<'a>(root: &Path, paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter_map(|path| match path.strip_prefix(root) {
+            Ok(relative) => Some(relative.to_path_buf()),
+            Err(_) if path.is_relative() => Some(path.clone()),
+            Err(_) => None,
+        })
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
+}
+
+/// Remove the install directory, keeping files that match any of the patterns and the kept paths
+/// (relative to `root`), along with the directories needed to hold them
+///
+/// Returns true if some locked files could only be scheduled for deletion on reboot (Windows).
+pub(crate) fn sweep_install_dir(root: &Path, patterns: &[glob::Pattern], keep_paths: &[PathBuf]) -> Result<bool> {
+    let mut reboot_required = false;
+
+    if !sweep_dir(root, root, patterns, keep_paths, &mut reboot_required)? {
+        remove_path(root, true, &mut reboot_required)?;
+    }
+
+    Ok(reboot_required)
+}
+
+/// Delete everything in `root` except the kept paths (relative to `root`), leaving `root` itself
+///
+/// Used to clear out an old version before extracting a new one. Unlike `sweep_install_dir`, a
+/// file that can't be deleted (e.g. locked by a running process on Windows) fails the operation
+/// instead of being scheduled for deletion on reboot, which would take its replacement with it.
+pub(crate) fn clear_install_dir(root: &Path, keep_paths: &[PathBuf]) -> Result<()> {
+    clear_dir(root, root, keep_paths).map(|_| ())
+}
+
+/// Recursively clear a directory, returning true if anything inside it was kept
+fn clear_dir(root: &Path, dir: &Path, keep_paths: &[PathBuf]) -> Result<bool> {
+    let mut kept_any = false;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = path.strip_prefix(root)?;
+
+        if keep_paths.iter().any(|keep| keep == relative) {
+            kept_any = true;
+        } else if entry.file_type()?.is_dir() {
+            if clear_dir(root, &path, keep_paths)? {
+                kept_any = true;
+            } else {
+                std::fs::remove_dir(&path).context(format!("Failed to remove '{}'", path.display()))?;
+            }
+        } else {
+            std::fs::remove_file(&path).context(format!("Failed to remove '{}'", path.display()))?;
+        }
+    }
+
+    Ok(kept_any)
+}
+
+/// Recursively sweep a directory, returning true if anything inside it was kept
+fn sweep_dir(
+    root: &Path,
+    dir: &Path,
+    patterns: &[glob::Pattern],
+    keep_paths: &[PathBuf],
+    reboot_required: &mut bool,
+) -> Result<bool> {
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    let mut kept_any = false;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = path.strip_prefix(root)?;
+
+        if keep_paths.iter().any(|keep| keep == relative) {
+            kept_any = true;
+            continue;
+        }
+
+        // file_type() does not follow symlinks, so linked directories are removed as links
+        if entry.file_type()?.is_dir() {
+            if sweep_dir(root, &path, patterns, keep_paths, reboot_required)? {
+                kept_any = true;
+            } else {
+                remove_path(&path, true, reboot_required)?;
+            }
+            continue;
+        }
+
+        if patterns.iter().any(|p| p.matches_path_with(relative, options)) {
+            kept_any = true;
+        } else {
+            remove_path(&path, false, reboot_required)?;
+        }
+    }
+
+    Ok(kept_any)
+}
+
+/// Remove a file or empty directory
+///
+/// On Windows, files locked by a running process (and directories still holding them)
+/// are scheduled for deletion on the next reboot instead.
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+pub(crate) fn remove_path(path: &Path, is_dir: bool, reboot_required: &mut bool) -> Result<()> {
+    let result = if is_dir {
+        std::fs::remove_dir(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+
+    match result {
+        Ok(()) => Ok(()),
+        #[cfg(target_os = "windows")]
+        Err(e) if crate::win::is_file_in_use(path, &e) || (is_dir && *reboot_required) => {
+            crate::win::schedule_delete_on_reboot(path)?;
+            *reboot_required = true;
+            Ok(())
+        }
+        Err(e) => Err(e).context(format!("Failed to remove '{}'", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(root: &Path, relative: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"data").unwrap();
+    }
+
+    #[test]
+    fn test_sweep_without_globs_removes_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        touch(&root, "bin/app");
+        touch(&root, "data/world.db");
+
+        assert!(!sweep_install_dir(&root, &[], &[]).unwrap());
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_sweep_keeps_matching_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        touch(&root, "app.db");
+        touch(&root, "bin/app");
+        touch(&root, "data/worlds/world.db");
+        touch(&root, "config/server.yml");
+        touch(&root, "config/nested/other.yml");
+        touch(&root, "logs/latest.log");
+
+        let keep = compile_keep_globs(&["**/*.db".to_string(), "config/*.yml".to_string()]).unwrap();
+        sweep_install_dir(&root, &keep, &[]).unwrap();
+
+        assert!(root.join("app.db").exists());
+        assert!(root.join("data/worlds/world.db").exists());
+        assert!(root.join("config/server.yml").exists());
+        assert!(!root.join("config/nested").exists());
+        assert!(!root.join("bin").exists());
+        assert!(!root.join("logs").exists());
+    }
+
+    #[test]
+    fn test_sweep_removes_root_when_nothing_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        touch(&root, "bin/app");
+
+        let keep = compile_keep_globs(&["**/*.db".to_string()]).unwrap();
+        sweep_install_dir(&root, &keep, &[]).unwrap();
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_sweep_keeps_data_dirs_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        touch(&root, "bin/app");
+        touch(&root, "worlds/survival/level.dat");
+        touch(&root, "config/server.yml");
+        touch(&root, "config/cache.tmp");
+        std::fs::create_dir_all(root.join("backups")).unwrap();
+
+        let keep = relative_keep_paths(
+            &root,
+            &[PathBuf::from("worlds"), root.join("config/server.yml"), PathBuf::from("backups"), PathBuf::from("/elsewhere")],
+        );
+        assert_eq!(keep.len(), 3);
+        sweep_install_dir(&root, &[], &keep).unwrap();
+
+        assert!(root.join("worlds/survival/level.dat").exists());
+        assert!(root.join("config/server.yml").exists());
+        assert!(root.join("backups").is_dir());
+        assert!(!root.join("config/cache.tmp").exists());
+        assert!(!root.join("bin").exists());
+    }
+
+    #[test]
+    fn test_clear_install_dir_keeps_root_and_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("app");
+        touch(&root, "bin/old-helper");
+        touch(&root, "worlds/survival/level.dat");
+        touch(&root, "app.install.log");
+
+        clear_install_dir(&root, &[PathBuf::from("worlds"), PathBuf::from("app.install.log")]).unwrap();
+        assert!(root.join("worlds/survival/level.dat").exists());
+        assert!(root.join("app.install.log").exists());
+        assert!(!root.join("bin").exists());
+
+        clear_install_dir(&root, &[]).unwrap();
+        assert!(root.is_dir());
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_invalid_keep_glob_is_rejected() {
+        assert!(compile_keep_globs(&["[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::InstallationConfig;
+
+/// Current manifest format, bumped on incompatible changes
+const MANIFEST_FORMAT: u32 = 1;
+
+/// A registry value under HKEY_LOCAL_MACHINE created by the install (Windows)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryValue {
+    pub key: String,
+    pub value: String,
+}
+
+/// Self-contained description of everything an installation created, so it can be removed
+/// later without the original installer or a correctly configured manager
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UninstallManifest {
+    pub format: u32,
+    /// Service registered with systemd or the Windows service manager
+    pub service_name: String,
+    pub version: Option<String>,
+    pub install_path: PathBuf,
+    pub registry_path: Option<String>,
+    pub version_file_dir: Option<String>,
+    /// Files created by the install, including metadata files kept outside install_path
+    pub files: Vec<PathBuf>,
+    #[serde(default)]
+    pub registry_values: Vec<RegistryValue>,
+    /// Value names under HKEY_CURRENT_USER\...\Run (Windows)
+    #[serde(default)]
+    pub startup_entries: Vec<String>,
+    #[serde(default)]
+    pub shortcuts: Vec<PathBuf>,
+    /// Directories the install created for install_path (deepest first), removed once empty
+    #[serde(default)]
+    pub created_dirs: Vec<PathBuf>,
+}
+
+impl UninstallManifest {
+    /// Describe the installation managed by `config` as it currently exists on disk
+    pub(crate) fn capture(config: &InstallationConfig, version: Option<String>) -> Result<Self> {
+        let mut files = Vec::new();
+        if config.install_path.exists() {
+            collect_files(&config.install_path, &mut files)?;
+        }
+
+        let created_dirs_path = config.get_created_dirs_path();
+        let created_dirs = read_created_dirs(&created_dirs_path)?;
+
+        for metadata_file in [config.get_install_log_path(), config.get_channel_lock_path(), created_dirs_path] {
+            if metadata_file.exists() && !files.contains(&metadata_file) {
+                files.push(metadata_file);
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let version_dir = config.get_version_file_dir();
+            files.push(version_dir.join(format!("{}.version", config.service_name)));
+            files.push(version_dir.join(format!("{}.path", config.service_name)));
+            files.push(crate::nix::unit_file_path(config));
+        }
+
+        #[cfg(unix)]
+        files.extend(crate::binaries::path_links(config).into_iter().filter(|link| link.read_link().is_ok_and(|target| target.starts_with(&config.install_path))));
+
+        #[cfg(target_os = "macos")]
+        {
+            let version_dir = config.get_version_file_dir();
+            files.push(version_dir.join(format!("{}.version", config.service_name)));
+            files.push(version_dir.join(format!("{}.path", config.service_name)));
+            files.push(crate::mac::plist_path(config));
+        }
+
+        #[cfg(target_os = "windows")]
+        let registry_values = ["version", "path"]
+            .iter()
+            .map(|suffix| RegistryValue {
+                key: config.get_registry_path().to_string(),
+                value: format!("{}_{}", config.service_name, suffix),
+            })
+            .collect();
+
+        #[cfg(not(target_os = "windows"))]
+        let registry_values = Vec::new();
+
+        #[cfg(target_os = "windows")]
+        let candidate_shortcuts = crate::win::shortcut_paths(config);
+
+        #[cfg(target_os = "linux")]
+        let candidate_shortcuts = crate::nix::shortcut_paths(config);
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let candidate_shortcuts: Vec<PathBuf> = Vec::new();
+
+        let shortcuts = candidate_shortcuts.into_iter().filter(|path| path.exists()).collect();
+
+        Ok(Self {
+            format: MANIFEST_FORMAT,
+            service_name: config.service_name.clone(),
+            version,
+            install_path: config.install_path.clone(),
+            registry_path: config.registry_path.clone(),
+            version_file_dir: config.version_file_dir.clone(),
+            files,
+            registry_values,
+            startup_entries: Vec::new(),
+            shortcuts,
+            created_dirs,
+        })
+    }
+
+    /// Read a manifest written by `write`
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Failed to read uninstall manifest '{}'", path.display()))?;
+        let manifest: Self = serde_json::from_str(&content)
+            .context(format!("Malformed uninstall manifest '{}'", path.display()))?;
+
+        if manifest.format > MANIFEST_FORMAT {
+            anyhow::bail!(
+                "Uninstall manifest '{}' uses format {}, but only format {} is supported",
+                path.display(),
+                manifest.format,
+                MANIFEST_FORMAT
+            );
+        }
+
+        Ok(manifest)
+    }
+
+    /// Write the manifest as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .context(format!("Failed to write uninstall manifest '{}'", path.display()))
+    }
+
+    /// Rebuild enough configuration to drive the platform service removal
+    pub(crate) fn to_config(&self) -> InstallationConfig {
+        let mut config = InstallationConfig::new(self.install_path.clone(), String::new(), self.service_name.clone());
+        config.registry_path = self.registry_path.clone();
+        config.version_file_dir = self.version_file_dir.clone();
+        config
+    }
+
+    /// Remove the listed files, shortcuts and startup entries, then any directories under
+    /// install_path left empty, and finally the parent directories the install created
+    ///
+    /// Files created after the manifest was written (e.g. user data) are left in place.
+    /// Returns true if some locked files could only be scheduled for deletion on reboot (Windows).
+    pub(crate) fn remove_files(&self) -> Result<bool> {
+        let mut reboot_required = false;
+
+        for path in self.files.iter().chain(&self.shortcuts) {
+            if path.exists() {
+                crate::uninstall::remove_path(path, false, &mut reboot_required)?;
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            for entry in &self.startup_entries {
+                crate::win::delete_startup_entry(entry)?;
+            }
+            for value in &self.registry_values {
+                crate::win::delete_machine_registry_value(&value.key, &value.value)?;
+            }
+        }
+
+        if self.install_path.exists() {
+            prune_empty_dirs(&self.install_path)?;
+        }
+
+        remove_created_dirs(&self.created_dirs);
+
+        Ok(reboot_required)
+    }
+}
+
+/// `path` and those of its ancestors that don't exist yet, deepest first
+pub(crate) fn missing_ancestors(path: &Path) -> Vec<PathBuf> {
+    path.ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Read the directories recorded by `write_created_dirs`, if any
+pub(crate) fn read_created_dirs(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read created directories record '{}'", path.display()))?;
+    serde_json::from_str(&content)
+        .context(format!("Malformed created directories record '{}'", path.display()))
+}
+
+/// Record the directories an install created, unless an earlier install already did
+///
+/// The record is written even when the install created none, since it also marks install_path
+/// as this library's while no version is recorded yet.
+pub(crate) fn write_created_dirs(path: &Path, dirs: &[PathBuf]) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(dirs)?)
+        .context(format!("Failed to write created directories record '{}'", path.display()))
+}
+
+/// Remove created directories deepest first, stopping at the first one that still has contents
+/// or can't be removed
+pub(crate) fn remove_created_dirs(dirs: &[PathBuf]) {
+    for dir in dirs {
+        if dir.exists() && std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Remove empty directories bottom-up, returning true if `dir` itself was removed
+fn prune_empty_dirs(dir: &Path) -> Result<bool> {
+    let mut empty = true;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() || !prune_empty_dirs(&entry.path())? {
+            empty = false;
+        }
+    }
+
+    Ok(empty && std::fs::remove_dir(dir).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip_and_removal() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("app");
+        std::fs::create_dir_all(install_path.join("bin")).unwrap();
+        std::fs::write(install_path.join("bin/server"), b"bin").unwrap();
+        std::fs::write(install_path.join("readme.txt"), b"doc").unwrap();
+
+        let config = InstallationConfig::new(install_path.clone(), "owner/repo".to_string(), "app".to_string())
+            .version_file_dir(dir.path().join("meta").to_string_lossy().into_owned());
+        let mut manifest = UninstallManifest::capture(&config, Some("1.0.0".to_string())).unwrap();
+        assert!(manifest.files.contains(&install_path.join("bin/server")));
+
+        let shortcut = dir.path().join("App.desktop");
+        std::fs::write(&shortcut, b"[Desktop Entry]").unwrap();
+        manifest.shortcuts.push(shortcut.clone());
+
+        let manifest_path = dir.path().join("uninstall.json");
+        manifest.write(&manifest_path).unwrap();
+        let manifest = UninstallManifest::read(&manifest_path).unwrap();
+        assert_eq!(manifest.to_config().service_name, "app");
+
+        // Created after the manifest was written, so it must survive
+        std::fs::create_dir_all(install_path.join("data")).unwrap();
+        std::fs::write(install_path.join("data/world.db"), b"user").unwrap();
+
+        assert!(!manifest.remove_files().unwrap());
+        assert!(!install_path.join("bin").exists());
+        assert!(!install_path.join("readme.txt").exists());
+        assert!(!shortcut.exists());
+        assert!(install_path.join("data/world.db").exists());
+    }
+
+    #[test]
+    fn test_created_dirs_are_removed_only_while_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("opt/obsidian/server");
+        let created = missing_ancestors(&install_path);
+        assert_eq!(created, vec![install_path.clone(), dir.path().join("opt/obsidian"), dir.path().join("opt")]);
+
+        let record = dir.path().join("meta/server.dirs.json");
+        write_created_dirs(&record, &created).unwrap();
+        std::fs::create_dir_all(&install_path).unwrap();
+
+        // A later reinstall finds nothing missing and must not overwrite the record
+        write_created_dirs(&record, &missing_ancestors(&install_path)).unwrap();
+        assert_eq!(read_created_dirs(&record).unwrap(), created);
+
+        // Something else now lives in /opt, so it stays
+        std::fs::write(dir.path().join("opt/other"), b"keep").unwrap();
+        remove_created_dirs(&created);
+        assert!(!dir.path().join("opt/obsidian").exists());
+        assert!(dir.path().join("opt/other").exists());
+    }
+
+    #[test]
+    fn test_newer_manifest_format_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallationConfig::new(dir.path().join("app"), "owner/repo".to_string(), "app".to_string());
+        let mut manifest = UninstallManifest::capture(&config, None).unwrap();
+        manifest.format = MANIFEST_FORMAT + 1;
+
+        let path = dir.path().join("uninstall.json");
+        manifest.write(&path).unwrap();
+        assert!(UninstallManifest::read(&path).is_err());
+    }
+}
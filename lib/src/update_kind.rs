@@ -0,0 +1,82 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{InstallError, InstallationManager, ReleaseChannel};
+
+/// How big a step going from one version to another is, e.g. for labelling an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateKind {
+    /// The major version goes up
+    Major,
+    /// The minor version goes up within the same major version
+    Minor,
+    /// Only the patch version goes up
+    Patch,
+    /// The version numbers stay the same and only the prerelease changes, e.g. `1.2.0-beta.1` to `1.2.0`
+    Prerelease,
+    /// The new version isn't newer than the old one
+    Downgrade,
+}
+
+/// An available update, as found by `check_for_updates_detailed`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    /// The installed version
+    pub from: Version,
+    /// The newest version on the channel
+    pub to: Version,
+    pub kind: UpdateKind,
+}
+
+/// Classify going from version `from` to `to`; the same version counts as a `Downgrade`
+pub fn classify_update(from: &Version, to: &Version) -> UpdateKind {
+    if to <= from {
+        UpdateKind::Downgrade
+    } else if to.major != from.major {
+        UpdateKind::Major
+    } else if to.minor != from.minor {
+        UpdateKind::Minor
+    } else if to.patch != from.patch {
+        UpdateKind::Patch
+    } else {
+        UpdateKind::Prerelease
+    }
+}
+
+impl InstallationManager {
+    /// Check for updates on the specified channel and describe the update, if there is one
+    ///
+    /// Returns None when the installed version is current, or when nothing is installed or its
+    /// version is unknown, as there is nothing to compare against.
+    pub async fn check_for_updates_detailed(&mut self, channel: ReleaseChannel) -> Result<Option<UpdateInfo>, InstallError> {
+        if !self.fetch_update_available(channel).await? {
+            return Ok(None);
+        }
+        let (Some(from), Some(to)) = (self.current_version(), self.latest_version()) else {
+            return Ok(None);
+        };
+        Ok(Some(UpdateInfo {
+            kind: classify_update(from, to),
+            from: from.clone(),
+            to: to.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_update() {
+        let v = |version: &str| Version::parse(version).unwrap();
+        assert_eq!(classify_update(&v("1.4.2"), &v("2.0.0")), UpdateKind::Major);
+        assert_eq!(classify_update(&v("1.4.2"), &v("1.5.0-beta.1")), UpdateKind::Minor);
+        assert_eq!(classify_update(&v("1.4.2"), &v("1.4.3")), UpdateKind::Patch);
+        assert_eq!(classify_update(&v("1.5.0-beta.1"), &v("1.5.0")), UpdateKind::Prerelease);
+        assert_eq!(classify_update(&v("1.5.0-beta.1"), &v("1.5.0-beta.2")), UpdateKind::Prerelease);
+        assert_eq!(classify_update(&v("1.4.2"), &v("1.4.2")), UpdateKind::Downgrade);
+        assert_eq!(classify_update(&v("2.0.0"), &v("1.9.9")), UpdateKind::Downgrade);
+    }
+}
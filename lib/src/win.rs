@@ -19,7 +19,7 @@ pub fn get_installed_version(config: &InstallationConfig) -> Result<Option<Versi
             let version_key = format!("{}_version", config.service_name);
             match key.get_value::<String, _>(&version_key) {
                 Ok(version_str) => {
-                    let version = Version::parse(&version_str)
+                    let version = Version::parse(version_str.trim().trim_start_matches('v'))
                         .context("Failed to parse version from registry")?;
                     Ok(Some(version))
                 }
@@ -92,6 +92,89 @@ fn remove_registry_entries(config: &InstallationConfig) -> Result<()> {
     Ok(())
 }
 
+/// Key under HKEY_LOCAL_MACHINE that lists the installation in Apps & features
+fn uninstall_key_path(config: &InstallationConfig) -> String {
+    format!(r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\{}", config.service_name)
+}
+
+/// List the installation in Apps & features ("Add or remove programs") if an uninstall command is configured
+pub fn register_uninstall_entry(config: &InstallationConfig, version: &str) -> Result<()> {
+    let Some(uninstall_command) = &config.uninstall_command else {
+        return Ok(());
+    };
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let (key, _) = hklm.create_subkey(uninstall_key_path(config))
+        .context("Failed to create uninstall registry key")?;
+
+    let install_location = config.install_path.to_string_lossy().to_string();
+    let values: [(&str, &str); 5] = [
+        ("DisplayName", config.get_display_name()),
+        ("DisplayVersion", version.trim_start_matches('v')),
+        ("Publisher", config.get_publisher()),
+        ("InstallLocation", &install_location),
+        ("UninstallString", uninstall_command),
+    ];
+    for (name, value) in values {
+        key.set_value(name, &value)
+            .context(format!("Failed to set {} in uninstall registry key", name))?;
+    }
+
+    // Only removal is offered
+    key.set_value("NoModify", &1u32)
+        .context("Failed to set NoModify in uninstall registry key")?;
+    key.set_value("NoRepair", &1u32)
+        .context("Failed to set NoRepair in uninstall registry key")?;
+
+    if let Ok(exe_path) = find_executable(config) {
+        key.set_value("DisplayIcon", &exe_path.to_string_lossy().to_string())
+            .context("Failed to set DisplayIcon in uninstall registry key")?;
+    }
+
+    Ok(())
+}
+
+/// Remove the Apps & features entry, ignoring one that doesn't exist
+fn remove_uninstall_entry(config: &InstallationConfig) -> Result<()> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    match hklm.delete_subkey_all(uninstall_key_path(config)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context("Failed to remove uninstall registry key")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Delete a value under HKEY_LOCAL_MACHINE, ignoring values that no longer exist
+pub fn delete_machine_registry_value(key: &str, value: &str) -> Result<()> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    if let Ok(key) = hklm.open_subkey_with_flags(key, KEY_WRITE) {
+        match key.delete_value(value) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context(format!("Failed to delete registry value '{}'", value));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete a startup entry from the current user's Run key, ignoring entries that no longer exist
+pub fn delete_startup_entry(name: &str) -> Result<()> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    if let Ok(key) = hkcu.open_subkey_with_flags(r"Software\Microsoft\Windows\CurrentVersion\Run", KEY_WRITE) {
+        match key.delete_value(name) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context(format!("Failed to delete startup entry '{}'", name));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
 /// Convert a Rust string to a wide string for Windows APIs
 fn to_wide_string(s: &str) -> Vec<u16> {
     OsStr::new(s)
@@ -117,11 +200,8 @@ unsafe fn open_sc_manager() -> Result<SC_HANDLE> {
     Ok(sc_manager)
 }
 
-/// Install a Windows service
-pub fn install_service(
-    config: &InstallationConfig,
-    version: &str,
-) -> Result<()> {
+/// Create the service, or update its configuration (re-enabling auto start) if it already exists
+fn register_service(config: &InstallationConfig) -> Result<()> {
     unsafe {
         let sc_manager = open_sc_manager()?;
 
@@ -133,6 +213,16 @@ pub fn install_service(
         let display_name_wide = to_wide_string(display_name);
         let exe_path_wide = to_wide_string(exe_path.to_string_lossy().as_ref());
 
+        // LocalSystem is the default when no account is given; the built-in accounts take no password
+        let account = config.service_account.as_ref();
+        let account_wide = account.and_then(|account| account.account_name()).map(to_wide_string);
+        let password_wide = match account {
+            Some(crate::ServiceAccount::User { password: Some(password), .. }) => Some(to_wide_string(password)),
+            Some(crate::ServiceAccount::LocalService | crate::ServiceAccount::NetworkService) => Some(to_wide_string("")),
+            _ => None,
+        };
+        let wide_or_null = |wide: &Option<Vec<u16>>| wide.as_ref().map_or(PCWSTR::null(), |wide| PCWSTR(wide.as_ptr()));
+
         // Create the service
         let service = CreateServiceW(
             sc_manager,
@@ -146,12 +236,13 @@ pub fn install_service(
             PCWSTR::null(),
             None,
             PCWSTR::null(),
-            PCWSTR::null(),
-            PCWSTR::null(),
+            wide_or_null(&account_wide),
+            wide_or_null(&password_wide),
         );
 
         if let Ok(service_handle) = service {
             if !service_handle.is_invalid() {
+                configure_recovery(config, service_handle)?;
                 CloseServiceHandle(service_handle).ok();
             }
         } else {
@@ -163,6 +254,9 @@ pub fn install_service(
             )?;
 
             if !service_handle.is_invalid() {
+                // An existing service moves back to LocalSystem when the account is unset
+                let start_name_wide = account_wide.clone().unwrap_or_else(|| to_wide_string("LocalSystem"));
+
                 // Update the service configuration
                 ChangeServiceConfigW(
                     service_handle,
@@ -173,11 +267,13 @@ pub fn install_service(
                     PCWSTR::null(),
                     None,
                     PCWSTR::null(),
-                    PCWSTR::null(),
-                    PCWSTR::null(),
+                    PCWSTR(start_name_wide.as_ptr()),
+                    wide_or_null(&password_wide),
                     PCWSTR(display_name_wide.as_ptr()),
-                ).ok();
+                )
+                .context("Failed to update the service's configuration")?;
 
+                configure_recovery(config, service_handle)?;
                 CloseServiceHandle(service_handle).ok();
             }
         }
@@ -185,48 +281,167 @@ pub fn install_service(
         CloseServiceHandle(sc_manager).ok();
     }
 
+    Ok(())
+}
+
+/// Apply the restart policy as the service's recovery actions (what `sc failure` sets) and
+/// store `service_env` as the environment the SCM starts it with
+///
+/// The SCM only acts on failures: a crash, or with `OnFailure` and `Always` also a clean stop
+/// that reports a non-zero exit code. A service that stops cleanly by itself stays stopped.
+unsafe fn configure_recovery(config: &InstallationConfig, service: SC_HANDLE) -> Result<()> {
+    let action_type = match config.restart_policy {
+        crate::RestartPolicy::Always | crate::RestartPolicy::OnFailure => SC_ACTION_RESTART,
+        crate::RestartPolicy::Never => SC_ACTION_NONE,
+    };
+    let delay_ms = u32::try_from(config.get_restart_sec().saturating_mul(1000)).unwrap_or(u32::MAX);
+    // Windows only takes up to three actions; the last one repeats for every later failure
+    let mut actions = [SC_ACTION { Type: action_type, Delay: delay_ms }; 3];
+    let failure_actions = SERVICE_FAILURE_ACTIONSW {
+        // Forget earlier failures after a day without one
+        dwResetPeriod: 24 * 60 * 60,
+        lpRebootMsg: windows::core::PWSTR::null(),
+        lpCommand: windows::core::PWSTR::null(),
+        cActions: actions.len() as u32,
+        lpsaActions: actions.as_mut_ptr(),
+    };
+    unsafe {
+        ChangeServiceConfig2W(service, SERVICE_CONFIG_FAILURE_ACTIONS, Some(&failure_actions as *const _ as *const core::ffi::c_void))
+    }
+    .context("Failed to set the service's recovery actions")?;
+
+    let flag = SERVICE_FAILURE_ACTIONS_FLAG {
+        fFailureActionsOnNonCrashFailures: (config.restart_policy != crate::RestartPolicy::Never).into(),
+    };
+    unsafe {
+        ChangeServiceConfig2W(service, SERVICE_CONFIG_FAILURE_ACTIONS_FLAG, Some(&flag as *const _ as *const core::ffi::c_void))
+    }
+    .context("Failed to set the service's recovery options")?;
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let (key, _) = hklm
+        .create_subkey(format!(r"SYSTEM\CurrentControlSet\Services\{}", config.service_name))
+        .context("Failed to open the service's registry key")?;
+    let env: Vec<String> = config.get_service_env().into_iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    if env.is_empty() {
+        let _ = key.delete_value("Environment");
+    } else {
+        key.set_value("Environment", &env).context("Failed to set the service's environment")?;
+    }
+
+    Ok(())
+}
+
+/// Install a Windows service and record the installed version
+///
+/// The service is registered but not started; use start_service and service_started.
+pub fn install_service(
+    config: &InstallationConfig,
+    version: &str,
+) -> Result<()> {
+    register_service(config)?;
+
     // Store version and path in registry
     set_installed_version(config, version)?;
     set_install_path(config, &config.install_path)?;
 
-    // Start the service
-    start_service(config)?;
+    register_uninstall_entry(config, version)?;
 
     Ok(())
 }
 
+/// Describe the service operations an install (or update, which stops the service first) would run
+pub fn planned_service_commands(config: &InstallationConfig, updating: bool) -> Vec<String> {
+    let mut commands = Vec::new();
+    if updating {
+        commands.push(format!("stop service '{}'", config.service_name));
+    }
+    commands.push(format!(
+        "create or reconfigure service '{}' ({}) to start automatically as {}",
+        config.service_name,
+        config.get_display_name(),
+        config.service_account.as_ref().and_then(|account| account.account_name()).unwrap_or("LocalSystem")
+    ));
+    commands.push(format!(r"record version and install path under HKLM\{}", config.get_registry_path()));
+    if config.uninstall_command.is_some() {
+        commands.push(format!(r"list '{}' in Apps & features under HKLM\{}", config.get_display_name(), uninstall_key_path(config)));
+    }
+    commands.push(format!("start service '{}'", config.service_name));
+    commands
+}
+
+/// Re-create the service from the current config without touching installed files
+pub fn repair_service(config: &InstallationConfig) -> Result<()> {
+    register_service(config)
+}
+
+/// Check whether a started service has reported running
+pub fn service_started(config: &InstallationConfig) -> Result<bool> {
+    let mut running = false;
+    unsafe {
+        let sc_manager = open_sc_manager()?;
+        let service_name_wide = to_wide_string(&config.service_name);
+
+        let service = OpenServiceW(
+            sc_manager,
+            PCWSTR(service_name_wide.as_ptr()),
+            SERVICE_QUERY_STATUS,
+        );
+
+        if let Ok(service) = service && !service.is_invalid() {
+            let mut status = SERVICE_STATUS::default();
+            if QueryServiceStatus(service, &mut status).is_ok() {
+                running = status.dwCurrentState == SERVICE_RUNNING;
+            }
+            CloseServiceHandle(service).ok();
+        }
+
+        CloseServiceHandle(sc_manager).ok();
+    }
+
+    Ok(running)
+}
+
 /// Set directory permissions to allow the application to write
-pub fn set_directory_permissions(install_path: &PathBuf) -> Result<()> {
+///
+/// The service's own account is granted write access as well when it isn't LocalSystem, since
+/// the built-in service accounts aren't members of Users.
+pub fn set_directory_permissions(config: &InstallationConfig) -> Result<()> {
     use std::process::Command;
 
     // Use icacls to grant full control to Users group
     // This ensures the installed application can write to its own directory
-    let path_str = install_path.to_string_lossy();
+    let path_str = config.install_path.to_string_lossy();
 
-    let output = Command::new("icacls")
-        .arg(&*path_str)
-        .arg("/grant")
-        .arg("Users:(OI)(CI)F")  // Grant full control, inherit to objects and containers
-        .arg("/T")  // Apply recursively
-        .output()
-        .context("Failed to execute icacls command")?;
+    let mut grants = vec!["Users:(OI)(CI)F".to_string()]; // Grant full control, inherit to objects and containers
+    if let Some(account) = config.service_account.as_ref().and_then(|account| account.account_name()) {
+        grants.push(format!("{}:(OI)(CI)M", account));
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to set directory permissions: {}", stderr);
+    for grant in grants {
+        crate::command::run_command_with(
+            Command::new("icacls")
+                .arg(&*path_str)
+                .arg("/grant")
+                .arg(grant)
+                .arg("/T"),  // Apply recursively
+            crate::command::COMMAND_TIMEOUT,
+            crate::command::COMMAND_RETRIES,
+        )
+        .context("Failed to set directory permissions")?;
     }
 
     Ok(())
 }
 
 /// Find the main executable in the installation directory
-fn find_executable(config: &InstallationConfig) -> Result<PathBuf> {
+pub fn find_executable(config: &InstallationConfig) -> Result<PathBuf> {
     let install_path = &config.install_path;
 
     // If a custom binary name is specified, look for that specifically
-    if let Some(binary_name) = &config.binary_name {
+    if let Some(binary_name) = config.get_binary_name() {
         let exe_name = if binary_name.ends_with(".exe") {
-            binary_name.clone()
+            binary_name.to_string()
         } else {
             format!("{}.exe", binary_name)
         };
@@ -337,11 +552,146 @@ pub fn stop_service(config: &InstallationConfig) -> Result<()> {
     Ok(())
 }
 
+/// Ask a service to stop without waiting for it
+pub fn request_stop(config: &InstallationConfig) -> Result<()> {
+    unsafe {
+        let sc_manager = open_sc_manager()?;
+        let service_name_wide = to_wide_string(&config.service_name);
+
+        let service = OpenServiceW(
+            sc_manager,
+            PCWSTR(service_name_wide.as_ptr()),
+            SERVICE_STOP,
+        );
+
+        if let Ok(service) = service && !service.is_invalid() {
+            let mut status = SERVICE_STATUS::default();
+            ControlService(service, SERVICE_CONTROL_STOP, &mut status).ok();
+            CloseServiceHandle(service).ok();
+        }
+
+        CloseServiceHandle(sc_manager).ok();
+    }
+
+    Ok(())
+}
+
+/// Check whether a service has stopped (a service that doesn't exist counts as stopped)
+pub fn service_stopped(config: &InstallationConfig) -> Result<bool> {
+    let mut stopped = true;
+    unsafe {
+        let sc_manager = open_sc_manager()?;
+        let service_name_wide = to_wide_string(&config.service_name);
+
+        let service = OpenServiceW(
+            sc_manager,
+            PCWSTR(service_name_wide.as_ptr()),
+            SERVICE_QUERY_STATUS,
+        );
+
+        if let Ok(service) = service && !service.is_invalid() {
+            let mut status = SERVICE_STATUS::default();
+            if QueryServiceStatus(service, &mut status).is_ok() {
+                stopped = status.dwCurrentState == SERVICE_STOPPED;
+            }
+            CloseServiceHandle(service).ok();
+        }
+
+        CloseServiceHandle(sc_manager).ok();
+    }
+
+    Ok(stopped)
+}
+
+/// Forcefully terminate the process hosting a service
+pub fn kill_service(config: &InstallationConfig) -> Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    let process_id = service_process_id(config)?;
+    if process_id == 0 {
+        return Ok(());
+    }
+
+    unsafe {
+        let process = OpenProcess(PROCESS_TERMINATE, false, process_id)
+            .context("Failed to open service process")?;
+        let result = TerminateProcess(process, 1).context("Failed to terminate service process");
+        CloseHandle(process).ok();
+        result
+    }
+}
+
+/// Path to the executable image of the service's running process, if it is running
+pub fn running_executable(config: &InstallationConfig) -> Result<Option<PathBuf>> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::core::PWSTR;
+
+    let process_id = service_process_id(config)?;
+    if process_id == 0 {
+        return Ok(None);
+    }
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+            .context("Failed to open service process")?;
+
+        let mut buffer = vec![0u16; 32768];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len)
+            .context("Failed to query service process image");
+        CloseHandle(process).ok();
+        result?;
+
+        Ok(Some(PathBuf::from(String::from_utf16_lossy(&buffer[..len as usize]))))
+    }
+}
+
+/// Process ID of a running service, or 0 if it isn't running
+fn service_process_id(config: &InstallationConfig) -> Result<u32> {
+    let mut process_id = 0;
+    unsafe {
+        let sc_manager = open_sc_manager()?;
+        let service_name_wide = to_wide_string(&config.service_name);
+
+        let service = OpenServiceW(
+            sc_manager,
+            PCWSTR(service_name_wide.as_ptr()),
+            SERVICE_QUERY_STATUS,
+        );
+
+        if let Ok(service) = service && !service.is_invalid() {
+            let mut status = SERVICE_STATUS_PROCESS::default();
+            let buffer = std::slice::from_raw_parts_mut(
+                &mut status as *mut SERVICE_STATUS_PROCESS as *mut u8,
+                std::mem::size_of::<SERVICE_STATUS_PROCESS>(),
+            );
+            let mut needed = 0;
+            if QueryServiceStatusEx(service, SC_STATUS_PROCESS_INFO, Some(buffer), &mut needed).is_ok() {
+                process_id = status.dwProcessId;
+            }
+            CloseServiceHandle(service).ok();
+        }
+
+        CloseServiceHandle(sc_manager).ok();
+    }
+
+    Ok(process_id)
+}
+
 /// Uninstall a Windows service
-pub fn uninstall_service(config: &InstallationConfig) -> Result<()> {
+///
+/// Returns true if the service could only be marked for deletion (it is still in use),
+/// in which case a reboot is needed to finish removing it.
+pub fn uninstall_service(config: &InstallationConfig) -> Result<bool> {
     // Stop the service first
     stop_service(config).ok();
 
+    let mut pending_delete = false;
+
     unsafe {
         let sc_manager = open_sc_manager()?;
         let service_name_wide = to_wide_string(&config.service_name);
@@ -355,13 +705,192 @@ pub fn uninstall_service(config: &InstallationConfig) -> Result<()> {
         if let Ok(service_handle) = service && !service_handle.is_invalid() {
             DeleteService(service_handle).ok();
             CloseServiceHandle(service_handle).ok();
+
+            // Once all handles are closed a stopped service disappears; if it can still be
+            // opened, it is only marked for deletion
+            if let Ok(remaining) = OpenServiceW(
+                sc_manager,
+                PCWSTR(service_name_wide.as_ptr()),
+                SERVICE_QUERY_STATUS,
+            ) && !remaining.is_invalid() {
+                pending_delete = true;
+                CloseServiceHandle(remaining).ok();
+            }
         }
 
         CloseServiceHandle(sc_manager).ok();
     }
 
+    remove_shortcuts(config)?;
+
     // Remove registry entries
     remove_registry_entries(config)?;
+    remove_uninstall_entry(config)?;
 
+    Ok(pending_delete)
+}
+
+/// Folders shortcuts go in: the all-users Start Menu, plus the public Desktop when `desktop` is set
+fn shortcut_dirs(desktop: bool) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(program_data) = std::env::var_os("ProgramData") {
+        dirs.push(PathBuf::from(program_data).join(r"Microsoft\Windows\Start Menu\Programs"));
+    }
+    if desktop && let Some(public) = std::env::var_os("PUBLIC") {
+        dirs.push(PathBuf::from(public).join("Desktop"));
+    }
+    dirs
+}
+
+/// File name of the shortcut for `name`, with characters Windows forbids in file names replaced
+fn shortcut_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') { '_' } else { c })
+        .collect();
+    format!("{}.lnk", name)
+}
+
+/// Every place a shortcut for this installation may have been created
+pub fn shortcut_paths(config: &InstallationConfig) -> Vec<PathBuf> {
+    let names: Vec<String> = std::iter::once(config.get_display_name().to_string())
+        .chain(config.secondary_shortcuts().into_iter().map(|(_, name)| name))
+        .collect();
+    shortcut_dirs(true)
+        .into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(shortcut_file_name(name))))
+        .collect()
+}
+
+/// Create a Start Menu shortcut named `name` that launches `target`, and a Desktop one if `desktop` is set
+pub fn create_shortcut(target: &std::path::Path, name: &str, desktop: bool) -> Result<()> {
+    for dir in shortcut_dirs(desktop) {
+        std::fs::create_dir_all(&dir)
+            .context(format!("Failed to create '{}'", dir.display()))?;
+        let path = dir.join(shortcut_file_name(name));
+        write_shell_link(target, &path)
+            .context(format!("Failed to write shortcut '{}'", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Save a `.lnk` at `path` pointing to `target` through the shell's IShellLink object
+fn write_shell_link(target: &std::path::Path, path: &std::path::Path) -> windows::core::Result<()> {
+    use windows::core::{Interface, HSTRING};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, IPersistFile, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::UI::Shell::{IShellLinkW, ShellLink};
+
+    unsafe {
+        // COM may already be initialized on this thread, in any mode; only undo our own call
+        let initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+
+        let result = (|| {
+            let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+            link.SetPath(&HSTRING::from(target))?;
+            if let Some(dir) = target.parent() {
+                link.SetWorkingDirectory(&HSTRING::from(dir))?;
+            }
+            link.cast::<IPersistFile>()?.Save(&HSTRING::from(path), true)
+        })();
+
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+/// Remove the shortcuts written by `create_shortcut`, wherever they exist
+pub fn remove_shortcuts(config: &InstallationConfig) -> Result<()> {
+    for path in shortcut_paths(config) {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context(format!("Failed to remove shortcut '{}'", path.display()));
+            }
+            _ => {}
+        }
+    }
     Ok(())
 }
+
+/// Get the free bytes available to the current user on the volume holding `path`
+pub fn available_space(path: &std::path::Path) -> Result<u64> {
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path_wide = to_wide_string(&path.to_string_lossy());
+    let mut available: u64 = 0;
+    unsafe {
+        GetDiskFreeSpaceExW(PCWSTR(path_wide.as_ptr()), Some(&mut available as *mut u64), None, None)
+            .context(format!("Failed to query free space for '{}'", path.display()))?;
+    }
+    Ok(available)
+}
+
+/// Check whether an IO error from writing or deleting `path` means the file is locked by a
+/// running process
+///
+/// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION always do. Deleting a running executable
+/// fails with ERROR_ACCESS_DENIED, as does a plain permission problem, so that only counts when
+/// opening the file for writing hits a sharing violation, which an image mapped by a process does.
+pub fn is_file_in_use(path: &std::path::Path, error: &std::io::Error) -> bool {
+    const ERROR_ACCESS_DENIED: i32 = 5;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    match error.raw_os_error() {
+        Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION) => true,
+        Some(ERROR_ACCESS_DENIED) => std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .is_err_and(|e| e.raw_os_error() == Some(ERROR_SHARING_VIOLATION)),
+        _ => false,
+    }
+}
+
+/// Schedule a file or (by then empty) directory for deletion on the next reboot
+pub fn schedule_delete_on_reboot(path: &std::path::Path) -> Result<()> {
+    use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVEFILE_DELAY_UNTIL_REBOOT};
+
+    let path_wide = to_wide_string(path.to_string_lossy().as_ref());
+    unsafe {
+        MoveFileExW(PCWSTR(path_wide.as_ptr()), PCWSTR::null(), MOVEFILE_DELAY_UNTIL_REBOOT)
+    }
+    .context(format!("Failed to schedule '{}' for deletion on reboot", path.display()))?;
+
+    Ok(())
+}
+
+
+/// Create a named mutex used as a cross-process operation lock
+///
+/// Returns None if the mutex already exists (another process holds the lock). Only the
+/// mutex's existence is used, so the lock isn't tied to the thread that created it.
+pub fn create_operation_mutex(name: &str) -> Result<Option<isize>> {
+    use windows::Win32::Foundation::{CloseHandle, ERROR_ALREADY_EXISTS, GetLastError};
+    use windows::Win32::System::Threading::CreateMutexW;
+
+    let name_wide = to_wide_string(name);
+    unsafe {
+        let handle = CreateMutexW(None, false, PCWSTR(name_wide.as_ptr()))
+            .context("Failed to create operation lock")?;
+
+        if GetLastError() == ERROR_ALREADY_EXISTS {
+            CloseHandle(handle).ok();
+            return Ok(None);
+        }
+
+        Ok(Some(handle.0 as isize))
+    }
+}
+
+/// Close a mutex created by create_operation_mutex
+pub fn close_operation_mutex(handle: isize) {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+
+    unsafe {
+        CloseHandle(HANDLE(handle as *mut std::ffi::c_void)).ok();
+    }
+}
\ No newline at end of file